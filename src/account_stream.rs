@@ -0,0 +1,129 @@
+use std::{
+    collections::HashMap,
+    sync::{OnceLock, RwLock},
+};
+
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+
+/// Accounts kept fresh by a streaming subscription, consulted before any RPC fetch.
+static ACCOUNT_CACHE: OnceLock<AccountCache> = OnceLock::new();
+
+#[derive(Default)]
+pub struct AccountCache {
+    accounts: RwLock<HashMap<Pubkey, Account>>,
+}
+
+impl AccountCache {
+    #[cfg(feature = "grpc")]
+    pub fn insert(&self, address: Pubkey, account: Account) {
+        self.accounts.write().unwrap().insert(address, account);
+    }
+
+    /// Drops everything, so readers fall back to RPC while the stream is down.
+    #[cfg(feature = "grpc")]
+    pub fn clear(&self) {
+        self.accounts.write().unwrap().clear();
+    }
+
+    /// The cached accounts, only if every one of them is cached.
+    pub fn get_all(&self, addresses: &[Pubkey]) -> Option<Vec<Option<Account>>> {
+        let accounts = self.accounts.read().unwrap();
+        addresses
+            .iter()
+            .map(|address| accounts.get(address).cloned().map(Some))
+            .collect()
+    }
+}
+
+/// The process-wide cache, if a subscription was started.
+pub fn account_cache() -> Option<&'static AccountCache> {
+    ACCOUNT_CACHE.get()
+}
+
+#[cfg(feature = "grpc")]
+pub fn subscribe(endpoint: String, x_token: Option<String>, addresses: Vec<Pubkey>) {
+    use std::time::Duration;
+
+    use colored::*;
+
+    let cache = ACCOUNT_CACHE.get_or_init(AccountCache::default);
+    tokio::spawn(async move {
+        loop {
+            if let Err(err) =
+                grpc::stream_accounts(&endpoint, x_token.clone(), &addresses, cache).await
+            {
+                println!("{} Account stream: {}", "WARNING".bold().yellow(), err);
+            }
+
+            // Reconnect, serving reads from RPC in the meantime
+            cache.clear();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    });
+}
+
+#[cfg(feature = "grpc")]
+mod grpc {
+    use std::collections::HashMap;
+
+    use futures::StreamExt;
+    use solana_program::pubkey::Pubkey;
+    use solana_sdk::account::Account;
+    use yellowstone_grpc_client::GeyserGrpcClient;
+    use yellowstone_grpc_proto::prelude::{
+        subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+        SubscribeRequestFilterAccounts,
+    };
+
+    use super::AccountCache;
+
+    pub async fn stream_accounts(
+        endpoint: &str,
+        x_token: Option<String>,
+        addresses: &[Pubkey],
+        cache: &AccountCache,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut client = GeyserGrpcClient::build_from_shared(endpoint.to_string())?
+            .x_token(x_token)?
+            .connect()
+            .await?;
+        let request = SubscribeRequest {
+            accounts: HashMap::from([(
+                "ore".to_string(),
+                SubscribeRequestFilterAccounts {
+                    account: addresses.iter().map(|a| a.to_string()).collect(),
+                    ..Default::default()
+                },
+            )]),
+            commitment: Some(CommitmentLevel::Confirmed as i32),
+            ..Default::default()
+        };
+        let (_sink, mut stream) = client.subscribe_with_request(Some(request)).await?;
+        while let Some(update) = stream.next().await {
+            let Some(UpdateOneof::Account(update)) = update?.update_oneof else {
+                continue;
+            };
+            let Some(info) = update.account else {
+                continue;
+            };
+            let (Ok(address), Ok(owner)) = (
+                Pubkey::try_from(info.pubkey.as_slice()),
+                Pubkey::try_from(info.owner.as_slice()),
+            ) else {
+                continue;
+            };
+            cache.insert(
+                address,
+                Account {
+                    lamports: info.lamports,
+                    data: info.data,
+                    owner,
+                    executable: info.executable,
+                    rent_epoch: info.rent_epoch,
+                },
+            );
+        }
+        Err("stream closed".into())
+    }
+}
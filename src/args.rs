@@ -1,4 +1,5 @@
-use clap::{arg, Parser};
+use clap::{Parser, Subcommand, ValueEnum};
+use solana_sdk::commitment_config::CommitmentConfig;
 
 #[derive(Parser, Debug)]
 pub struct BalanceArgs {
@@ -16,54 +17,993 @@ pub struct BenchmarkArgs {
         long,
         short,
         value_name = "THREAD_COUNT",
-        help = "The number of threads to use during the benchmark",
+        help = "The number of threads to use during the benchmark (default 1, or every core with --scaling)"
+    )]
+    pub threads: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Share anonymized hardware specs and the result with the leaderboard",
+        default_value = "false",
+        requires = "leaderboard_url"
+    )]
+    pub share: bool,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Leaderboard endpoint to share results with"
+    )]
+    pub leaderboard_url: Option<String>,
+
+    #[arg(
+        long,
+        help = "Measure at 1, 2, 4, ... threads up to --threads (or every core) and find the knee",
+        default_value = "false",
+        conflicts_with = "share"
+    )]
+    pub scaling: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "How long to measure each thread count with --scaling",
+        default_value = "10"
+    )]
+    pub step_duration: i64,
+
+    #[arg(
+        long,
+        help = "Record the difficulty of every hash and report the distribution",
+        default_value = "false",
+        conflicts_with_all = ["share", "scaling"]
+    )]
+    pub distribution: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "How long to hash with --distribution",
+        default_value = "30"
+    )]
+    pub duration: u64,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "The mining window to project the best difficulty over",
+        default_value = "55"
+    )]
+    pub window: u64,
+
+    #[arg(
+        long,
+        help = "Measure latency to the RPC and each Jito block engine region instead of hashing",
+        default_value = "false",
+        conflicts_with_all = ["share", "scaling", "distribution"]
+    )]
+    pub network: bool,
+
+    #[arg(
+        long = "compare-rpc",
+        value_name = "URL",
+        help = "Another RPC to measure alongside the configured one with --network. May be repeated."
+    )]
+    pub compare_rpcs: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Round trips per endpoint with --network",
+        default_value = "5"
+    )]
+    pub samples: usize,
+}
+
+#[derive(Parser, Debug)]
+pub struct BussesArgs {
+    #[arg(
+        long,
+        help = "Keep refreshing the bus balances and the epoch reset countdown",
+        default_value = "false"
+    )]
+    pub watch: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "How often to refresh in watch mode",
+        default_value = "5"
+    )]
+    pub interval: u64,
+}
+
+/// How much of a proof's balance to claim.
+#[derive(Clone, Copy, Debug)]
+pub enum ClaimAmount {
+    Ore(f64),
+    Percent(f64),
+}
+
+#[derive(Parser, Debug)]
+pub struct ClaimArgs {
+    #[arg(
+        long,
+        value_name = "AMOUNT",
+        help = "The amount of rewards to claim, in ORE or as a percentage of the balance like 50%. Defaults to max.",
+        value_parser = crate::utils::parse_claim_amount
+    )]
+    pub amount: Option<ClaimAmount>,
+
+    #[arg(
+        long,
+        value_name = "ORE",
+        help = "Leave this much staked in each proof and claim the rest.",
+        conflicts_with = "amount"
+    )]
+    pub leave: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "PUBKEY",
+        visible_alias = "beneficiary",
+        help = "Wallet to receive claimed tokens, for every keypair with --all. Its token account is created if missing."
+    )]
+    pub to: Option<String>,
+
+    #[arg(
+        long,
+        help = "Claim from every keypair in the folder path.",
+        default_value = "false"
+    )]
+    pub all: bool,
+
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "Write a signed receipts file for the claims made with --all."
+    )]
+    pub receipts: Option<String>,
+
+    #[arg(
+        long,
+        help = "Swap the claimed ORE to SOL through Jupiter after claiming.",
+        default_value = "false",
+        conflicts_with_all = ["to", "sign_only"]
+    )]
+    pub auto_swap: bool,
+
+    #[arg(
+        long,
+        value_name = "BPS",
+        help = "Maximum slippage for --auto-swap, in basis points.",
+        default_value = "50"
+    )]
+    pub slippage_bps: u16,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Jupiter API to route swaps through.",
+        default_value = crate::swap::JUPITER_API_URL
+    )]
+    pub jupiter_url: String,
+
+    #[command(flatten)]
+    pub durable: DurableNonceArgs,
+}
+
+#[derive(Parser, Debug)]
+pub struct CleanupArgs {
+    #[arg(
+        long,
+        value_name = "AMOUNT",
+        help = "Also burn and close accounts holding at most this much ORE",
+        default_value = "0"
+    )]
+    pub dust: f64,
+}
+
+#[derive(Parser, Debug)]
+pub struct CloseArgs {}
+
+#[derive(Parser, Debug)]
+pub struct ConfigArgs {}
+
+#[derive(Parser, Debug)]
+pub struct CoordinatorArgs {
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "The address to accept workers on",
+        default_value = "0.0.0.0:7878"
+    )]
+    pub listen: String,
+
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        help = "Shared secret workers must present to join"
+    )]
+    pub token: Option<String>,
+
+    #[command(flatten)]
+    pub mine: MineArgs,
+}
+
+#[derive(Parser, Debug)]
+pub struct CostsArgs {
+    #[arg(
+        long,
+        value_enum,
+        help = "Where to read fees and tips from. The ledger also records tips",
+        default_value = "chain"
+    )]
+    pub source: ExportSource,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Maximum transactions to scan per wallet",
+        default_value = "1000"
+    )]
+    pub limit: usize,
+}
+
+#[cfg(feature = "admin")]
+#[derive(Parser, Debug)]
+pub struct PauseArgs {}
+
+#[cfg(feature = "admin")]
+#[derive(Parser, Debug)]
+pub struct InitializeArgs {}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum BusStrategy {
+    /// The bus holding the most rewards
+    Highest,
+    /// A random bus that still holds rewards
+    Random,
+    /// A random one of the three richest busses
+    Top3,
+    /// Each funded bus in turn, one per pass
+    RoundRobin,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum SendMode {
+    /// One Jito bundle for all wallets
+    Jito,
+    /// A priority-fee transaction per wallet through the RPC, or --sender if given
+    Rpc,
+    /// Both at once, keeping whichever lands first
+    Both,
+    /// A priority-fee transaction per wallet sent over QUIC straight to the leaders
+    Tpu,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Commitment {
+    /// Seen by the node, may still be rolled back
+    Processed,
+    /// Voted on by a supermajority of the cluster
+    Confirmed,
+    /// Rooted, can no longer be rolled back
+    Finalized,
+}
+
+impl Commitment {
+    pub fn config(self) -> CommitmentConfig {
+        match self {
+            Commitment::Processed => CommitmentConfig::processed(),
+            Commitment::Confirmed => CommitmentConfig::confirmed(),
+            Commitment::Finalized => CommitmentConfig::finalized(),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum ExportSource {
+    /// Scan each wallet's transaction history on chain
+    Chain,
+    /// Read the --db ledger
+    Ledger,
+}
+
+#[derive(Parser, Debug, Default)]
+pub struct DurableNonceArgs {
+    #[arg(
+        long,
+        value_name = "NONCE_ADDRESS",
+        help = "Build against this durable nonce account instead of a recent blockhash. The signer must be its authority."
+    )]
+    pub nonce: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "BLOCKHASH",
+        help = "The nonce's current value, from `ore nonce show`. Needed to sign without an RPC.",
+        requires = "nonce"
+    )]
+    pub nonce_blockhash: Option<String>,
+
+    #[arg(
+        long,
+        help = "Print the signed transaction instead of sending it, for `ore nonce broadcast`.",
+        default_value = "false",
+        requires = "nonce"
+    )]
+    pub sign_only: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct ExportArgs {
+    #[arg(long, value_enum, help = "Output format", default_value = "csv")]
+    pub format: ExportFormat,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Where to read earnings from",
+        default_value = "chain"
+    )]
+    pub source: ExportSource,
+
+    #[arg(
+        long,
+        short,
+        value_name = "FILEPATH",
+        help = "File to write to. Defaults to stdout"
+    )]
+    pub output: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Maximum transactions to scan per wallet",
+        default_value = "1000"
+    )]
+    pub limit: usize,
+}
+
+#[derive(Parser, Debug)]
+pub struct GenerateUnitArgs {
+    #[arg(
+        long,
+        value_name = "USER",
+        help = "User to run the service as. Defaults to the current user."
+    )]
+    pub user: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "TEXT",
+        help = "Description of the service",
+        default_value = "ORE miner"
+    )]
+    pub description: String,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Restart the miner when a pass makes no progress for this long",
+        default_value = "300"
+    )]
+    pub watchdog_sec: u64,
+
+    #[arg(
+        last = true,
+        value_name = "MINE_ARGS",
+        help = "Arguments for `ore mine`, after --"
+    )]
+    pub mine_args: Vec<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct HistoryArgs {
+    #[arg(
+        long,
+        value_name = "WALLET_ADDRESS",
+        help = "Only include passes this wallet took part in"
+    )]
+    pub wallet: Option<String>,
+
+    #[arg(long, value_name = "HOURS", help = "Only include the last N hours")]
+    pub hours: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of recent passes to list",
+        default_value = "20"
+    )]
+    pub limit: u64,
+
+    #[arg(
+        long,
+        help = "Report lifetime earnings from each wallet's chain history instead of the ledger",
+        default_value = "false"
+    )]
+    pub lifetime: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct LookupTableArgs {
+    #[command(subcommand)]
+    pub command: LookupTableCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LookupTableCommand {
+    #[command(about = "Create a lookup table for the fleet, owned by the fee payer")]
+    Create(LookupTableCreateArgs),
+
+    #[command(about = "Add any fleet accounts the lookup table is missing")]
+    Extend(LookupTableExtendArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct LookupTableCreateArgs {}
+
+#[derive(Parser, Debug)]
+pub struct LookupTableExtendArgs {
+    #[arg(
+        value_name = "TABLE_ADDRESS",
+        help = "The lookup table to extend. Defaults to --lookup-table"
+    )]
+    pub address: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct MineArgs {
+    // #[cfg(not(feature = "gpu"))]
+    #[arg(
+        long,
+        short,
+        value_name = "THREAD_COUNT",
+        help = "The number of CPU threads to allocate to mining",
         default_value = "1"
     )]
-    pub threads: u64,
+    pub threads: u64,
+
+    #[arg(
+        long,
+        help = "Pin each solver thread to its own physical core, skipping SMT siblings",
+        default_value = "false"
+    )]
+    pub pin_cores: bool,
+
+    #[arg(
+        long,
+        value_name = "CORE",
+        help = "First core to pin solver threads to, leaving the ones before it to the runtime",
+        default_value = "0",
+        requires = "pin_cores"
+    )]
+    pub core_offset: usize,
+
+    #[arg(
+        long,
+        help = "Balance solver threads across NUMA nodes and keep each thread's memory on its node",
+        default_value = "false",
+        conflicts_with = "pin_cores"
+    )]
+    pub numa: bool,
+
+    #[arg(
+        long,
+        visible_alias = "low-priority",
+        help = "Run solver threads at the lowest OS priority so the machine stays responsive",
+        default_value = "false"
+    )]
+    pub nice: bool,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Share of each second spent hashing, resting for the remainder",
+        default_value = "100",
+        value_parser = clap::value_parser!(u8).range(1..=100)
+    )]
+    pub duty_cycle: u8,
+
+    #[arg(
+        long,
+        value_name = "CELSIUS",
+        help = "CPU temperature to throttle solver threads above, resuming as it cools"
+    )]
+    pub max_temp: Option<f64>,
+
+    #[arg(
+        long,
+        short,
+        value_name = "SECONDS",
+        help = "The number seconds before the deadline to stop mining and start submitting",
+        default_value = "5"
+    )]
+    pub buffer_time: u64,
+
+    #[arg(
+        long,
+        help = "Size the buffer from recently observed submit and landing latency",
+        default_value = "false"
+    )]
+    pub adaptive_buffer: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Smallest buffer --adaptive-buffer may pick",
+        default_value = "1"
+    )]
+    pub min_buffer_time: u64,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Largest buffer --adaptive-buffer may pick",
+        default_value = "20"
+    )]
+    pub max_buffer_time: u64,
+
+    #[arg(
+        long,
+        help = "Hold each bundle until just before a Jito-enabled leader's slots",
+        default_value = "false"
+    )]
+    pub align_leaders: bool,
+
+    #[arg(
+        long,
+        value_name = "MILLISECONDS",
+        help = "Longest --align-leaders may hold a bundle",
+        default_value = "2000"
+    )]
+    pub max_leader_wait: u64,
+
+    #[arg(
+        long,
+        short,
+        value_name = "LAMPORTS",
+        help = "Jito Tip Amount",
+        default_value = "10_000"
+    )]
+    pub jito_tip: u64,
+
+    #[arg(
+        long,
+        short,
+        value_name = "MINIMUM_DIFFICULTY",
+        help = "Minimum difficulty to mine",
+        default_value = "0"
+    )]
+    pub min_difficulty: u32,
+
+    #[arg(
+        long,
+        value_name = "DIFFICULTY",
+        help = "Hash each wallet until the window closes, but stop as soon as a solution reaches this difficulty"
+    )]
+    pub target_difficulty: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "DIFFICULTY",
+        help = "Difficulty worth waiting for. Wallets below it keep hashing past the cutoff for a bounded time"
+    )]
+    pub desired_difficulty: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Extra hashing per difficulty a wallet is short of --desired-difficulty",
+        default_value = "2",
+        requires = "desired_difficulty"
+    )]
+    pub extend_seconds_per_missing_difficulty: u64,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Most extra hashing per pass, across all wallets",
+        default_value = "10",
+        requires = "desired_difficulty"
+    )]
+    pub max_extend_seconds: u64,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "Keep hashing, or skip the pass, unless the expected reward beats the tip and fees by this much"
+    )]
+    pub min_profit: Option<f64>,
+
+    #[arg(
+        long,
+        short,
+        value_name = "JITO_URL",
+        help = "JITO URL",
+        default_value = "https://ny.mainnet.block-engine.jito.wtf/api/v1/bundles"
+    )]
+    pub jito_url: String,
+
+    #[arg(
+        long,
+        value_name = "UUID",
+        help = "Jito block engine UUID to authenticate bundles with, for a higher rate limit"
+    )]
+    pub jito_uuid: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Most instructions to pack into each bundle transaction. Defaults to as many as fit in a packet"
+    )]
+    pub ixs_per_tx: Option<usize>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "How to pick the bus to submit to",
+        default_value = "highest"
+    )]
+    pub bus_strategy: BusStrategy,
+
+    #[arg(
+        long,
+        help = "Give each wallet in the bundle its own bus, in --bus-strategy order",
+        default_value = "false"
+    )]
+    pub bus_per_wallet: bool,
+
+    #[arg(
+        long,
+        help = "Simulate each wallet's mine before bundling it, leaving out wallets that would fail the bundle",
+        default_value = "false"
+    )]
+    pub simulate_wallets: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Route to submit solutions through",
+        default_value = "jito"
+    )]
+    pub send_mode: SendMode,
+
+    #[arg(
+        long,
+        value_name = "PASSES",
+        help = "Stop mining and print the session summary after this many passes"
+    )]
+    pub max_passes: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        help = "Stop mining and print the session summary after this long, e.g. 90m or 4h",
+        value_parser = crate::utils::parse_duration
+    )]
+    pub duration: Option<std::time::Duration>,
+
+    #[arg(
+        long,
+        value_name = "PASSES",
+        help = "Print a bundle packing efficiency report every this many passes"
+    )]
+    pub packing_report: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        help = "Serve an HTTP control API on this port for status, pause/resume, tips, reloads and claims"
+    )]
+    pub control_port: Option<u16>,
+
+    #[arg(
+        long,
+        value_name = "HOST",
+        help = "The interface to serve the control API on",
+        default_value = "127.0.0.1",
+        requires = "control_port"
+    )]
+    pub control_host: String,
+
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        help = "Bearer token control API requests must present",
+        requires = "control_port"
+    )]
+    pub control_token: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "WALLET_ADDRESS",
+        help = "Wallet whose token account receives rewards claimed while mining, instead of each keypair's own"
+    )]
+    pub beneficiary: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Abort a pass and reconnect to the RPC after this long without progress. 0 disables.",
+        default_value = "120"
+    )]
+    pub watchdog: u64,
+
+    #[arg(
+        long,
+        value_name = "LAMPORTS",
+        help = "Hold off submitting while the priority fee for a mine transaction, or through Jito the tip floor or --jito-tip, is above this"
+    )]
+    pub fee_ceiling: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Also stop hashing while fees are above --fee-ceiling",
+        default_value = "false",
+        requires = "fee_ceiling"
+    )]
+    pub fee_ceiling_pause_hashing: bool,
+
+    #[arg(
+        long,
+        value_name = "GROUPS",
+        help = "Split the wallets into this many groups that submit separately, each paying its own tip",
+        default_value = "1"
+    )]
+    pub stagger_groups: usize,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "How far apart staggered groups submit",
+        default_value = "3"
+    )]
+    pub stagger_seconds: u64,
+
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "Save each wallet's best solution here, and submit it after a restart if its challenge is still current"
+    )]
+    pub solution_file: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Alert through the log and notifications when fewer than this share of recent passes landed",
+        value_parser = clap::value_parser!(u8).range(1..=100)
+    )]
+    pub min_landing_rate: Option<u8>,
+
+    #[arg(
+        long,
+        value_name = "PASSES",
+        help = "How many recent passes --min-landing-rate is measured over",
+        default_value = "20",
+        requires = "min_landing_rate"
+    )]
+    pub landing_window: usize,
+
+    #[arg(
+        long,
+        help = "Keep mining in the background after the terminal closes, see `ore status` and `ore stop`",
+        default_value = "false"
+    )]
+    pub daemon: bool,
+
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "Write the process ID here while mining. Defaults to ore.pid with --daemon."
+    )]
+    pub pid_file: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "Where a --daemon miner writes its output",
+        default_value = "ore.log",
+        requires = "daemon"
+    )]
+    pub log_file: String,
+
+    #[arg(
+        long,
+        help = "Tell systemd when mining is up and feed its watchdog while passes make progress",
+        default_value = "false"
+    )]
+    pub systemd: bool,
+
+    #[arg(
+        long,
+        value_name = "DIRECTORY",
+        help = "Append each wallet's proof fetches, solutions, submissions and results to <pubkey>.log here"
+    )]
+    pub log_dir: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "MEGABYTES",
+        help = "Rotate a wallet's log once it reaches this size, keeping the last 3",
+        default_value = "10",
+        requires = "log_dir"
+    )]
+    pub log_max_mb: u64,
+}
+
+#[derive(Parser, Debug)]
+pub struct NonceArgs {
+    #[command(subcommand)]
+    pub command: NonceCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum NonceCommand {
+    #[command(about = "Create a durable nonce account with the signer as its authority")]
+    Create(NonceCreateArgs),
+
+    #[command(about = "Show a nonce account's authority and current value")]
+    Show(NonceShowArgs),
+
+    #[command(about = "Withdraw a nonce account's lamports and close it")]
+    Close(NonceCloseArgs),
+
+    #[command(about = "Send a transaction signed with --sign-only")]
+    Broadcast(NonceBroadcastArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct NonceCreateArgs {
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "Keypair to use as the nonce account address. Defaults to a new one."
+    )]
+    pub nonce_keypair: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct NonceShowArgs {
+    #[arg(value_name = "NONCE_ADDRESS", help = "The nonce account to show")]
+    pub address: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct NonceCloseArgs {
+    #[arg(value_name = "NONCE_ADDRESS", help = "The nonce account to close")]
+    pub address: String,
+
+    #[arg(
+        long,
+        value_name = "WALLET_ADDRESS",
+        help = "Wallet to receive the lamports. Defaults to the signer."
+    )]
+    pub to: Option<String>,
 }
 
 #[derive(Parser, Debug)]
-pub struct BussesArgs {}
+pub struct NonceBroadcastArgs {
+    #[arg(
+        value_name = "TRANSACTION",
+        help = "The base64 transaction printed by --sign-only, or a file containing it"
+    )]
+    pub transaction: String,
+}
 
 #[derive(Parser, Debug)]
-pub struct ClaimArgs {
+pub struct OpenArgs {
     #[arg(
         long,
-        value_name = "AMOUNT",
-        help = "The amount of rewards to claim. Defaults to max."
+        value_name = "FILEPATH",
+        help = "Keypair to own the proof. Defaults to the configured keypair."
     )]
-    pub amount: Option<f64>,
+    pub authority: Option<String>,
 
     #[arg(
         long,
-        value_name = "WALLET_ADDRESS",
-        help = "Wallet to receive claimed tokens."
+        value_name = "FILEPATH",
+        help = "Keypair to pay the rent and fees. Defaults to the authority."
     )]
-    pub to: Option<String>,
+    pub payer: Option<String>,
 }
 
 #[derive(Parser, Debug)]
-pub struct CloseArgs {}
+pub struct ProfitArgs {
+    #[arg(
+        long,
+        short,
+        value_name = "THREAD_COUNT",
+        help = "The number of threads to benchmark with",
+        default_value = "1"
+    )]
+    pub threads: u64,
 
-#[derive(Parser, Debug)]
-pub struct ConfigArgs {}
+    #[arg(
+        long,
+        value_name = "HASHES_PER_SEC",
+        help = "Use this hashrate instead of running a benchmark"
+    )]
+    pub hashrate: Option<u64>,
+
+    #[arg(
+        long,
+        short,
+        value_name = "SECONDS",
+        help = "The buffer time you mine with",
+        default_value = "5"
+    )]
+    pub buffer_time: u64,
+
+    #[arg(
+        long,
+        short,
+        value_name = "LAMPORTS",
+        help = "The Jito tip you mine with",
+        default_value = "10000"
+    )]
+    pub jito_tip: u64,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of keypairs mining in parallel. Defaults to the folder size."
+    )]
+    pub wallets: Option<u64>,
+}
 
-#[cfg(feature = "admin")]
 #[derive(Parser, Debug)]
-pub struct PauseArgs {}
+pub struct ProofArgs {
+    #[arg(
+        value_name = "ADDRESS",
+        help = "The authority whose proof to fetch. Defaults to your signer."
+    )]
+    pub address: Option<String>,
+}
 
-#[cfg(feature = "admin")]
 #[derive(Parser, Debug)]
-pub struct InitializeArgs {}
+pub struct ReceiptsArgs {
+    #[arg(value_name = "FILEPATH", help = "The receipts file to verify")]
+    pub file: String,
+
+    #[arg(
+        long,
+        value_name = "WALLET_ADDRESS",
+        help = "Only verify receipts for this wallet"
+    )]
+    pub wallet: Option<String>,
+}
 
 #[derive(Parser, Debug)]
-pub struct MineArgs {
-    // #[cfg(not(feature = "gpu"))]
+pub struct RewardsArgs {
+    #[arg(
+        long,
+        value_name = "HASHES_PER_SEC",
+        help = "Estimate expected earnings at this hashrate"
+    )]
+    pub hashrate: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Benchmark to measure the hashrate to estimate earnings at",
+        default_value = "false",
+        conflicts_with = "hashrate"
+    )]
+    pub benchmark: bool,
+
     #[arg(
         long,
         short,
         value_name = "THREAD_COUNT",
-        help = "The number of CPU threads to allocate to mining",
+        help = "The number of threads to benchmark with",
         default_value = "1"
     )]
     pub threads: u64,
@@ -72,41 +1012,68 @@ pub struct MineArgs {
         long,
         short,
         value_name = "SECONDS",
-        help = "The number seconds before the deadline to stop mining and start submitting",
+        help = "The buffer time you mine with",
         default_value = "5"
     )]
     pub buffer_time: u64,
+}
 
+#[derive(Parser, Debug)]
+pub struct RotateAuthorityArgs {
     #[arg(
         long,
-        short,
-        value_name = "LAMPORTS",
-        help = "Jito Tip Amount",
-        default_value = "10_000"
+        value_name = "FILEPATH",
+        help = "Keypair to take over the proof. Defaults to a new one saved next to the current keypair.",
+        conflicts_with = "all"
     )]
-    pub jito_tip: u64,
+    pub new_authority: Option<String>,
 
     #[arg(
         long,
-        short,
-        value_name = "MINIMUM_DIFFICULTY",
-        help = "Minimum difficulty to mine",
-        default_value = "0"
+        help = "Rotate every keypair in the folder path, with no overlap.",
+        default_value = "false"
     )]
-    pub min_difficulty: u32,
+    pub all: bool,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "SOL to move from each old key to its replacement",
+        default_value = "0.01"
+    )]
+    pub fund: f64,
+
+    #[arg(
+        long,
+        value_name = "DIRECTORY",
+        help = "Where to move retired keypairs with --all. Defaults to <folder-path>-archive",
+        requires = "all"
+    )]
+    pub archive_dir: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "LAMPORTS",
+        help = "Jito tip for each bundle migrating a proof",
+        default_value = "10000"
+    )]
+    pub jito_tip: u64,
 
     #[arg(
         long,
-        short,
         value_name = "JITO_URL",
-        help = "JITO URL",
+        help = "Jito block engine to send migration bundles to",
         default_value = "https://ny.mainnet.block-engine.jito.wtf/api/v1/bundles"
     )]
     pub jito_url: String,
-}
 
-#[derive(Parser, Debug)]
-pub struct RewardsArgs {}
+    #[arg(
+        long,
+        value_name = "UUID",
+        help = "Jito block engine UUID to authenticate bundles with"
+    )]
+    pub jito_uuid: Option<String>,
+}
 
 #[derive(Parser, Debug)]
 pub struct StakeArgs {
@@ -123,14 +1090,158 @@ pub struct StakeArgs {
         help = "Token account to send Ore from."
     )]
     pub sender: Option<String>,
+
+    #[arg(
+        long,
+        help = "Stake from every keypair in the folder path, in Jito bundles. --amount is then per wallet.",
+        default_value = "false",
+        conflicts_with_all = ["sender", "sign_only"]
+    )]
+    pub all: bool,
+
+    #[arg(
+        long,
+        value_name = "ORE",
+        help = "With --all, keep this much ORE in each wallet and stake the rest.",
+        requires = "all",
+        conflicts_with = "amount"
+    )]
+    pub floor: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "LAMPORTS",
+        help = "Jito tip for each bundle sent with --all",
+        default_value = "10000"
+    )]
+    pub jito_tip: u64,
+
+    #[arg(
+        long,
+        value_name = "JITO_URL",
+        help = "Jito block engine to send --all bundles to",
+        default_value = "https://ny.mainnet.block-engine.jito.wtf/api/v1/bundles"
+    )]
+    pub jito_url: String,
+
+    #[arg(
+        long,
+        value_name = "UUID",
+        help = "Jito block engine UUID to authenticate bundles with"
+    )]
+    pub jito_uuid: Option<String>,
+
+    #[command(flatten)]
+    pub durable: DurableNonceArgs,
 }
 
+#[derive(Parser, Debug)]
+pub struct StakeInfoArgs {}
+
 #[cfg(feature = "admin")]
 #[derive(Parser, Debug)]
 pub struct UpdateAdminArgs {
     pub new_admin: String,
 }
 
+#[derive(Parser, Debug)]
+pub struct StatusArgs {
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "PID file of the miner to check on",
+        default_value = crate::daemon::DEFAULT_PID_FILE
+    )]
+    pub pid_file: String,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        help = "Also fetch the miner's status from its control API on this port"
+    )]
+    pub control_port: Option<u16>,
+
+    #[arg(
+        long,
+        value_name = "HOST",
+        help = "The interface the control API is served on",
+        default_value = "127.0.0.1",
+        requires = "control_port"
+    )]
+    pub control_host: String,
+
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        help = "Bearer token the control API expects",
+        requires = "control_port"
+    )]
+    pub control_token: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct StopArgs {
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "PID file of the miner to stop",
+        default_value = crate::daemon::DEFAULT_PID_FILE
+    )]
+    pub pid_file: String,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "How long to let the current pass finish before exiting the miner anyway",
+        default_value = "120"
+    )]
+    pub timeout: u64,
+
+    #[arg(
+        long,
+        help = "Exit right away instead of finishing the current pass",
+        default_value = "false"
+    )]
+    pub force: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct SwapArgs {
+    #[arg(
+        long,
+        value_name = "AMOUNT",
+        help = "The amount of ORE to swap. Defaults to the whole token balance."
+    )]
+    pub amount: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "BPS",
+        help = "Maximum slippage, in basis points",
+        default_value = "50"
+    )]
+    pub slippage_bps: u16,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Jupiter API to route the swap through",
+        default_value = crate::swap::JUPITER_API_URL
+    )]
+    pub jupiter_url: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct TipStatsArgs {
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        help = "How far back to look at landed bundles",
+        default_value = "10"
+    )]
+    pub minutes: i64,
+}
+
 #[derive(Parser, Debug)]
 pub struct UpgradeArgs {
     #[arg(
@@ -139,4 +1250,114 @@ pub struct UpgradeArgs {
         help = "The amount of Ore to upgrade from v1 to v2. Defaults to max."
     )]
     pub amount: Option<f64>,
+
+    #[command(flatten)]
+    pub durable: DurableNonceArgs,
+}
+
+#[derive(Parser, Debug)]
+pub struct WorkerArgs {
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "The coordinator to take work from, as HOST:PORT"
+    )]
+    pub coordinator: String,
+
+    #[arg(
+        long,
+        short,
+        value_name = "THREAD_COUNT",
+        help = "The number of CPU threads to allocate to mining",
+        default_value = "1"
+    )]
+    pub threads: u64,
+
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        help = "Shared secret the coordinator expects"
+    )]
+    pub token: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct WalletsArgs {
+    #[command(subcommand)]
+    pub command: WalletsCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WalletsCommand {
+    #[command(about = "Rotate the folder keypairs to freshly generated ones")]
+    Rotate(RotateArgs),
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum RotateStep {
+    /// Generate replacement keys, fund them and open their proofs
+    Start,
+    /// Print the rotation checklist
+    Status,
+    /// Migrate balances to the replacement keys and archive the old ones
+    Finish,
+}
+
+#[derive(Parser, Debug)]
+pub struct RotateArgs {
+    #[arg(value_enum, help = "The rotation step to run")]
+    pub step: RotateStep,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "SOL to move from each old key to its replacement on start",
+        default_value = "0.01"
+    )]
+    pub fund: f64,
+
+    #[arg(
+        long,
+        value_name = "HOURS",
+        help = "How long both key sets mine side by side before finish is allowed",
+        default_value = "24"
+    )]
+    pub overlap_hours: u64,
+
+    #[arg(
+        long,
+        value_name = "DIRECTORY",
+        help = "Where to move retired keypairs. Defaults to <folder-path>-archive"
+    )]
+    pub archive_dir: Option<String>,
+
+    #[arg(
+        long,
+        help = "Finish before the overlap window ends",
+        default_value = "false"
+    )]
+    pub force: bool,
+
+    #[arg(
+        long,
+        value_name = "LAMPORTS",
+        help = "Jito tip for each bundle migrating a proof",
+        default_value = "10000"
+    )]
+    pub jito_tip: u64,
+
+    #[arg(
+        long,
+        value_name = "JITO_URL",
+        help = "Jito block engine to send migration bundles to",
+        default_value = "https://ny.mainnet.block-engine.jito.wtf/api/v1/bundles"
+    )]
+    pub jito_url: String,
+
+    #[arg(
+        long,
+        value_name = "UUID",
+        help = "Jito block engine UUID to authenticate bundles with"
+    )]
+    pub jito_uuid: Option<String>,
 }
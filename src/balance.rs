@@ -1,11 +1,13 @@
 use std::str::FromStr;
 
-use solana_program::pubkey::Pubkey;
+use serde_json::json;
+use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
 use solana_sdk::signature::Signer;
 
 use crate::{
     args::BalanceArgs,
-    utils::{amount_u64_to_string, get_proof_with_authority},
+    price::{ore_usd, sol_usd},
+    utils::{amount_u64_to_f64, amount_u64_to_string, get_proof_with_authority, print_json},
     Miner,
 };
 
@@ -22,24 +24,49 @@ impl Miner {
         } else {
             signer.pubkey()
         };
-        let proof = get_proof_with_authority(&self.rpc_client, address).await;
+        let proof = get_proof_with_authority(&self.rpc_client(), address).await;
         let token_account_address = spl_associated_token_account::get_associated_token_address(
             &address,
             &ore_api::consts::MINT_ADDRESS,
         );
-        let token_balance = if let Ok(Some(token_account)) = self
-            .rpc_client
+        let token_account = self
+            .rpc_client()
             .get_token_account(&token_account_address)
             .await
-        {
-            token_account.token_amount.ui_amount_string
-        } else {
-            "0".to_string()
-        };
+            .ok()
+            .flatten();
+        if self.json {
+            let sol_balance = self.rpc_client().get_balance(&address).await.unwrap_or(0);
+            print_json(&json!({
+                "address": address.to_string(),
+                "balance": token_account
+                    .map(|account| account.token_amount.amount)
+                    .unwrap_or_else(|| "0".to_string())
+                    .parse::<u64>()
+                    .unwrap_or(0),
+                "stake": proof.balance,
+                "sol": sol_balance,
+            }));
+            return;
+        }
+        let token_balance = token_account
+            .map(|account| account.token_amount.ui_amount_string)
+            .unwrap_or_else(|| "0".to_string());
+        let prices = self.prices().await;
         println!(
-            "Balance: {} ORE\nStake: {} ORE",
+            "Balance: {} ORE{}\nStake: {} ORE{}",
             token_balance,
-            amount_u64_to_string(proof.balance)
-        )
+            ore_usd(prices, token_balance.parse().unwrap_or(0.0)),
+            amount_u64_to_string(proof.balance),
+            ore_usd(prices, amount_u64_to_f64(proof.balance)),
+        );
+        if prices.is_some() {
+            let sol_balance = self.rpc_client().get_balance(&address).await.unwrap_or(0);
+            println!(
+                "SOL: {}{}",
+                lamports_to_sol(sol_balance),
+                sol_usd(prices, lamports_to_sol(sol_balance))
+            );
+        }
     }
 }
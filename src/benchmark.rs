@@ -1,62 +1,367 @@
-use std::{sync::Arc, time::Instant};
+use std::time::{Duration, Instant};
 
+use colored::*;
+use drillx::equix::SolverMemory;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use solana_rpc_client::spinner;
 
-use crate::{args::BenchmarkArgs, Miner};
+use crate::{args::BenchmarkArgs, rewards::p_at_least, utils::print_json, Miner};
 
-const TEST_DURATION: i64 = 30;
+pub const TEST_DURATION: i64 = 30;
+
+/// Past the knee, each added thread brings less than this share of a single thread's rate.
+const KNEE_EFFICIENCY: f64 = 0.5;
+
+/// Percentiles of single-hash difficulty to report.
+const HASH_PERCENTILES: [f64; 5] = [50.0, 90.0, 99.0, 99.9, 99.99];
+
+/// Percentiles of the best difficulty per mining window to report.
+const WINDOW_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+
+/// Width of the longest histogram bar.
+const HISTOGRAM_WIDTH: f64 = 40.0;
+
+/// What gets shared with the leaderboard. Nothing here identifies the user or their wallets.
+#[derive(Serialize)]
+struct BenchmarkReport {
+    cpu_model: String,
+    physical_cores: usize,
+    logical_cores: usize,
+    os: &'static str,
+    arch: &'static str,
+    threads: u64,
+    hashrate: u64,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+struct ScalingPoint {
+    threads: u64,
+    hashrate: u64,
+    speedup: f64,
+    efficiency: f64,
+}
+
+#[derive(Serialize)]
+struct ScalingReport {
+    points: Vec<ScalingPoint>,
+    knee: u64,
+}
+
+#[derive(Deserialize)]
+struct LeaderboardResponse {
+    percentile: f64,
+    similar_rigs: u64,
+    median_hashrate: f64,
+}
 
 impl Miner {
     pub async fn benchmark(&self, args: BenchmarkArgs) {
+        if args.scaling {
+            self.benchmark_scaling(args).await;
+            return;
+        }
+        if args.distribution {
+            self.benchmark_distribution(args).await;
+            return;
+        }
+        if args.network {
+            self.benchmark_network(args).await;
+            return;
+        }
+
         // Check num threads
-        self.check_num_cores(args.threads);
+        let threads = args.threads.unwrap_or(1);
+        self.check_num_cores(threads);
 
-        // Dispatch job to each thread
-        let challenge = [0; 32];
-        let progress_bar = Arc::new(spinner::new_progress_bar());
+        // Measure
+        let progress_bar = spinner::new_progress_bar();
         progress_bar.set_message(format!(
             "Benchmarking. This will take {} sec...",
             TEST_DURATION
         ));
-        let handles: Vec<_> = (0..args.threads)
-            .map(|i| {
-                std::thread::spawn({
-                    move || {
-                        let timer = Instant::now();
-                        let first_nonce = u64::MAX.saturating_div(args.threads).saturating_mul(i);
-                        let mut nonce = first_nonce;
-                        loop {
-                            // Create hash
-                            let _hx = drillx::hash(&challenge, &nonce.to_le_bytes());
-
-                            // Increment nonce
-                            nonce += 1;
-
-                            // Exit if time has elapsed
-                            if (timer.elapsed().as_secs() as i64).ge(&TEST_DURATION) {
-                                break;
-                            }
+        let hashrate = measure_hashrate(threads, TEST_DURATION);
+        progress_bar.finish_with_message(format!("Hashpower: {} H/sec", hashrate));
+
+        // Compare against similar hardware
+        if args.share {
+            self.share_benchmark(args.leaderboard_url.unwrap(), threads, hashrate)
+                .await;
+        }
+    }
+
+    async fn benchmark_scaling(&self, args: BenchmarkArgs) {
+        let max_threads = args.threads.unwrap_or(num_cpus::get() as u64);
+        self.check_num_cores(max_threads);
+        let mut steps: Vec<u64> = std::iter::successors(Some(1u64), |n| Some(n * 2))
+            .take_while(|n| n.lt(&max_threads))
+            .collect();
+        steps.push(max_threads);
+
+        // Measure each step
+        let progress_bar = spinner::new_progress_bar();
+        let mut points: Vec<ScalingPoint> = vec![];
+        for (i, threads) in steps.iter().enumerate() {
+            progress_bar.set_message(format!(
+                "Benchmarking {} threads ({}/{}, {} sec each)...",
+                threads,
+                i + 1,
+                steps.len(),
+                args.step_duration
+            ));
+            let hashrate = measure_hashrate(*threads, args.step_duration);
+            let base = points
+                .first()
+                .map_or(hashrate, |point| point.hashrate)
+                .max(1) as f64;
+            points.push(ScalingPoint {
+                threads: *threads,
+                hashrate,
+                speedup: hashrate as f64 / base,
+                efficiency: hashrate as f64 / (base * *threads as f64),
+            });
+        }
+        progress_bar.finish_and_clear();
+        let knee = knee(&points);
+
+        if self.json {
+            let report = ScalingReport { points, knee };
+            print_json(&json!(report));
+            return;
+        }
+        println!(
+            "{:>8} {:>12} {:>8} {:>10}",
+            "Threads".bold(),
+            "H/sec".bold(),
+            "Speedup".bold(),
+            "Efficiency".bold()
+        );
+        for point in points.iter() {
+            let line = format!(
+                "{:>8} {:>12} {:>7.2}x {:>9.0}%",
+                point.threads,
+                point.hashrate,
+                point.speedup,
+                point.efficiency * 100.0
+            );
+            match point.threads.eq(&knee) {
+                true => println!("{}", line.green()),
+                false => println!("{}", line),
+            }
+        }
+        println!(
+            "\n{}: {} threads. Beyond this, each added thread gains less than {:.0}% of a single thread.",
+            "Knee".bold(),
+            knee,
+            KNEE_EFFICIENCY * 100.0
+        );
+    }
+
+    async fn benchmark_distribution(&self, args: BenchmarkArgs) {
+        let threads = args.threads.unwrap_or(1);
+        self.check_num_cores(threads);
+        let progress_bar = spinner::new_progress_bar();
+        progress_bar.set_message(format!(
+            "Recording difficulties. This will take {} sec...",
+            args.duration
+        ));
+        let counts = measure_difficulties(threads, Duration::from_secs(args.duration));
+        let hashes: u64 = counts.iter().sum();
+        progress_bar.finish_and_clear();
+        if hashes.eq(&0) {
+            println!("{} No hashes recorded", "ERROR".bold().red());
+            return;
+        }
+
+        // Histogram of what was found
+        let hashrate = hashes.saturating_div(args.duration.max(1));
+        println!(
+            "{} hashes in {} sec ({} H/sec)\n",
+            hashes, args.duration, hashrate
+        );
+        let widest = counts.iter().max().copied().unwrap_or(1).max(1) as f64;
+        let highest = counts.iter().rposition(|count| count.gt(&0)).unwrap_or(0);
+        for (difficulty, count) in counts.iter().enumerate().take(highest + 1) {
+            // Log scale, since each difficulty is half as common as the one before
+            let width = ((*count as f64).ln_1p() / widest.ln_1p() * HISTOGRAM_WIDTH) as usize;
+            println!(
+                "{:>4} {:>12} {}",
+                difficulty,
+                count,
+                "#".repeat(width).cyan()
+            );
+        }
+
+        // Percentiles of single hashes
+        println!("\n{}", "Per hash".bold());
+        for p in HASH_PERCENTILES {
+            let rank = (hashes as f64 * p / 100.0).ceil() as u64;
+            let mut seen = 0;
+            let difficulty = counts
+                .iter()
+                .position(|count| {
+                    seen += count;
+                    seen.ge(&rank)
+                })
+                .unwrap_or(highest);
+            println!("  {:>7}: {}", format!("p{}", p), difficulty);
+        }
+        println!("  {:>7}: {}", "max", highest);
+
+        // What that means for the best hash of a mining window
+        let window_hashes = hashrate as f64 * args.window as f64;
+        let expected: f64 = (1..64).map(|d| p_at_least(window_hashes, d)).sum();
+        println!(
+            "\n{} ({} sec, ~{:.0} hashes)",
+            "Best per mining window".bold(),
+            args.window,
+            window_hashes
+        );
+        for p in WINDOW_PERCENTILES {
+            let difficulty = (1..64u32)
+                .take_while(|d| p_at_least(window_hashes, *d).ge(&(1.0 - p / 100.0)))
+                .last()
+                .unwrap_or(0);
+            println!("  {:>7}: {}", format!("p{}", p), difficulty);
+        }
+        println!("  {:>7}: {:.2}", "mean", expected);
+    }
+
+    async fn share_benchmark(&self, url: String, threads: u64, hashrate: u64) {
+        let report = BenchmarkReport {
+            cpu_model: cpu_model(),
+            physical_cores: num_cpus::get_physical(),
+            logical_cores: num_cpus::get(),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            threads,
+            hashrate,
+            version: env!("CARGO_PKG_VERSION"),
+        };
+        let res = reqwest::Client::new().post(url).json(&report).send().await;
+        match res {
+            Ok(res) => match res.json::<LeaderboardResponse>().await {
+                Ok(board) => println!(
+                    "Faster than {:.1}% of {} similar rigs (median {:.0} H/sec)",
+                    board.percentile, board.similar_rigs, board.median_hashrate
+                ),
+                Err(err) => println!(
+                    "{} Unexpected leaderboard response: {}",
+                    "ERROR".bold().red(),
+                    err
+                ),
+            },
+            Err(err) => println!(
+                "{} Failed to reach leaderboard: {}",
+                "ERROR".bold().red(),
+                err
+            ),
+        }
+    }
+}
+
+/// Hashes a zero challenge on `threads` threads for `duration` seconds and returns hashes per second.
+pub fn measure_hashrate(threads: u64, duration: i64) -> u64 {
+    let challenge = [0; 32];
+    let handles: Vec<_> = (0..threads)
+        .map(|i| {
+            std::thread::spawn({
+                move || {
+                    let timer = Instant::now();
+                    let first_nonce = u64::MAX.saturating_div(threads).saturating_mul(i);
+                    let mut nonce = first_nonce;
+                    loop {
+                        // Create hash
+                        let _hx = drillx::hash(&challenge, &nonce.to_le_bytes());
+
+                        // Increment nonce
+                        nonce += 1;
+
+                        // Exit if time has elapsed
+                        if (timer.elapsed().as_secs() as i64).ge(&duration) {
+                            break;
                         }
+                    }
+
+                    // Return hash count
+                    nonce - first_nonce
+                }
+            })
+        })
+        .collect();
+
+    // Join handles and sum hash counts
+    let mut total_nonces = 0;
+    for h in handles {
+        if let Ok(count) = h.join() {
+            total_nonces += count;
+        }
+    }
+    total_nonces.saturating_div(duration as u64)
+}
 
-                        // Return hash count
-                        nonce - first_nonce
+/// Hashes a zero challenge on `threads` threads for `duration` and counts the hashes found
+/// at each difficulty.
+fn measure_difficulties(threads: u64, duration: Duration) -> [u64; 64] {
+    let handles: Vec<_> = (0..threads)
+        .map(|i| {
+            std::thread::spawn(move || {
+                let challenge = [0; 32];
+                let mut memory = SolverMemory::new();
+                let mut counts = [0u64; 64];
+                let timer = Instant::now();
+                let mut nonce = u64::MAX.saturating_div(threads).saturating_mul(i);
+                while timer.elapsed().lt(&duration) {
+                    if let Ok(hx) =
+                        drillx::hash_with_memory(&mut memory, &challenge, &nonce.to_le_bytes())
+                    {
+                        counts[(hx.difficulty() as usize).min(63)] += 1;
                     }
-                })
+                    nonce += 1;
+                }
+                counts
             })
-            .collect();
+        })
+        .collect();
 
-        // Join handles and return best nonce
-        let mut total_nonces = 0;
-        for h in handles {
-            if let Ok(count) = h.join() {
-                total_nonces += count;
+    // Join handles and merge counts
+    let mut total = [0u64; 64];
+    for h in handles {
+        if let Ok(counts) = h.join() {
+            for (total, count) in total.iter_mut().zip(counts) {
+                *total += count;
             }
         }
+    }
+    total
+}
 
-        // Update log
-        progress_bar.finish_with_message(format!(
-            "Hashpower: {} H/sec",
-            total_nonces.saturating_div(TEST_DURATION as u64),
-        ));
+/// The last thread count before adding threads stops paying off: the marginal gain per
+/// added thread falls below `KNEE_EFFICIENCY` of the single-thread rate.
+fn knee(points: &[ScalingPoint]) -> u64 {
+    let Some(first) = points.first() else {
+        return 1;
+    };
+    let base = first.hashrate.max(1) as f64;
+    for pair in points.windows(2) {
+        let added = (pair[1].threads - pair[0].threads) as f64;
+        let gain = pair[1].hashrate as f64 - pair[0].hashrate as f64;
+        if gain / added < base * KNEE_EFFICIENCY {
+            return pair[0].threads;
+        }
     }
+    points.last().map_or(1, |point| point.threads)
+}
+
+fn cpu_model() -> String {
+    std::fs::read_to_string("/proc/cpuinfo")
+        .ok()
+        .and_then(|info| {
+            info.lines()
+                .find(|line| line.starts_with("model name"))
+                .and_then(|line| line.split(':').nth(1))
+                .map(|model| model.trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string())
 }
@@ -0,0 +1,145 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use serde_json::json;
+use solana_client::{
+    client_error::Result as ClientResult,
+    nonblocking::rpc_client::RpcClient,
+    rpc_request::{RpcError, RpcRequest},
+    rpc_response::{Response, RpcBlockhash},
+};
+use solana_sdk::{commitment_config::CommitmentConfig, hash::Hash};
+
+use crate::Miner;
+
+/// How often the background task fetches a new blockhash.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Oldest a cached blockhash may be before senders stop trusting the background task and
+/// fetch one themselves.
+const MAX_AGE: Duration = Duration::from_secs(10);
+
+/// Blockhashes handed out to sign with that are remembered, for looking up when they expire.
+const SERVED_HISTORY: usize = 64;
+
+/// A recent blockhash, with the slot it was read at and when.
+#[derive(Clone, Copy, Debug)]
+pub struct RecentBlockhash {
+    pub hash: Hash,
+    pub slot: u64,
+    pub last_valid_block_height: u64,
+    pub fetched_at: Instant,
+}
+
+impl RecentBlockhash {
+    pub fn age(&self) -> Duration {
+        self.fetched_at.elapsed()
+    }
+}
+
+/// The freshest blockhash, kept up to date by a background task once a send path first asks
+/// for one, so transactions are signed without waiting on an RPC round trip.
+#[derive(Default)]
+pub struct BlockhashCache {
+    latest: RwLock<Option<RecentBlockhash>>,
+    served: Mutex<VecDeque<RecentBlockhash>>,
+    started: AtomicBool,
+}
+
+impl BlockhashCache {
+    /// The cached blockhash, if it is recent enough to sign with.
+    pub fn fresh(&self) -> Option<RecentBlockhash> {
+        self.latest
+            .read()
+            .unwrap()
+            .filter(|latest| latest.age() < MAX_AGE)
+    }
+
+    /// The last block height a transaction signed with `hash` can land at, if it was handed
+    /// out by this cache.
+    pub fn last_valid_block_height(&self, hash: &Hash) -> Option<u64> {
+        self.served
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|served| served.hash.eq(hash))
+            .map(|served| served.last_valid_block_height)
+    }
+
+    fn serve(&self, latest: RecentBlockhash) -> RecentBlockhash {
+        let mut served = self.served.lock().unwrap();
+        if !served.iter().any(|served| served.hash.eq(&latest.hash)) {
+            if served.len() == SERVED_HISTORY {
+                served.pop_front();
+            }
+            served.push_back(latest);
+        }
+        latest
+    }
+
+    fn set(&self, latest: RecentBlockhash) {
+        let mut current = self.latest.write().unwrap();
+        // A slow response may arrive after a newer one
+        if !current.is_some_and(|current| current.slot > latest.slot) {
+            *current = Some(latest);
+        }
+    }
+
+    /// Starts refreshing in the background, reading through whichever RPC client is current.
+    fn start(self: &Arc<Self>, rpc: Arc<RwLock<Arc<RpcClient>>>) {
+        if self.started.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let cache = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let client = rpc.read().unwrap().clone();
+                if let Ok(latest) = fetch_blockhash(&client, client.commitment()).await {
+                    cache.set(latest);
+                }
+                tokio::time::sleep(REFRESH_INTERVAL).await;
+            }
+        });
+    }
+}
+
+/// Fetches the latest blockhash along with the slot the RPC read it at.
+async fn fetch_blockhash(
+    client: &RpcClient,
+    commitment: CommitmentConfig,
+) -> ClientResult<RecentBlockhash> {
+    let response: Response<RpcBlockhash> = client
+        .send(RpcRequest::GetLatestBlockhash, json!([commitment]))
+        .await?;
+    Ok(RecentBlockhash {
+        hash: response
+            .value
+            .blockhash
+            .parse()
+            .map_err(|_| RpcError::ParseError("Hash".into()))?,
+        slot: response.context.slot,
+        last_valid_block_height: response.value.last_valid_block_height,
+        fetched_at: Instant::now(),
+    })
+}
+
+impl Miner {
+    /// The freshest blockhash to sign with. Served from the background refresher, falling
+    /// back to fetching one directly until it has a recent enough hash.
+    pub async fn recent_blockhash(&self) -> ClientResult<RecentBlockhash> {
+        self.blockhash.start(self.rpc.clone());
+        if let Some(latest) = self.blockhash.fresh() {
+            return Ok(self.blockhash.serve(latest));
+        }
+        let client = self.rpc_client();
+        let latest = fetch_blockhash(&client, client.commitment()).await?;
+        self.blockhash.set(latest);
+        Ok(self.blockhash.serve(latest))
+    }
+}
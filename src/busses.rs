@@ -1,22 +1,75 @@
-use ore_api::{
-    consts::{BUS_ADDRESSES, TOKEN_DECIMALS},
-    state::Bus,
-};
-use ore_utils::AccountDeserialize;
+use std::time::Duration;
+
+use colored::*;
+use ore_api::consts::{EPOCH_DURATION, TOKEN_DECIMALS};
+use serde_json::json;
 
-use crate::Miner;
+use crate::{
+    args::BussesArgs,
+    utils::{get_busses, get_clock, get_config, print_json},
+    Miner,
+};
 
 impl Miner {
-    pub async fn busses(&self) {
-        let client = self.rpc_client.clone();
-        for address in BUS_ADDRESSES.iter() {
-            let data = client.get_account_data(address).await.unwrap();
-            match Bus::try_from_bytes(&data) {
-                Ok(bus) => {
-                    let rewards = (bus.rewards as f64) / 10f64.powf(TOKEN_DECIMALS as f64);
-                    println!("Bus {}: {:} ORE", bus.id, rewards);
-                }
-                Err(_) => {}
+    pub async fn busses(&self, args: BussesArgs) {
+        if self.json {
+            let busses = get_busses(&self.rpc_client()).await;
+            let busses: Vec<_> = busses
+                .iter()
+                .map(|bus| {
+                    json!({
+                        "id": bus.id,
+                        "rewards": bus.rewards,
+                        "theoretical_rewards": bus.theoretical_rewards,
+                        "top_balance": bus.top_balance,
+                    })
+                })
+                .collect();
+            print_json(&json!(busses));
+            return;
+        }
+        if !args.watch {
+            self.print_busses(false).await;
+            return;
+        }
+        loop {
+            // Clear the screen and redraw from the top
+            print!("\x1B[2J\x1B[1;1H");
+            self.print_busses(true).await;
+            tokio::time::sleep(Duration::from_secs(args.interval)).await;
+        }
+    }
+
+    async fn print_busses(&self, watch: bool) {
+        let client = self.rpc_client();
+        let busses = get_busses(&client).await;
+
+        // The miner submits to the richest bus
+        let picked = busses
+            .iter()
+            .max_by_key(|bus| bus.rewards)
+            .map(|bus| bus.id);
+        for bus in busses.iter() {
+            let rewards = (bus.rewards as f64) / 10f64.powf(TOKEN_DECIMALS as f64);
+            let line = format!("Bus {}: {:} ORE", bus.id, rewards);
+            if watch && picked.eq(&Some(bus.id)) {
+                println!("{} {}", line.bold().green(), "<- next pick".dimmed());
+            } else {
+                println!("{}", line);
+            }
+        }
+
+        if watch {
+            let config = get_config(&client).await;
+            let clock = get_clock(&client).await;
+            let reset_in = config
+                .last_reset_at
+                .saturating_add(EPOCH_DURATION)
+                .saturating_sub(clock.unix_timestamp);
+            if reset_in > 0 {
+                println!("\nNext epoch reset in {}s", reset_in);
+            } else {
+                println!("\nEpoch reset is due");
             }
         }
     }
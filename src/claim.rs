@@ -3,57 +3,80 @@ use std::str::FromStr;
 use colored::*;
 use ore_api::consts::MINT_ADDRESS;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::signature::Signer;
+use solana_sdk::signature::{Keypair, Signer};
 use spl_token::amount_to_ui_amount;
 
 use crate::{
-    args::ClaimArgs,
-    cu_limits::CU_LIMIT_CLAIM,
-    send_and_confirm::ComputeBudget,
-    utils::{amount_f64_to_u64, ask_confirm, get_proof_with_authority},
+    args::{ClaimAmount, ClaimArgs},
+    cu_limits::{ComputeBudget, CU_LIMIT_CLAIM, CU_LIMIT_CREATE_ATA},
+    exit::{abort, fail_with, ExitCode},
+    labels::label,
+    receipts::{ReceiptEntry, Receipts},
+    utils::{amount_f64_to_u64, amount_u64_to_string, ask_confirm, get_proof_with_authority},
     Miner,
 };
 
 impl Miner {
     pub async fn claim(&self, args: ClaimArgs) {
+        if args.all {
+            if args.durable.nonce.is_some() {
+                println!(
+                    "{} --nonce claims one wallet at a time",
+                    "ERROR".bold().red()
+                );
+                return;
+            }
+            self.claim_all(args).await;
+            return;
+        }
+
+        // Signing offline, so nothing can be looked up
+        let sign_only = args.durable.sign_only;
+        if sign_only && !matches!(args.amount, Some(ClaimAmount::Ore(_))) {
+            println!(
+                "{} --sign-only needs --amount in ORE, the proof can't be read offline",
+                "ERROR".bold().red()
+            );
+            return;
+        }
+
         let signer = self.signer();
         let pubkey = signer.pubkey();
-        let proof = get_proof_with_authority(&self.rpc_client, pubkey).await;
         let mut ixs = vec![];
-        let beneficiary = match args.to {
+        let beneficiary = match args.to.as_deref() {
             Some(to) => {
-                // Create beneficiary token account, if needed
-                let wallet = Pubkey::from_str(&to).expect("Failed to parse wallet address");
-                let benefiary_tokens = spl_associated_token_account::get_associated_token_address(
-                    &wallet,
-                    &MINT_ADDRESS,
+                // Create the beneficiary token account in the same transaction, if needed.
+                // Idempotent, so it also works when signing offline.
+                let wallet = Pubkey::from_str(to)
+                    .unwrap_or_else(|_| abort(ExitCode::Config, "Failed to parse wallet address"));
+                ixs.push(
+                    spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                        &pubkey,
+                        &wallet,
+                        &MINT_ADDRESS,
+                        &spl_token::id(),
+                    ),
                 );
-                if self
-                    .rpc_client
-                    .get_token_account(&benefiary_tokens)
-                    .await
-                    .is_err()
-                {
-                    ixs.push(
-                        spl_associated_token_account::instruction::create_associated_token_account(
-                            &signer.pubkey(),
-                            &wallet,
-                            &ore_api::consts::MINT_ADDRESS,
-                            &spl_token::id(),
-                        ),
-                    );
-                }
-                benefiary_tokens
+                spl_associated_token_account::get_associated_token_address(&wallet, &MINT_ADDRESS)
+            }
+            None if sign_only => {
+                spl_associated_token_account::get_associated_token_address(&pubkey, &MINT_ADDRESS)
             }
             None => self.initialize_ata().await,
         };
 
         // Parse amount to claim
-        let amount = if let Some(amount) = args.amount {
-            amount_f64_to_u64(amount)
-        } else {
-            proof.balance
+        let amount = match args.amount {
+            Some(ClaimAmount::Ore(amount)) => amount_f64_to_u64(amount),
+            _ => {
+                let proof = get_proof_with_authority(&self.rpc_client(), pubkey).await;
+                claimable(&args, proof.balance)
+            }
         };
+        if amount.eq(&0) {
+            println!("Nothing to claim");
+            return;
+        }
 
         // Confirm user wants to claim
         if !ask_confirm(
@@ -71,16 +94,30 @@ impl Miner {
         }
 
         // Send and confirm
+        let cu_limit = if ixs.is_empty() {
+            CU_LIMIT_CLAIM
+        } else {
+            CU_LIMIT_CLAIM + CU_LIMIT_CREATE_ATA
+        };
         ixs.push(ore_api::instruction::claim(pubkey, beneficiary, amount));
-        self.send_and_confirm(&ixs, ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
-            .await
-            .ok();
+        let res = self
+            .send_and_confirm_durable(&ixs, ComputeBudget::Fixed(cu_limit), &args.durable)
+            .await;
+        if let Err(err) = &res {
+            fail_with(err);
+        }
+
+        // Convert the claim to SOL so it can fund future fees
+        if res.is_ok() && args.auto_swap {
+            self.swap_ore_to_sol(&args.jupiter_url, &signer, amount, args.slippage_bps)
+                .await;
+        }
     }
 
     async fn initialize_ata(&self) -> Pubkey {
         // Initialize client.
         let signer = self.signer();
-        let client = self.rpc_client.clone();
+        let client = self.rpc_client();
 
         // Build instructions.
         let token_account_pubkey = spl_associated_token_account::get_associated_token_address(
@@ -106,4 +143,146 @@ impl Miner {
         // Return token account address
         token_account_pubkey
     }
+
+    async fn claim_all(&self, args: ClaimArgs) {
+        let signers = self.multi_signers();
+
+        // Fetch claimable balances
+        let mut claims = vec![];
+        for signer in signers.iter() {
+            let proof = get_proof_with_authority(&self.rpc_client(), signer.pubkey()).await;
+            let amount = claimable(&args, proof.balance);
+            if amount.gt(&0) {
+                claims.push((signer, amount));
+            }
+        }
+        if claims.is_empty() {
+            println!("Nothing to claim");
+            return;
+        }
+
+        // Confirm user wants to claim
+        let total: u64 = claims.iter().map(|(_, amount)| amount).sum();
+        if !ask_confirm(
+            format!(
+                "\nYou are about to claim {} from {} wallets.\n\nAre you sure you want to continue? [Y/n]",
+                format!("{} ORE", amount_u64_to_string(total)).bold(),
+                claims.len(),
+            )
+            .as_str(),
+        ) {
+            return;
+        }
+
+        // Claim into the beneficiary's token account, or each wallet's own
+        let beneficiary = args.to.as_ref().map(|to| {
+            Pubkey::from_str(to)
+                .unwrap_or_else(|_| abort(ExitCode::Config, "Failed to parse wallet address"))
+        });
+        let swap = args
+            .auto_swap
+            .then_some((args.jupiter_url.as_str(), args.slippage_bps));
+        let entries = self.claim_into_accounts(claims, beneficiary, swap).await;
+
+        // Export receipts signed by the operator keypair
+        if let Some(path) = args.receipts {
+            let receipts = Receipts::build(entries, &self.signer());
+            match receipts.write(&path) {
+                Ok(()) => println!("Receipts root {} written to {}", receipts.root, path),
+                Err(err) => println!("{} Failed to write receipts: {}", "ERROR".bold().red(), err),
+            }
+        }
+    }
+
+    /// Claims each amount into the beneficiary wallet's token account, or the wallet's own
+    /// when there is none, swapping it to SOL through the given Jupiter API and slippage if
+    /// asked. Returns receipts for the claims that landed.
+    async fn claim_into_accounts(
+        &self,
+        claims: Vec<(&Keypair, u64)>,
+        beneficiary: Option<Pubkey>,
+        swap: Option<(&str, u16)>,
+    ) -> Vec<ReceiptEntry> {
+        let mut entries = vec![];
+        for (signer, amount) in claims {
+            let wallet = beneficiary.unwrap_or(signer.pubkey());
+            let beneficiary =
+                spl_associated_token_account::get_associated_token_address(&wallet, &MINT_ADDRESS);
+            let mut ixs = vec![];
+            if !matches!(
+                self.rpc_client().get_token_account(&beneficiary).await,
+                Ok(Some(_))
+            ) {
+                ixs.push(
+                    spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                        &signer.pubkey(),
+                        &wallet,
+                        &MINT_ADDRESS,
+                        &spl_token::id(),
+                    ),
+                );
+            }
+            ixs.push(ore_api::instruction::claim(
+                signer.pubkey(),
+                beneficiary,
+                amount,
+            ));
+            match self.send_and_confirm_with_key(&ixs, false, signer).await {
+                Ok(sig) => {
+                    entries.push(ReceiptEntry {
+                        wallet: signer.pubkey().to_string(),
+                        beneficiary: beneficiary.to_string(),
+                        amount,
+                        signature: sig.to_string(),
+                    });
+                    if let Some((jupiter_url, slippage_bps)) = swap {
+                        self.swap_ore_to_sol(jupiter_url, signer, amount, slippage_bps)
+                            .await;
+                    }
+                }
+                Err(err) => {
+                    println!(
+                        "{} {}: {}",
+                        "ERROR".bold().red(),
+                        label(&signer.pubkey()),
+                        err
+                    );
+                    fail_with(&err);
+                }
+            }
+        }
+        entries
+    }
+
+    /// Claims every wallet's full balance without asking, for claims requested while mining.
+    pub async fn claim_all_unattended(&self, beneficiary: Option<Pubkey>) {
+        let signers = self.multi_signers();
+        let mut claims = vec![];
+        for signer in signers.iter() {
+            let proof = get_proof_with_authority(&self.rpc_client(), signer.pubkey()).await;
+            if proof.balance.gt(&0) {
+                claims.push((signer, proof.balance));
+            }
+        }
+        let entries = self.claim_into_accounts(claims, beneficiary, None).await;
+        let total: u64 = entries.iter().map(|entry| entry.amount).sum();
+        let event = format!(
+            "Claimed {} ORE from {} wallets",
+            amount_u64_to_string(total),
+            entries.len()
+        );
+        println!("{} {}", "NOTICE".bold().cyan(), event);
+        self.notifier.send(&event).await;
+    }
+}
+
+/// The part of a proof's `balance` to claim under the amount or leave given.
+fn claimable(args: &ClaimArgs, balance: u64) -> u64 {
+    let amount = match args.amount {
+        Some(ClaimAmount::Ore(amount)) => amount_f64_to_u64(amount),
+        Some(ClaimAmount::Percent(percent)) => (balance as f64 * percent / 100.0) as u64,
+        None => balance,
+    };
+    let leave = args.leave.map(amount_f64_to_u64).unwrap_or(0);
+    amount.min(balance.saturating_sub(leave))
 }
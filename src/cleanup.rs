@@ -0,0 +1,166 @@
+use colored::*;
+use ore_api::consts::{MINT_ADDRESS, MINT_V1_ADDRESS, TOKEN_DECIMALS, TOKEN_DECIMALS_V1};
+use solana_program::{
+    instruction::Instruction, native_token::lamports_to_sol, program_pack::Pack, pubkey::Pubkey,
+};
+use solana_sdk::signature::{Keypair, Signer};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::amount_to_ui_amount;
+
+use crate::{
+    args::CleanupArgs,
+    exit::fail_with,
+    labels::label,
+    utils::{amount_f64_to_u64, amount_f64_to_u64_v1, ask_confirm, get_accounts},
+    Miner,
+};
+
+/// A token account worth closing, along with what it still holds.
+struct Closable<'a> {
+    signer: &'a Keypair,
+    address: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+    decimals: u8,
+    rent: u64,
+}
+
+impl Miner {
+    /// Closes empty or dust ORE and v1 ORE token accounts across the folder, returning the
+    /// rent to each wallet. Dust is burned first, since a token account must be empty to close.
+    pub async fn cleanup(&self, args: CleanupArgs) {
+        let signers = match self.folder_path {
+            Some(_) => self.multi_signers(),
+            None => vec![self.signer()],
+        };
+        let mints = [
+            (MINT_ADDRESS, TOKEN_DECIMALS, amount_f64_to_u64(args.dust)),
+            (
+                MINT_V1_ADDRESS,
+                TOKEN_DECIMALS_V1,
+                amount_f64_to_u64_v1(args.dust),
+            ),
+        ];
+        let mut candidates = vec![];
+        for signer in signers.iter() {
+            for (mint, decimals, dust) in mints {
+                let address = get_associated_token_address(&signer.pubkey(), &mint);
+                candidates.push((signer, address, mint, decimals, dust));
+            }
+        }
+        let addresses: Vec<Pubkey> = candidates.iter().map(|c| c.1).collect();
+        let accounts = get_accounts(&self.rpc_client(), &addresses).await;
+
+        // Find accounts at or below the dust threshold
+        let mut closable = vec![];
+        for ((signer, address, mint, decimals, dust), account) in
+            candidates.into_iter().zip(accounts)
+        {
+            let Some(account) = account else {
+                continue;
+            };
+            let Ok(token_account) = spl_token::state::Account::unpack(&account.data) else {
+                continue;
+            };
+            if token_account.amount.le(&dust) {
+                closable.push(Closable {
+                    signer,
+                    address,
+                    mint,
+                    amount: token_account.amount,
+                    decimals,
+                    rent: account.lamports,
+                });
+            }
+        }
+        if closable.is_empty() {
+            println!("No token accounts to clean up");
+            return;
+        }
+
+        // List what would be closed before asking
+        for account in closable.iter() {
+            let mint = if account.mint.eq(&MINT_ADDRESS) {
+                "ORE"
+            } else {
+                "ORE v1"
+            };
+            println!(
+                "{} {} account {}: {} {}, {} SOL rent",
+                label(&account.signer.pubkey()),
+                mint,
+                account.address,
+                amount_to_ui_amount(account.amount, account.decimals),
+                mint,
+                lamports_to_sol(account.rent)
+            );
+        }
+        let rent: u64 = closable.iter().map(|account| account.rent).sum();
+        if !ask_confirm(
+            format!(
+                "\nYou are about to close {} token accounts and recover {} SOL.\n\nAre you sure you want to continue? [Y/n]",
+                closable.len(),
+                lamports_to_sol(rent)
+            )
+            .as_str(),
+        ) {
+            return;
+        }
+
+        // Close each wallet's accounts in one transaction, paid by the wallet
+        let mut closed = 0;
+        for signer in signers.iter() {
+            let ixs: Vec<Instruction> = closable
+                .iter()
+                .filter(|account| account.signer.pubkey().eq(&signer.pubkey()))
+                .flat_map(close_ixs)
+                .collect();
+            if ixs.is_empty() {
+                continue;
+            }
+            match self.send_and_confirm_with_key(&ixs, false, signer).await {
+                Ok(_) => closed += 1,
+                Err(err) => {
+                    println!(
+                        "{} {}: {}",
+                        "ERROR".bold().red(),
+                        label(&signer.pubkey()),
+                        err
+                    );
+                    fail_with(&err);
+                }
+            }
+        }
+        println!("Cleaned up token accounts of {} wallets", closed);
+    }
+}
+
+/// Burns whatever dust is left, then closes the account back to its owner.
+fn close_ixs(account: &Closable) -> Vec<Instruction> {
+    let owner = account.signer.pubkey();
+    let mut ixs = vec![];
+    if account.amount.gt(&0) {
+        ixs.push(
+            spl_token::instruction::burn(
+                &spl_token::id(),
+                &account.address,
+                &account.mint,
+                &owner,
+                &[],
+                account.amount,
+            )
+            .unwrap(),
+        );
+    }
+    ixs.push(
+        spl_token::instruction::close_account(
+            &spl_token::id(),
+            &account.address,
+            &owner,
+            &owner,
+            &[],
+        )
+        .unwrap(),
+    );
+    ixs
+}
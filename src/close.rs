@@ -3,8 +3,9 @@ use solana_sdk::signature::Signer;
 use spl_token::amount_to_ui_amount;
 
 use crate::{
-    args::ClaimArgs,
-    send_and_confirm::ComputeBudget,
+    args::{ClaimArgs, DurableNonceArgs},
+    cu_limits::ComputeBudget,
+    exit::fail_with,
     utils::{ask_confirm, get_proof_with_authority},
     Miner,
 };
@@ -13,7 +14,7 @@ impl Miner {
     pub async fn close(&self) {
         // Confirm proof exists
         let signer = self.signer();
-        let proof = get_proof_with_authority(&self.rpc_client, signer.pubkey()).await;
+        let proof = get_proof_with_authority(&self.rpc_client(), signer.pubkey()).await;
 
         // Confirm the user wants to close.
         if !ask_confirm(
@@ -30,15 +31,25 @@ impl Miner {
         if proof.balance.gt(&0) {
             self.claim(ClaimArgs {
                 amount: None,
+                leave: None,
                 to: None,
+                all: false,
+                receipts: None,
+                auto_swap: false,
+                slippage_bps: 50,
+                jupiter_url: crate::swap::JUPITER_API_URL.to_string(),
+                durable: DurableNonceArgs::default(),
             })
             .await;
         }
 
         // Submit close transaction
         let ix = ore_api::instruction::close(signer.pubkey());
-        self.send_and_confirm(&[ix], ComputeBudget::Dynamic, false)
+        if let Err(err) = self
+            .send_and_confirm(&[ix], ComputeBudget::Dynamic, false)
             .await
-            .ok();
+        {
+            fail_with(&err);
+        }
     }
 }
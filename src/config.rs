@@ -1,13 +1,23 @@
 use colored::Colorize;
+use serde_json::json;
 
 use crate::{
-    utils::{amount_u64_to_string, get_config},
+    utils::{amount_u64_to_string, get_config, print_json},
     Miner,
 };
 
 impl Miner {
     pub async fn config(&self) {
-        let config = get_config(&self.rpc_client).await;
+        let config = get_config(&self.rpc_client()).await;
+        if self.json {
+            print_json(&json!({
+                "last_reset_at": config.last_reset_at,
+                "min_difficulty": config.min_difficulty,
+                "base_reward_rate": config.base_reward_rate,
+                "top_balance": config.top_balance,
+            }));
+            return;
+        }
         println!("{}: {}", "Last reset at".bold(), config.last_reset_at);
         println!("{}: {}", "Min difficulty".bold(), config.min_difficulty);
         println!("{}: {}", "Base reward rate".bold(), config.base_reward_rate);
@@ -0,0 +1,276 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+
+use colored::*;
+
+use crate::{exit::ExitCode, stats::SessionStats, telemetry::BundleTelemetry};
+
+/// Weight of the newest sample in latency moving averages.
+const LATENCY_EWMA_ALPHA: f64 = 0.3;
+
+/// How many recent passes landing latency is judged over.
+const LANDING_SAMPLES: usize = 20;
+
+/// How often the watchdog checks for progress.
+const WATCHDOG_POLL: Duration = Duration::from_secs(1);
+
+/// Runtime state of the mine loop that can be inspected and steered from outside it.
+#[derive(Default)]
+pub struct Control {
+    paused: AtomicBool,
+    shutdown: AtomicBool,
+    reload: AtomicBool,
+    claim: AtomicBool,
+    jito_tip: Mutex<Option<u64>>,
+    progress: Mutex<Option<Instant>>,
+    status: Mutex<String>,
+    stats: Mutex<SessionStats>,
+    telemetry: Mutex<Option<BundleTelemetry>>,
+    submit_latency: Mutex<Option<Duration>>,
+    landing_latencies: Mutex<VecDeque<Duration>>,
+}
+
+impl Control {
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn request_shutdown(&self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.load(Ordering::Relaxed)
+    }
+
+    pub fn request_reload(&self) {
+        self.reload.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether a reload was requested, clearing the request.
+    pub fn take_reload(&self) -> bool {
+        self.reload.swap(false, Ordering::Relaxed)
+    }
+
+    pub fn request_claim(&self) {
+        self.claim.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether a claim was requested, clearing the request.
+    pub fn take_claim(&self) -> bool {
+        self.claim.swap(false, Ordering::Relaxed)
+    }
+
+    /// Sets the Jito tip the next pass should use.
+    pub fn set_jito_tip(&self, tip: u64) {
+        *self.jito_tip.lock().unwrap() = Some(tip);
+    }
+
+    /// Returns a newly set Jito tip, clearing it.
+    pub fn take_jito_tip(&self) -> Option<u64> {
+        self.jito_tip.lock().unwrap().take()
+    }
+
+    /// Turns the first Ctrl-C or SIGTERM into a shutdown request so the current pass can
+    /// finish; a second signal exits immediately.
+    pub fn handle_signals(self: &Arc<Self>) {
+        let control = self.clone();
+        tokio::spawn(async move {
+            loop {
+                wait_for_signal().await;
+                if control.is_shutting_down() {
+                    println!("\n{}", control.stats().to_text());
+                    std::process::exit(ExitCode::Interrupted as i32);
+                }
+                control.request_shutdown();
+                println!(
+                    "\n{} Shutting down after the in-flight submission. Press Ctrl-C again to exit now.",
+                    "NOTICE".bold().cyan()
+                );
+            }
+        });
+
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            let control = self.clone();
+            tokio::spawn(async move {
+                let mut sighup =
+                    signal(SignalKind::hangup()).expect("Failed to install SIGHUP handler");
+                while sighup.recv().await.is_some() {
+                    control.request_reload();
+                }
+            });
+        }
+    }
+
+    /// Marks that the mine loop is making progress.
+    pub fn heartbeat(&self) {
+        *self.progress.lock().unwrap() = Some(Instant::now());
+    }
+
+    /// Whether there has been a heartbeat in the last `window`.
+    pub fn progressed_within(&self, window: Duration) -> bool {
+        let progress = *self.progress.lock().unwrap();
+        progress.is_some_and(|progress| progress.elapsed().lt(&window))
+    }
+
+    /// Resolves once there has been no heartbeat for `timeout`.
+    pub async fn stalled(&self, timeout: Duration) {
+        loop {
+            tokio::time::sleep(WATCHDOG_POLL).await;
+            let progress = *self.progress.lock().unwrap();
+            if progress.is_some_and(|progress| progress.elapsed().ge(&timeout)) {
+                return;
+            }
+        }
+    }
+
+    pub fn set_status(&self, status: String) {
+        *self.status.lock().unwrap() = status;
+    }
+
+    pub fn status(&self) -> String {
+        let status = format!(
+            "{}\n\n{}",
+            self.status.lock().unwrap(),
+            self.stats().to_text()
+        );
+        if self.is_paused() {
+            format!("Paused\n{}", status)
+        } else {
+            status
+        }
+    }
+
+    pub fn stats(&self) -> MutexGuard<'_, SessionStats> {
+        self.stats.lock().unwrap()
+    }
+
+    /// Folds in the time from building a bundle to the block engine accepting it.
+    pub fn record_submit_latency(&self, latency: Duration) {
+        let mut avg = self.submit_latency.lock().unwrap();
+        *avg = Some(match *avg {
+            Some(avg) => {
+                avg.mul_f64(1.0 - LATENCY_EWMA_ALPHA) + latency.mul_f64(LATENCY_EWMA_ALPHA)
+            }
+            None => latency,
+        });
+    }
+
+    pub fn submit_latency(&self) -> Duration {
+        self.submit_latency.lock().unwrap().unwrap_or_default()
+    }
+
+    /// Time from submission to the slot the bundle landed in.
+    pub fn record_landing_latency(&self, latency: Duration) {
+        let mut latencies = self.landing_latencies.lock().unwrap();
+        if latencies.len() == LANDING_SAMPLES {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+
+    /// The 90th percentile of recent landing latencies, so a few slow passes widen the buffer.
+    pub fn landing_latency(&self) -> Option<Duration> {
+        let mut latencies: Vec<_> = self
+            .landing_latencies
+            .lock()
+            .unwrap()
+            .iter()
+            .copied()
+            .collect();
+        if latencies.is_empty() {
+            return None;
+        }
+        latencies.sort();
+        let index = (latencies.len() * 9 / 10).min(latencies.len() - 1);
+        Some(latencies[index])
+    }
+
+    pub fn enable_telemetry(&self) {
+        self.telemetry
+            .lock()
+            .unwrap()
+            .get_or_insert_with(BundleTelemetry::default);
+    }
+
+    /// Bundle packing telemetry, `None` unless enabled.
+    pub fn telemetry(&self) -> MutexGuard<'_, Option<BundleTelemetry>> {
+        self.telemetry.lock().unwrap()
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut sigterm = signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = sigterm.recv() => {}
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    tokio::signal::ctrl_c().await.ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn no_latency_before_a_landing() {
+        assert_eq!(Control::default().landing_latency(), None);
+    }
+
+    #[test]
+    fn takes_the_90th_percentile() {
+        let control = Control::default();
+        for ms in (1..=10).rev() {
+            control.record_landing_latency(millis(ms * 100));
+        }
+        assert_eq!(control.landing_latency(), Some(millis(1000)));
+
+        let control = Control::default();
+        for _ in 0..19 {
+            control.record_landing_latency(millis(400));
+        }
+        // a single slow pass in twenty doesn't move it
+        control.record_landing_latency(millis(5000));
+        assert_eq!(control.landing_latency(), Some(millis(400)));
+        control.record_landing_latency(millis(5000));
+        assert_eq!(control.landing_latency(), Some(millis(5000)));
+    }
+
+    #[test]
+    fn forgets_passes_outside_the_window() {
+        let control = Control::default();
+        for _ in 0..LANDING_SAMPLES {
+            control.record_landing_latency(millis(5000));
+        }
+        for _ in 0..LANDING_SAMPLES {
+            control.record_landing_latency(millis(400));
+        }
+        assert_eq!(control.landing_latency(), Some(millis(400)));
+    }
+}
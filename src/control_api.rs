@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+use colored::*;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{control::Control, labels::label};
+
+/// Largest request head the control API reads before giving up on a client.
+const MAX_HEADER_LINES: usize = 64;
+
+/// Serves a small HTTP API over the mine loop's `Control`, so orchestration tools can steer
+/// a miner without a shell on the box.
+///
+/// - `GET /status`
+/// - `POST /pause`, `POST /resume`
+/// - `POST /tip?lamports=N` sets the Jito tip from the next pass
+/// - `POST /reload` picks up wallets added to or removed from the folder
+/// - `POST /claim` claims every wallet's rewards before the next pass
+pub async fn serve(control: Arc<Control>, host: &str, port: u16, token: Option<String>) {
+    let listener = match TcpListener::bind((host, port)).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            println!(
+                "{} Failed to serve the control API on {}:{}: {}",
+                "ERROR".bold().red(),
+                host,
+                port,
+                err
+            );
+            return;
+        }
+    };
+    if token.is_none() && host.ne("127.0.0.1") && host.ne("localhost") {
+        println!(
+            "{} The control API is reachable beyond this machine without --control-token",
+            "WARNING".bold().yellow()
+        );
+    }
+    println!("Control API listening on {}:{}", host, port);
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                continue;
+            };
+            tokio::spawn(handle(stream, control.clone(), token.clone()));
+        }
+    });
+}
+
+async fn handle(stream: TcpStream, control: Arc<Control>, token: Option<String>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    // Request line, then headers up to the blank line. Requests carry no body.
+    let Ok(Some(request_line)) = lines.next_line().await else {
+        return;
+    };
+    let mut authorization = None;
+    for _ in 0..MAX_HEADER_LINES {
+        match lines.next_line().await {
+            Ok(Some(line)) if line.is_empty() => break,
+            Ok(Some(line)) => {
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.trim().eq_ignore_ascii_case("authorization") {
+                        authorization = Some(value.trim().to_string());
+                    }
+                }
+            }
+            _ => return,
+        }
+    }
+
+    let (status, body) = match token {
+        Some(token) if authorization.ne(&Some(format!("Bearer {}", token))) => (
+            "401 Unauthorized",
+            json!({ "error": "missing or wrong bearer token" }),
+        ),
+        _ => route(&request_line, &control),
+    };
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    writer.write_all(response.as_bytes()).await.ok();
+}
+
+fn route(request_line: &str, control: &Control) -> (&'static str, Value) {
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    match (method, path) {
+        ("GET", "/status") => {
+            // Status locks the stats itself, so take it first
+            let status = control.status();
+            let stats = control.stats();
+            let wallets: serde_json::Map<_, _> = stats
+                .wallets
+                .iter()
+                .map(|(wallet, stats)| {
+                    (
+                        wallet.to_string(),
+                        json!({
+                            "label": label(wallet),
+                            "passes": stats.passes,
+                            "landed": stats.landed,
+                            "missed": stats.missed(),
+                            "reward_per_landed": stats.reward_per_landed(),
+                        }),
+                    )
+                })
+                .collect();
+            (
+                "200 OK",
+                json!({
+                    "paused": control.is_paused(),
+                    "status": status,
+                    "rpc_calls": stats.rpc_calls.clone(),
+                    "wallets": wallets,
+                }),
+            )
+        }
+        ("POST", "/pause") => {
+            control.pause();
+            ("200 OK", json!({ "message": "Mining paused" }))
+        }
+        ("POST", "/resume") => {
+            control.resume();
+            ("200 OK", json!({ "message": "Mining resumed" }))
+        }
+        ("POST", "/tip") => {
+            let lamports = query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| key.eq(&"lamports"))
+                .and_then(|(_, value)| value.parse::<u64>().ok());
+            match lamports {
+                Some(lamports) => {
+                    control.set_jito_tip(lamports);
+                    (
+                        "200 OK",
+                        json!({ "message": format!("Jito tip set to {} lamports from the next pass", lamports) }),
+                    )
+                }
+                None => (
+                    "400 Bad Request",
+                    json!({ "error": "expected ?lamports=<amount>" }),
+                ),
+            }
+        }
+        ("POST", "/reload") => {
+            control.request_reload();
+            (
+                "200 OK",
+                json!({ "message": "Reloading wallets before the next pass" }),
+            )
+        }
+        ("POST", "/claim") => {
+            control.request_claim();
+            (
+                "200 OK",
+                json!({ "message": "Claiming before the next pass" }),
+            )
+        }
+        _ => ("404 Not Found", json!({ "error": "unknown endpoint" })),
+    }
+}
@@ -0,0 +1,259 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use colored::*;
+use drillx::Solution;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    time::Instant,
+};
+
+use crate::{args::CoordinatorArgs, Miner};
+
+/// How long past the cutoff to wait for workers to report back.
+const COLLECT_GRACE: Duration = Duration::from_secs(5);
+
+/// Messages between the coordinator and its workers, one JSON object per line.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PoolMessage {
+    /// First message from a worker.
+    Hello { token: Option<String>, threads: u64 },
+    /// A challenge to hash, starting at `first_nonce`.
+    Work {
+        id: u64,
+        challenge: [u8; 32],
+        first_nonce: u64,
+        cutoff: u64,
+        min_difficulty: u32,
+        target_difficulty: Option<u32>,
+    },
+    /// The best solution a worker found for a piece of work.
+    Solution {
+        id: u64,
+        digest: [u8; 16],
+        nonce: [u8; 8],
+    },
+}
+
+/// A challenge handed out to the pool, awaiting solutions.
+pub struct Dispatch {
+    id: u64,
+    challenge: [u8; 32],
+    workers: usize,
+    deadline: Instant,
+}
+
+struct ConnectedWorker {
+    threads: u64,
+    sender: UnboundedSender<PoolMessage>,
+}
+
+/// Workers connected to the coordinator. Workers hold no keys, they only hash challenges
+/// and report the best solution back.
+pub struct WorkerPool {
+    token: Option<String>,
+    workers: Mutex<HashMap<SocketAddr, ConnectedWorker>>,
+    next_id: AtomicU64,
+    solutions: tokio::sync::Mutex<UnboundedReceiver<PoolMessage>>,
+    solution_sender: UnboundedSender<PoolMessage>,
+}
+
+impl WorkerPool {
+    fn new(token: Option<String>) -> Self {
+        let (solution_sender, solutions) = mpsc::unbounded_channel();
+        Self {
+            token,
+            workers: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+            solutions: tokio::sync::Mutex::new(solutions),
+            solution_sender,
+        }
+    }
+
+    /// Accepts workers on `listen` for the life of the process.
+    async fn listen(self: Arc<Self>, listen: &str) -> std::io::Result<()> {
+        let listener = TcpListener::bind(listen).await?;
+        println!("Accepting workers on {}", listener.local_addr()?);
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, addr)) = listener.accept().await else {
+                    continue;
+                };
+                tokio::spawn(self.clone().serve(stream, addr));
+            }
+        });
+        Ok(())
+    }
+
+    async fn serve(self: Arc<Self>, stream: TcpStream, addr: SocketAddr) {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        // Check the worker is one of ours
+        let threads = match lines.next_line().await {
+            Ok(Some(line)) => match serde_json::from_str::<PoolMessage>(&line) {
+                Ok(PoolMessage::Hello { token, threads }) if token.eq(&self.token) => threads,
+                _ => {
+                    println!(
+                        "{} Rejected worker {}: bad handshake",
+                        "WARNING".bold().yellow(),
+                        addr
+                    );
+                    return;
+                }
+            },
+            _ => return,
+        };
+        let (sender, mut outbox) = mpsc::unbounded_channel::<PoolMessage>();
+        self.workers
+            .lock()
+            .unwrap()
+            .insert(addr, ConnectedWorker { threads, sender });
+        println!(
+            "{} Worker {} connected with {} threads",
+            "NOTICE".bold().cyan(),
+            addr,
+            threads
+        );
+
+        // Forward work out and solutions in until either side hangs up
+        loop {
+            tokio::select! {
+                message = outbox.recv() => {
+                    let Some(message) = message else { break };
+                    let mut line = serde_json::to_string(&message).unwrap();
+                    line.push('\n');
+                    if writer.write_all(line.as_bytes()).await.is_err() {
+                        break;
+                    }
+                }
+                line = lines.next_line() => {
+                    let Ok(Some(line)) = line else { break };
+                    if let Ok(message @ PoolMessage::Solution { .. }) = serde_json::from_str::<PoolMessage>(&line) {
+                        self.solution_sender.send(message).ok();
+                    }
+                }
+            }
+        }
+        self.workers.lock().unwrap().remove(&addr);
+        println!("{} Worker {} disconnected", "WARNING".bold().yellow(), addr);
+    }
+
+    /// Sends a challenge to every connected worker, each starting in its own slice of the
+    /// nonce space. Returns nothing when no workers are connected.
+    pub fn dispatch(
+        &self,
+        challenge: [u8; 32],
+        cutoff: u64,
+        min_difficulty: u32,
+        target_difficulty: Option<u32>,
+    ) -> Option<Dispatch> {
+        let workers = self.workers.lock().unwrap();
+        if workers.is_empty() {
+            return None;
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        // Slices are sized by thread count. The coordinator's own threads start at random,
+        // which is as good as a slice of its own in a 64-bit space.
+        let total_threads: u64 = workers.values().map(|worker| worker.threads.max(1)).sum();
+        let slice = u64::MAX / total_threads;
+        let mut first_nonce = 0u64;
+        let mut sent = 0;
+        for worker in workers.values() {
+            let message = PoolMessage::Work {
+                id,
+                challenge,
+                first_nonce,
+                cutoff,
+                min_difficulty,
+                target_difficulty,
+            };
+            if worker.sender.send(message).is_ok() {
+                sent += 1;
+            }
+            first_nonce = first_nonce.saturating_add(slice.saturating_mul(worker.threads.max(1)));
+        }
+        println!("Dispatched challenge to {} workers", sent);
+        Some(Dispatch {
+            id,
+            challenge,
+            workers: sent,
+            deadline: Instant::now() + Duration::from_secs(cutoff) + COLLECT_GRACE,
+        })
+    }
+
+    /// Waits for the workers' solutions to a dispatch and returns the best of them and `local`.
+    pub async fn collect(&self, dispatch: Dispatch, local: Solution) -> Solution {
+        let mut best = local;
+        let mut received = 0;
+        let mut solutions = self.solutions.lock().await;
+        while received < dispatch.workers {
+            let message = match tokio::time::timeout_at(dispatch.deadline, solutions.recv()).await {
+                Ok(Some(message)) => message,
+                _ => break,
+            };
+            let PoolMessage::Solution { id, digest, nonce } = message else {
+                continue;
+            };
+            // Late solutions to earlier challenges are no use now
+            if id.ne(&dispatch.id) {
+                continue;
+            }
+            received += 1;
+
+            // Workers are trusted with hashing, not with the result
+            let solution = Solution::new(digest, nonce);
+            if !solution.is_valid(&dispatch.challenge) {
+                println!(
+                    "{} Discarding an invalid solution from a worker",
+                    "WARNING".bold().yellow()
+                );
+                continue;
+            }
+            if solution
+                .to_hash()
+                .difficulty()
+                .gt(&best.to_hash().difficulty())
+            {
+                best = solution;
+            }
+        }
+        if received.lt(&dispatch.workers) {
+            println!(
+                "{} {} of {} workers did not report back in time",
+                "WARNING".bold().yellow(),
+                dispatch.workers - received,
+                dispatch.workers
+            );
+        }
+        best
+    }
+}
+
+impl Miner {
+    pub async fn coordinator(&self, args: CoordinatorArgs) {
+        let pool = Arc::new(WorkerPool::new(args.token));
+        if let Err(err) = pool.clone().listen(&args.listen).await {
+            println!(
+                "{} Failed to listen on {}: {}",
+                "ERROR".bold().red(),
+                args.listen,
+                err
+            );
+            return;
+        }
+        self.mine_with_pool(args.mine, Some(pool)).await;
+    }
+}
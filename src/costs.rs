@@ -0,0 +1,101 @@
+use colored::*;
+use ore_api::consts::{MINT_ADDRESS, MINT_V1_ADDRESS};
+use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
+use solana_sdk::signature::Signer;
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::{
+    args::{CostsArgs, ExportSource},
+    labels::label,
+    utils::{get_balances, proof_pubkey},
+    Miner,
+};
+
+impl Miner {
+    /// Totals the SOL mining has tied up or spent: rent held by proofs and token accounts,
+    /// per wallet, plus transaction fees and Jito tips from the ledger or chain history.
+    pub async fn costs(&self, args: CostsArgs) {
+        let wallets: Vec<Pubkey> = match self.folder_path {
+            Some(_) => self
+                .multi_signers()
+                .iter()
+                .map(|signer| signer.pubkey())
+                .collect(),
+            None => vec![self.signer().pubkey()],
+        };
+
+        // Rent is whatever the accounts hold, since none of them keep spare lamports
+        let mut addresses = vec![];
+        for wallet in wallets.iter() {
+            addresses.push(proof_pubkey(*wallet));
+            addresses.push(get_associated_token_address(wallet, &MINT_ADDRESS));
+            addresses.push(get_associated_token_address(wallet, &MINT_V1_ADDRESS));
+        }
+        let rents = get_balances(&self.rpc_client(), &addresses).await;
+
+        // Fees each wallet paid itself, if reading them from chain history
+        let scan_fees = matches!(args.source, ExportSource::Chain);
+        println!(
+            "{:<44} {:>14} {:>14} {:>14}",
+            "Wallet".bold(),
+            "Proof rent".bold(),
+            "Token rent".bold(),
+            if scan_fees { "Fees paid" } else { "" }.bold()
+        );
+        let (mut proof_rent, mut token_rent, mut fees) = (0, 0, 0);
+        for (wallet, rent) in wallets.iter().zip(rents.chunks(3)) {
+            let wallet_fees = if scan_fees {
+                let scanned = self.scan_wallet(*wallet, args.limit).await;
+                Some(scanned.iter().map(|tx| tx.fee_paid).sum::<u64>())
+            } else {
+                None
+            };
+            proof_rent += rent[0];
+            token_rent += rent[1] + rent[2];
+            fees += wallet_fees.unwrap_or(0);
+            println!(
+                "{:<44} {:>10.6} SOL {:>10.6} SOL {:>14}",
+                label(wallet),
+                lamports_to_sol(rent[0]),
+                lamports_to_sol(rent[1] + rent[2]),
+                wallet_fees
+                    .map(|fees| format!("{:.6} SOL", lamports_to_sol(fees)))
+                    .unwrap_or_default()
+            );
+        }
+
+        println!(
+            "\n{}: {} SOL in proofs, {} SOL in token accounts",
+            "Rent locked".bold(),
+            lamports_to_sol(proof_rent),
+            lamports_to_sol(token_rent)
+        );
+        match args.source {
+            ExportSource::Chain => println!(
+                "{}: {} SOL in fees paid by the wallets over their last {} proof transactions",
+                "Spent".bold(),
+                lamports_to_sol(fees),
+                args.limit
+            ),
+            ExportSource::Ledger => {
+                let Some(ledger) = &self.ledger else {
+                    println!(
+                        "{} No ledger configured. Pass --db <PATH>.",
+                        "ERROR".bold().red()
+                    );
+                    return;
+                };
+                match ledger.totals(0, None) {
+                    Ok(totals) => println!(
+                        "{}: {} SOL in fees, {} SOL in tips over {} recorded passes",
+                        "Spent".bold(),
+                        lamports_to_sol(totals.fees),
+                        lamports_to_sol(totals.tips),
+                        totals.passes
+                    ),
+                    Err(err) => println!("{} {}", "ERROR".bold().red(), err),
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,85 @@
+use solana_program::instruction::Instruction;
+use solana_sdk::{compute_budget, compute_budget::ComputeBudgetInstruction, pubkey::Pubkey};
+
+use crate::Miner;
+
 pub const CU_LIMIT_UPGRADE: u32 = 20_000;
 pub const CU_LIMIT_CLAIM: u32 = 32_000;
-pub const _CU_LIMIT_RESET: u32 = 12_200;
-pub const _CU_LIMIT_MINE: u32 = 3200;
+pub const CU_LIMIT_CREATE_ATA: u32 = 30_000;
+pub const CU_LIMIT_RESET: u32 = 12_200;
+pub const CU_LIMIT_MINE: u32 = 3200;
+
+/// Ceiling for a single transaction, used when simulating to measure usage.
+pub const CU_LIMIT_MAX: u32 = 1_400_000;
+
+/// Headroom on top of simulated usage, since state may change before landing.
+const CU_MARGIN_PERCENT: u64 = 10;
+const CU_MARGIN_MIN: u64 = 1_000;
+
+/// The compute unit limit to fall back to when simulation can't measure a transaction.
+#[derive(Clone, Copy, Debug)]
+pub enum ComputeBudget {
+    Dynamic,
+    Fixed(u32),
+}
+
+/// A compute unit limit for a transaction that used `units` in simulation.
+pub fn with_margin(units: u64) -> u32 {
+    let margin = (units * CU_MARGIN_PERCENT / 100).max(CU_MARGIN_MIN);
+    (units + margin).min(CU_LIMIT_MAX as u64) as u32
+}
+
+/// Whether the instructions already set their own compute budget, like a Jupiter swap.
+pub fn has_compute_budget(ixs: &[Instruction]) -> bool {
+    ixs.iter().any(|ix| ix.program_id.eq(&compute_budget::id()))
+}
+
+impl Miner {
+    /// Prefixes the instructions with a compute unit limit sized from simulation, falling back
+    /// to `budget`, and a compute unit price of `priority_fee`.
+    pub async fn with_compute_budget(
+        &self,
+        ixs: &[Instruction],
+        payer: &Pubkey,
+        budget: ComputeBudget,
+        priority_fee: u64,
+    ) -> Vec<Instruction> {
+        let cu_limit = match self.simulate_compute_units(ixs, payer).await {
+            Some(units) => with_margin(units),
+            None => match budget {
+                ComputeBudget::Dynamic => CU_LIMIT_MAX,
+                ComputeBudget::Fixed(cus) => cus,
+            },
+        };
+        let mut final_ixs = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(cu_limit),
+            ComputeBudgetInstruction::set_compute_unit_price(priority_fee),
+        ];
+        final_ixs.extend_from_slice(ixs);
+        final_ixs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_at_least_the_minimum_margin() {
+        assert_eq!(with_margin(0), 1_000);
+        assert_eq!(with_margin(3_000), 4_000);
+        assert_eq!(with_margin(10_000), 11_000);
+    }
+
+    #[test]
+    fn adds_ten_percent_to_larger_transactions() {
+        assert_eq!(with_margin(20_000), 22_000);
+        assert_eq!(with_margin(200_000), 220_000);
+    }
+
+    #[test]
+    fn never_exceeds_the_transaction_ceiling() {
+        assert_eq!(with_margin(1_300_000), CU_LIMIT_MAX);
+        assert_eq!(with_margin(CU_LIMIT_MAX as u64), CU_LIMIT_MAX);
+    }
+}
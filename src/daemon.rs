@@ -0,0 +1,274 @@
+use std::{
+    fs::OpenOptions,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use colored::*;
+use serde_json::Value;
+
+use crate::{
+    args::{MineArgs, StatusArgs, StopArgs},
+    exit::{abort, fail, ExitCode},
+    Miner,
+};
+
+/// Where the PID is written when `--pid-file` isn't given.
+pub const DEFAULT_PID_FILE: &str = "ore.pid";
+
+/// How long a freshly spawned daemon must stay up to count as started.
+const STARTUP_GRACE: Duration = Duration::from_secs(2);
+
+/// How often `ore stop` checks whether the miner has exited.
+const STOP_POLL: Duration = Duration::from_millis(500);
+
+/// How long to wait after the second signal before killing the miner outright.
+const KILL_GRACE: Duration = Duration::from_secs(5);
+
+/// Records the running miner's PID, and removes the file again when mining stops.
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Writes the current PID, refusing to start if another live miner already holds the file.
+    pub fn create(path: &str) -> Self {
+        let pid = std::process::id();
+        if let Some(running) = read_pid(Path::new(path)) {
+            if running.ne(&pid) && is_running(running) {
+                abort(
+                    ExitCode::Config,
+                    format!("Already mining with pid {} (from {})", running, path),
+                );
+            }
+        }
+        if let Err(err) = std::fs::write(path, format!("{}\n", pid)) {
+            abort(
+                ExitCode::Config,
+                format!("Failed to write pid file {}: {}", path, err),
+            );
+        }
+        Self { path: path.into() }
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Leave it alone if another miner has taken it over since
+        if read_pid(&self.path).eq(&Some(std::process::id())) {
+            std::fs::remove_file(&self.path).ok();
+        }
+    }
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    // Signal 0 only checks that the process exists and can be signalled
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn signal(pid: u32, signal: libc::c_int) {
+    unsafe {
+        libc::kill(pid as libc::pid_t, signal);
+    }
+}
+
+/// Starts this same command again in the background, without `--daemon`, detached from the
+/// terminal and logging to `--log-file`, then exits once it is up.
+#[cfg(unix)]
+pub fn spawn(args: &MineArgs) -> ! {
+    use std::os::unix::process::CommandExt;
+
+    let pid_file = args.pid_file.as_deref().unwrap_or(DEFAULT_PID_FILE);
+    if let Some(running) = read_pid(Path::new(pid_file)) {
+        if is_running(running) {
+            abort(
+                ExitCode::Config,
+                format!("Already mining with pid {} (from {})", running, pid_file),
+            );
+        }
+    }
+    let log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&args.log_file)
+        .unwrap_or_else(|err| {
+            abort(
+                ExitCode::Config,
+                format!("Failed to open log file {}: {}", args.log_file, err),
+            )
+        });
+    let log_err = log.try_clone().unwrap_or_else(|err| {
+        abort(
+            ExitCode::Config,
+            format!("Failed to open log file {}: {}", args.log_file, err),
+        )
+    });
+
+    let exe = std::env::current_exe()
+        .unwrap_or_else(|err| abort(ExitCode::Failure, format!("Failed to find ore: {}", err)));
+    let mut command = Command::new(exe);
+    command.args(std::env::args_os().skip(1).filter(|arg| arg.ne("--daemon")));
+    if args.pid_file.is_none() {
+        command.args(["--pid-file", pid_file]);
+    }
+    command
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::null())
+        .stdout(log)
+        .stderr(log_err);
+    // A session of its own, so closing the terminal doesn't hang it up
+    unsafe {
+        command.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+    let mut child = command.spawn().unwrap_or_else(|err| {
+        abort(
+            ExitCode::Failure,
+            format!("Failed to start the miner: {}", err),
+        )
+    });
+    std::fs::write(pid_file, format!("{}\n", child.id())).ok();
+
+    // Catch bad arguments and the like, which fail right away
+    std::thread::sleep(STARTUP_GRACE);
+    if let Ok(Some(status)) = child.try_wait() {
+        std::fs::remove_file(pid_file).ok();
+        abort(
+            ExitCode::Failure,
+            format!(
+                "The miner exited with {} right after starting, see {}",
+                status, args.log_file
+            ),
+        );
+    }
+    println!(
+        "Mining in the background with pid {}\nLogs: {}\nCheck on it with `ore status` and stop it with `ore stop`",
+        child.id(),
+        args.log_file
+    );
+    std::process::exit(ExitCode::Success as i32)
+}
+
+#[cfg(not(unix))]
+pub fn spawn(_args: &MineArgs) -> ! {
+    abort(ExitCode::Config, "--daemon is only supported on Unix")
+}
+
+impl Miner {
+    /// Reports whether a background miner is running, and what it is doing if it serves the
+    /// control API.
+    pub async fn status(&self, args: StatusArgs) {
+        let Some(pid) = running_pid(&args.pid_file) else {
+            return;
+        };
+        println!("{} with pid {}", "Running".bold().green(), pid);
+        let Some(port) = args.control_port else {
+            return;
+        };
+        let url = format!("http://{}:{}/status", args.control_host, port);
+        let mut request = reqwest::Client::new().get(&url);
+        if let Some(token) = &args.control_token {
+            request = request.bearer_auth(token);
+        }
+        let body = match request.send().await {
+            Ok(res) => res.json::<Value>().await,
+            Err(err) => Err(err),
+        };
+        match body {
+            Ok(body) => match body["status"].as_str() {
+                Some(status) => println!("\n{}", status),
+                None => println!(
+                    "{} {}",
+                    "ERROR".bold().red(),
+                    body["error"].as_str().unwrap_or("Unexpected response")
+                ),
+            },
+            Err(err) => {
+                println!(
+                    "{} Failed to reach the control API at {}: {}",
+                    "ERROR".bold().red(),
+                    url,
+                    err
+                );
+                fail(ExitCode::Failure);
+            }
+        }
+    }
+
+    /// Asks a background miner to finish its current pass and exit, as Ctrl-C would.
+    #[cfg(unix)]
+    pub async fn stop(&self, args: StopArgs) {
+        let Some(pid) = running_pid(&args.pid_file) else {
+            return;
+        };
+        signal(pid, libc::SIGTERM);
+        if args.force {
+            // A second signal skips finishing the pass
+            tokio::time::sleep(STOP_POLL).await;
+            signal(pid, libc::SIGTERM);
+        } else {
+            println!("Waiting for pid {} to finish its current pass...", pid);
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(args.timeout);
+        while is_running(pid) && Instant::now() < deadline {
+            tokio::time::sleep(STOP_POLL).await;
+        }
+        if is_running(pid) {
+            println!(
+                "{} Still running after {}s, exiting it now",
+                "WARNING".bold().yellow(),
+                args.timeout
+            );
+            signal(pid, libc::SIGTERM);
+            tokio::time::sleep(KILL_GRACE).await;
+            if is_running(pid) {
+                signal(pid, libc::SIGKILL);
+            }
+        }
+        std::fs::remove_file(&args.pid_file).ok();
+        println!("{} pid {}", "Stopped".bold().green(), pid);
+    }
+
+    #[cfg(not(unix))]
+    pub async fn stop(&self, _args: StopArgs) {
+        abort(ExitCode::Config, "ore stop is only supported on Unix")
+    }
+}
+
+/// The PID of the miner holding `pid_file`, if it is still running.
+fn running_pid(pid_file: &str) -> Option<u32> {
+    let pid = read_pid(Path::new(pid_file));
+    match pid {
+        Some(pid) if is_running(pid) => Some(pid),
+        Some(pid) => {
+            println!(
+                "{} (pid {} from {} has exited)",
+                "Not running".bold().red(),
+                pid,
+                pid_file
+            );
+            fail(ExitCode::Failure);
+            None
+        }
+        None => {
+            println!("{} (no pid in {})", "Not running".bold().red(), pid_file);
+            fail(ExitCode::Failure);
+            None
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_request::{RpcError, RpcResponseErrorData},
+};
+use solana_program::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+
+/// Custom error codes of the ORE program, mirroring `ore_api::error::OreError`,
+/// with what to do about each.
+const ORE_ERRORS: [(u32, &str, &str); 8] = [
+    (
+        0,
+        "epoch needs reset",
+        "The epoch ended and nobody has reset it yet. The next pass will include a reset.",
+    ),
+    (
+        1,
+        "hash invalid",
+        "The solution does not match the proof's current challenge. Another transaction for this wallet probably landed first; the next pass will use the new challenge.",
+    ),
+    (
+        2,
+        "hash too easy",
+        "The solution is below the program's minimum difficulty. Raise --min-difficulty or give hashing more time with a lower --buffer-time.",
+    ),
+    (
+        3,
+        "claim too large",
+        "The claim amount exceeds the proof balance. Check `ore proof` and claim less.",
+    ),
+    (
+        4,
+        "clock invalid",
+        "The submission landed outside the allowed window. Increase --buffer-time or try --adaptive-buffer.",
+    ),
+    (
+        5,
+        "submitted too early",
+        "This wallet already submitted within the last minute. Wait for the cooldown before mining again.",
+    ),
+    (
+        6,
+        "max supply reached",
+        "ORE has reached its maximum supply. There is nothing left to mine.",
+    ),
+    (
+        7,
+        "proof authority mismatch",
+        "The proof does not belong to this signer. Check the keypair or run `ore proof` for the address.",
+    ),
+];
+
+/// A readable message and hint for a transaction failing with an ORE program error.
+pub fn explain_transaction_error(err: &TransactionError) -> Option<String> {
+    let TransactionError::InstructionError(_, InstructionError::Custom(code)) = err else {
+        return None;
+    };
+    ORE_ERRORS
+        .iter()
+        .find(|(c, _, _)| c.eq(code))
+        .map(|(_, message, hint)| format!("ORE program error: {}. {}", message, hint))
+}
+
+/// The error with an ORE explanation when there is one.
+pub fn describe_transaction_error(err: &TransactionError) -> String {
+    explain_transaction_error(err).unwrap_or_else(|| err.to_string())
+}
+
+/// Failures to send that call for something other than a plain retry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SendFailure {
+    /// The transaction already landed, most likely from an earlier broadcast of it.
+    AlreadyProcessed,
+    /// The node doesn't know the blockhash, either because it expired or the node hasn't
+    /// seen it yet.
+    BlockhashNotFound,
+    /// Another transaction holds a write lock on one of the accounts.
+    AccountInUse,
+    /// The node is behind the cluster, by this many slots if it said.
+    NodeBehind(Option<u64>),
+    Other,
+}
+
+/// Sorts a send error into the failures the send paths react to.
+pub fn classify_send_error(err: &ClientError) -> SendFailure {
+    if let ClientErrorKind::RpcError(RpcError::RpcResponseError {
+        data: RpcResponseErrorData::NodeUnhealthy { num_slots_behind },
+        ..
+    }) = &err.kind
+    {
+        return SendFailure::NodeBehind(*num_slots_behind);
+    }
+    match err.get_transaction_error() {
+        Some(TransactionError::AlreadyProcessed) => SendFailure::AlreadyProcessed,
+        Some(TransactionError::BlockhashNotFound) => SendFailure::BlockhashNotFound,
+        Some(TransactionError::AccountInUse) => SendFailure::AccountInUse,
+        _ => SendFailure::Other,
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicI32, Ordering},
+};
+
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_sdk::transaction::TransactionError;
+
+use crate::{
+    send_and_confirm::INSUFFICIENT_BALANCE,
+    submit::{BLOCKHASH_EXPIRED, MAX_RETRIES},
+};
+
+/// Why the process exited, so supervisors like systemd or Kubernetes can decide whether
+/// restarting could help. Documented in the README.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitCode {
+    Success = 0,
+    /// Anything not covered below
+    Failure = 1,
+    /// Bad arguments, config file, keypair or address
+    Config = 2,
+    /// The RPC could not be reached
+    RpcUnreachable = 3,
+    /// A wallet or the fee payer can't cover the transaction
+    InsufficientFunds = 4,
+    /// A transaction didn't land within the retries or before its blockhash expired
+    MaxRetries = 5,
+    /// The user declined a confirmation prompt
+    UserAbort = 6,
+    /// Stopped by a second Ctrl-C or SIGTERM
+    Interrupted = 130,
+}
+
+impl From<&ClientError> for ExitCode {
+    fn from(err: &ClientError) -> Self {
+        match &err.kind {
+            ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) => {
+                return ExitCode::RpcUnreachable
+            }
+            ClientErrorKind::Custom(message)
+                if message.eq(MAX_RETRIES) || message.eq(BLOCKHASH_EXPIRED) =>
+            {
+                return ExitCode::MaxRetries
+            }
+            ClientErrorKind::Custom(message) if message.starts_with(INSUFFICIENT_BALANCE) => {
+                return ExitCode::InsufficientFunds
+            }
+            _ => {}
+        }
+        match err.get_transaction_error() {
+            Some(
+                TransactionError::InsufficientFundsForFee
+                | TransactionError::InsufficientFundsForRent { .. },
+            ) => ExitCode::InsufficientFunds,
+            _ => ExitCode::Failure,
+        }
+    }
+}
+
+/// The first failure recorded, which is what the process exits with.
+static STATUS: AtomicI32 = AtomicI32::new(ExitCode::Success as i32);
+
+/// Records why the command failed. The first failure wins, since later ones usually follow
+/// from it.
+pub fn fail(code: ExitCode) {
+    STATUS
+        .compare_exchange(
+            ExitCode::Success as i32,
+            code as i32,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        )
+        .ok();
+}
+
+/// Records a failed RPC request or send, by what went wrong.
+pub fn fail_with(err: &ClientError) {
+    fail(ExitCode::from(err));
+}
+
+/// Exits with the first failure recorded, or success.
+pub fn exit() -> ! {
+    std::process::exit(STATUS.load(Ordering::Relaxed))
+}
+
+/// Exits right away, for failures that leave nothing else worth doing.
+pub fn abort(code: ExitCode, message: impl Display) -> ! {
+    eprintln!("error: {}", message);
+    std::process::exit(code as i32)
+}
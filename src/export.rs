@@ -0,0 +1,115 @@
+use std::io::Write;
+
+use chrono::{TimeZone, Utc};
+use colored::*;
+use serde::Serialize;
+use solana_program::native_token::lamports_to_sol;
+use solana_sdk::signature::Signer;
+
+use crate::{
+    args::{ExportArgs, ExportFormat, ExportSource},
+    utils::amount_u64_to_f64,
+    Miner,
+};
+
+#[derive(Serialize)]
+struct EarningsRow {
+    timestamp: String,
+    wallet: String,
+    signature: String,
+    ore_reward: f64,
+    sol_cost: f64,
+}
+
+impl Miner {
+    pub async fn export(&self, args: ExportArgs) {
+        let rows = match args.source {
+            ExportSource::Ledger => self.ledger_rows(),
+            ExportSource::Chain => self.chain_rows(args.limit).await,
+        };
+        let Some(rows) = rows else {
+            return;
+        };
+
+        let output = match args.format {
+            ExportFormat::Json => serde_json::to_string_pretty(&rows).unwrap(),
+            ExportFormat::Csv => {
+                let mut csv = "timestamp,wallet,signature,ore_reward,sol_cost\n".to_string();
+                for row in rows.iter() {
+                    csv.push_str(&format!(
+                        "{},{},{},{},{}\n",
+                        row.timestamp, row.wallet, row.signature, row.ore_reward, row.sol_cost
+                    ));
+                }
+                csv
+            }
+        };
+        match args.output {
+            Some(path) => match std::fs::write(&path, output) {
+                Ok(()) => println!("Exported {} rows to {}", rows.len(), path),
+                Err(err) => println!("{} Failed to write {}: {}", "ERROR".bold().red(), path, err),
+            },
+            None => {
+                std::io::stdout().write_all(output.as_bytes()).ok();
+            }
+        }
+    }
+
+    /// One row per recorded pass; the ledger keeps rewards for the fleet as a whole.
+    fn ledger_rows(&self) -> Option<Vec<EarningsRow>> {
+        let Some(ledger) = &self.ledger else {
+            println!(
+                "{} No ledger configured. Pass --db <PATH>.",
+                "ERROR".bold().red()
+            );
+            return None;
+        };
+        let passes = ledger.passes(0, None, u64::MAX >> 1).ok()?;
+        Some(
+            passes
+                .into_iter()
+                .rev()
+                .map(|record| EarningsRow {
+                    timestamp: format_timestamp(Some(record.timestamp)),
+                    wallet: "fleet".to_string(),
+                    signature: record.signature.unwrap_or_default(),
+                    ore_reward: amount_u64_to_f64(record.reward),
+                    sol_cost: lamports_to_sol(record.tip + record.fees),
+                })
+                .collect(),
+        )
+    }
+
+    async fn chain_rows(&self, limit: usize) -> Option<Vec<EarningsRow>> {
+        let wallets = match self.folder_path {
+            Some(_) => self
+                .multi_signers()
+                .iter()
+                .map(|signer| signer.pubkey())
+                .collect(),
+            None => vec![self.signer().pubkey()],
+        };
+        let mut rows = vec![];
+        for wallet in wallets {
+            eprintln!("Scanning {}...", wallet);
+            for tx in self.scan_wallet(wallet, limit).await.into_iter().rev() {
+                rows.push(EarningsRow {
+                    timestamp: format_timestamp(tx.timestamp),
+                    wallet: wallet.to_string(),
+                    signature: tx.signature.to_string(),
+                    ore_reward: amount_u64_to_f64(tx.reward),
+                    sol_cost: lamports_to_sol(tx.fee_paid),
+                });
+            }
+        }
+        rows.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        Some(rows)
+    }
+}
+
+fn format_timestamp(timestamp: Option<i64>) -> String {
+    timestamp
+        .and_then(|ts| Utc.timestamp_opt(ts, 0).single())
+        .map(|t| t.to_rfc3339())
+        .unwrap_or_default()
+}
@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use colored::*;
+use ore_api::consts::BUS_ADDRESSES;
+use solana_program::native_token::sol_to_lamports;
+
+use crate::{args::SendMode, cu_limits::CU_LIMIT_MINE, tip_stats::tip_floor, Miner};
+
+/// How often to recheck fees while hashing is held off.
+const FEE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+impl Miner {
+    /// Why fees are too high to mine right now, if they are. The priority fee is the median
+    /// paid for the bus accounts over recent slots, priced at a mine transaction's compute
+    /// limit, and only counts when transactions go out on their own. Bundles pay no priority
+    /// fee, so through Jito the tip floor counts instead, along with `tip`, what each bundle
+    /// would tip.
+    pub async fn fees_over_ceiling(
+        &self,
+        ceiling: u64,
+        send_mode: &SendMode,
+        tip: u64,
+    ) -> Option<String> {
+        let mut priority_fee = None;
+        if !matches!(send_mode, SendMode::Jito) {
+            if let Ok(fees) = self
+                .rpc_client()
+                .get_recent_prioritization_fees(&BUS_ADDRESSES)
+                .await
+            {
+                priority_fee =
+                    median_priority_fee(fees.iter().map(|fee| fee.prioritization_fee).collect());
+            }
+        }
+        let mut floor = None;
+        if matches!(send_mode, SendMode::Jito | SendMode::Both) {
+            floor = tip_floor()
+                .await
+                .map(|floor| sol_to_lamports(floor.landed_tips_50th_percentile));
+        }
+        ceiling_breach(ceiling, send_mode, priority_fee, floor, tip)
+    }
+
+    /// Holds off until fees are back under the ceiling or mining is shutting down.
+    /// Only the network's fees count, as waiting can't bring down the tip mining pays.
+    pub async fn wait_for_calm_fees(&self, ceiling: u64, send_mode: &SendMode) {
+        let Some(reason) = self.fees_over_ceiling(ceiling, send_mode, 0).await else {
+            return;
+        };
+        println!("{} {}. Pausing.", "NOTICE".bold().cyan(), reason);
+        self.control.stats().timeline.annotate(reason);
+        while !self.control.is_shutting_down() {
+            // Waiting on fees isn't a stall
+            self.control.heartbeat();
+            tokio::time::sleep(FEE_POLL_INTERVAL).await;
+            if self
+                .fees_over_ceiling(ceiling, send_mode, 0)
+                .await
+                .is_none()
+            {
+                let event = "Fees back under the ceiling, resuming".to_string();
+                println!("{} {}", "NOTICE".bold().cyan(), event);
+                self.control.stats().timeline.annotate(event);
+                return;
+            }
+        }
+    }
+}
+
+/// The median of recent priority fee prices, in lamports for a mine transaction.
+fn median_priority_fee(mut prices: Vec<u64>) -> Option<u64> {
+    if prices.is_empty() {
+        return None;
+    }
+    prices.sort();
+    Some(prices[prices.len() / 2] * CU_LIMIT_MINE as u64 / 1_000_000)
+}
+
+/// Which of the fees that count under `send_mode` is above the ceiling, if any.
+fn ceiling_breach(
+    ceiling: u64,
+    send_mode: &SendMode,
+    priority_fee: Option<u64>,
+    tip_floor: Option<u64>,
+    tip: u64,
+) -> Option<String> {
+    if !matches!(send_mode, SendMode::Jito) {
+        if let Some(lamports) = priority_fee.filter(|lamports| lamports.gt(&ceiling)) {
+            return Some(format!(
+                "Network priority fee {} lamports is above the {} lamport ceiling",
+                lamports, ceiling
+            ));
+        }
+    }
+    if matches!(send_mode, SendMode::Jito | SendMode::Both) {
+        if let Some(lamports) = tip_floor.filter(|lamports| lamports.gt(&ceiling)) {
+            return Some(format!(
+                "Jito tip floor {} lamports is above the {} lamport ceiling",
+                lamports, ceiling
+            ));
+        }
+        if tip.gt(&ceiling) {
+            return Some(format!(
+                "Jito tip {} lamports is above the {} lamport ceiling",
+                tip, ceiling
+            ));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prices_the_median_at_the_mine_limit() {
+        assert_eq!(median_priority_fee(vec![]), None);
+        let price = 1_000_000;
+        assert_eq!(
+            median_priority_fee(vec![5 * price, 0, price]),
+            Some(CU_LIMIT_MINE as u64)
+        );
+    }
+
+    #[test]
+    fn rpc_only_counts_the_priority_fee() {
+        assert!(ceiling_breach(100, &SendMode::Rpc, Some(100), Some(500), 500).is_none());
+        let reason = ceiling_breach(100, &SendMode::Rpc, Some(101), None, 0).unwrap();
+        assert!(reason.starts_with("Network priority fee 101"));
+    }
+
+    #[test]
+    fn jito_only_counts_the_tips() {
+        assert!(ceiling_breach(100, &SendMode::Jito, Some(500), Some(100), 100).is_none());
+        let reason = ceiling_breach(100, &SendMode::Jito, None, Some(101), 0).unwrap();
+        assert!(reason.starts_with("Jito tip floor 101"));
+        let reason = ceiling_breach(100, &SendMode::Jito, None, None, 101).unwrap();
+        assert!(reason.starts_with("Jito tip 101"));
+    }
+
+    #[test]
+    fn both_counts_every_fee() {
+        assert!(ceiling_breach(100, &SendMode::Both, Some(100), Some(100), 100).is_none());
+        assert!(ceiling_breach(100, &SendMode::Both, Some(101), None, 0).is_some());
+        assert!(ceiling_breach(100, &SendMode::Both, None, Some(101), 0).is_some());
+        assert!(ceiling_breach(100, &SendMode::Both, None, None, 101).is_some());
+    }
+}
@@ -0,0 +1,137 @@
+use std::str::FromStr;
+
+use chrono::{Local, TimeZone};
+use colored::*;
+use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
+use solana_sdk::signature::Signer;
+
+use crate::{
+    args::HistoryArgs,
+    exit::{abort, ExitCode},
+    labels::label,
+    utils::amount_u64_to_string,
+    Miner,
+};
+
+impl Miner {
+    pub async fn history(&self, args: HistoryArgs) {
+        if args.lifetime {
+            self.lifetime_history(args).await;
+            return;
+        }
+        let Some(ledger) = &self.ledger else {
+            println!(
+                "{} No ledger configured. Pass --db <PATH>.",
+                "ERROR".bold().red()
+            );
+            return;
+        };
+        let since = match args.hours {
+            Some(hours) => chrono::Utc::now().timestamp() - hours as i64 * 3600,
+            None => 0,
+        };
+        let wallet = args.wallet.as_deref();
+
+        let passes = match ledger.passes(since, wallet, args.limit) {
+            Ok(passes) => passes,
+            Err(err) => {
+                println!("{} {}", "ERROR".bold().red(), err);
+                return;
+            }
+        };
+        for record in passes.iter().rev() {
+            let time = Local
+                .timestamp_opt(record.timestamp, 0)
+                .single()
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default();
+            println!(
+                "{} pass {:>5} {} difficulty {:>2} reward {} ORE {}",
+                time,
+                record.pass,
+                if record.landed {
+                    "landed".green()
+                } else {
+                    "missed".red()
+                },
+                record.best_difficulty,
+                amount_u64_to_string(record.reward),
+                record.signature.clone().unwrap_or_default().dimmed(),
+            );
+        }
+
+        if let Ok(totals) = ledger.totals(since, wallet) {
+            println!(
+                "\n{}: {} passes, {} landed\n{}: {:.2}\n{}: {} ORE\n{}: {} SOL in fees, {} SOL in tips",
+                "Passes".bold(),
+                totals.passes,
+                totals.landed,
+                "Average difficulty".bold(),
+                totals.average_difficulty,
+                "Earned".bold(),
+                amount_u64_to_string(totals.reward),
+                "Spent".bold(),
+                lamports_to_sol(totals.fees),
+                lamports_to_sol(totals.tips),
+            );
+        }
+    }
+
+    /// Walks each wallet's whole proof history on chain, so earnings can be reported
+    /// without a ledger.
+    async fn lifetime_history(&self, args: HistoryArgs) {
+        let wallets: Vec<Pubkey> = match (&args.wallet, &self.folder_path) {
+            (Some(wallet), _) => {
+                vec![Pubkey::from_str(wallet)
+                    .unwrap_or_else(|_| abort(ExitCode::Config, "Failed to parse wallet address"))]
+            }
+            (None, Some(_)) => self
+                .multi_signers()
+                .iter()
+                .map(|signer| signer.pubkey())
+                .collect(),
+            (None, None) => vec![self.signer().pubkey()],
+        };
+
+        println!(
+            "{:<44} {:>8} {:>16} {:>16} {:>10}",
+            "Wallet".bold(),
+            "Mines".bold(),
+            "Mined".bold(),
+            "Claimed".bold(),
+            "Avg diff".bold()
+        );
+        let (mut mines, mut mined, mut claimed, mut difficulty_sum) = (0u64, 0u64, 0u64, 0u64);
+        for wallet in wallets.iter() {
+            let scanned = self.scan_wallet(*wallet, usize::MAX).await;
+            let difficulties: Vec<u64> = scanned.iter().filter_map(|tx| tx.difficulty).collect();
+            let wallet_mined: u64 = scanned.iter().map(|tx| tx.reward).sum();
+            let wallet_claimed: u64 = scanned.iter().map(|tx| tx.claimed).sum();
+            let wallet_difficulty: u64 = difficulties.iter().sum();
+            println!(
+                "{:<44} {:>8} {:>12} ORE {:>12} ORE {:>10.2}",
+                label(wallet),
+                difficulties.len(),
+                amount_u64_to_string(wallet_mined),
+                amount_u64_to_string(wallet_claimed),
+                wallet_difficulty as f64 / difficulties.len().max(1) as f64
+            );
+            mines += difficulties.len() as u64;
+            mined += wallet_mined;
+            claimed += wallet_claimed;
+            difficulty_sum += wallet_difficulty;
+        }
+
+        println!(
+            "\n{}: {}\n{}: {:.2}\n{}: {} ORE\n{}: {} ORE",
+            "Mines".bold(),
+            mines,
+            "Average difficulty".bold(),
+            difficulty_sum as f64 / mines.max(1) as f64,
+            "Mined".bold(),
+            amount_u64_to_string(mined),
+            "Claimed".bold(),
+            amount_u64_to_string(claimed),
+        );
+    }
+}
@@ -6,12 +6,17 @@ use crate::Miner;
 impl Miner {
     pub async fn initialize(&self) {
         // Return early if program is already initialized
-        if self.rpc_client.get_account(&TREASURY_ADDRESS).await.is_ok() {
+        if self
+            .rpc_client()
+            .get_account(&TREASURY_ADDRESS)
+            .await
+            .is_ok()
+        {
             return;
         }
 
         // Submit initialize tx
-        let blockhash = self.rpc_client.get_latest_blockhash().await.unwrap();
+        let blockhash = self.rpc_client().get_latest_blockhash().await.unwrap();
         let ix = ore_api::instruction::initialize(self.signer().pubkey());
         let tx = Transaction::new_signed_with_payer(
             &[ix],
@@ -19,7 +24,7 @@ impl Miner {
             &[&self.signer()],
             blockhash,
         );
-        let res = self.rpc_client.send_and_confirm_transaction(&tx).await;
+        let res = self.rpc_client().send_and_confirm_transaction(&tx).await;
         println!("{:?}", res);
     }
 }
@@ -1,7 +1,6 @@
-use solana_sdk::pubkey::Pubkey;
-use solana_sdk::pubkey;
 use crate::Miner;
-
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
 
 /// The number of bus accounts, for parallelizing mine operations.
 pub const JITO_COUNT: usize = 8;
@@ -22,4 +21,4 @@ impl Miner {
     pub fn get_jito_tip_account(&self, id: usize) -> Pubkey {
         JITO_TIP_ACCOUNTS[id]
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,184 @@
+use std::{
+    collections::BTreeMap,
+    io::{IsTerminal, Read},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use serde_json::Value;
+use solana_sdk::signature::{keypair_from_seed, Keypair};
+
+use crate::vault;
+
+/// Prefix of keypair sources read from an environment variable, as in `env:ORE_KEY_1`.
+const ENV_PREFIX: &str = "env:";
+
+/// Prefix marking a 32-byte seed rather than a full keypair, as in `seed:[1,2,...]`.
+const SEED_PREFIX: &str = "seed:";
+
+/// Source that reads a keypair, or for `--folder-path` a JSON map of them, from stdin.
+pub const STDIN: &str = "-";
+
+/// What was piped on stdin, kept since it can only be read once.
+static STDIN_TEXT: OnceLock<Result<String, String>> = OnceLock::new();
+
+fn read_stdin() -> Result<&'static str, String> {
+    STDIN_TEXT
+        .get_or_init(|| {
+            if std::io::stdin().is_terminal() {
+                return Err("nothing piped on stdin".to_string());
+            }
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|err| err.to_string())?;
+            Ok(text)
+        })
+        .as_deref()
+        .map_err(|err| format!("stdin: {}", err))
+}
+
+/// Reads a keypair from `source`: `-` for stdin, `env:NAME` for an environment variable,
+/// `vault:PATH` for a secret fetched from Vault, or a file. Secrets are never taken from the
+/// command line itself, where other users can see them.
+pub fn read_keypair(source: &str) -> Result<Keypair, String> {
+    if source.eq(STDIN) {
+        return parse_keypair(read_stdin()?).map_err(|err| format!("stdin: {}", err));
+    }
+    if let Some(path) = source.strip_prefix(vault::PREFIX) {
+        return vault::keypair(path);
+    }
+    match source.strip_prefix(ENV_PREFIX) {
+        Some(name) => {
+            let text = std::env::var(name).map_err(|_| format!("{}: not set", source))?;
+            parse_keypair(&text).map_err(|err| format!("{}: {}", source, err))
+        }
+        None => read_keypair_file(Path::new(source)),
+    }
+}
+
+/// Reads a keypair file in any format `parse_keypair` accepts.
+pub fn read_keypair_file(path: &Path) -> Result<Keypair, String> {
+    std::fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|text| parse_keypair(&text))
+        .map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+/// Parses a keypair as a JSON byte array like the Solana CLI writes, or base58, of the 64-byte
+/// keypair, or of its 32-byte seed after `seed:`. A bare 32-byte value is refused, as it is
+/// most likely a public address pasted in place of the keypair.
+pub fn parse_keypair(text: &str) -> Result<Keypair, String> {
+    let text = text.trim();
+    if let Some(seed) = text.strip_prefix(SEED_PREFIX) {
+        let bytes = parse_bytes(seed.trim())?;
+        return match bytes.len() {
+            32 => keypair_from_seed(&bytes).map_err(|err| err.to_string()),
+            len => Err(format!("expected a 32-byte seed, found {} bytes", len)),
+        };
+    }
+    let bytes = parse_bytes(text)?;
+    match bytes.len() {
+        64 => Keypair::from_bytes(&bytes).map_err(|err| err.to_string()),
+        32 => Err(format!(
+            "32 bytes looks like a public key, not a keypair; prefix a seed with `{}`",
+            SEED_PREFIX
+        )),
+        len => Err(format!("expected a 64-byte keypair, found {} bytes", len)),
+    }
+}
+
+/// Decodes a JSON byte array or base58.
+fn parse_bytes(text: &str) -> Result<Vec<u8>, String> {
+    let bytes = match text.starts_with('[') {
+        true => serde_json::from_str::<Vec<u8>>(text)
+            .map_err(|err| format!("not a JSON byte array: {}", err))?,
+        false => bs58::decode(text)
+            .into_vec()
+            .map_err(|_| "neither a JSON byte array nor base58".to_string())?,
+    };
+    Ok(bytes)
+}
+
+/// Reads the folder wallets from a JSON object piped on stdin, of names to keypairs in any
+/// format `parse_keypair` accepts. Names stand in for file names, in name order.
+pub fn read_stdin_keypairs(keypairs: &mut Vec<(PathBuf, Keypair)>, errors: &mut Vec<String>) {
+    let entries = match read_stdin().and_then(|text| {
+        serde_json::from_str::<BTreeMap<String, Value>>(text)
+            .map_err(|err| format!("stdin: not a JSON object of keypairs: {}", err))
+    }) {
+        Ok(entries) => entries,
+        Err(err) => {
+            errors.push(err);
+            return;
+        }
+    };
+    for (name, value) in entries {
+        let text = match value {
+            Value::String(text) => text,
+            value => value.to_string(),
+        };
+        match parse_keypair(&text) {
+            Ok(keypair) => keypairs.push((PathBuf::from(name), keypair)),
+            Err(err) => errors.push(format!("stdin {}: {}", name, err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signature::Signer;
+
+    use super::*;
+
+    #[test]
+    fn parses_json_keypair() {
+        let keypair = Keypair::new();
+        let text = serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap();
+        let parsed = parse_keypair(&format!("{}\n", text)).unwrap();
+        assert_eq!(parsed.pubkey(), keypair.pubkey());
+    }
+
+    #[test]
+    fn parses_base58_keypair() {
+        let keypair = Keypair::new();
+        let parsed = parse_keypair(&keypair.to_base58_string()).unwrap();
+        assert_eq!(parsed.pubkey(), keypair.pubkey());
+    }
+
+    #[test]
+    fn parses_prefixed_seed() {
+        let seed = [7u8; 32];
+        let expected = keypair_from_seed(&seed).unwrap();
+        let json = serde_json::to_string(&seed.to_vec()).unwrap();
+        let from_json = parse_keypair(&format!("seed:{}", json)).unwrap();
+        let from_base58 =
+            parse_keypair(&format!("seed:{}", bs58::encode(seed).into_string())).unwrap();
+        assert_eq!(from_json.pubkey(), expected.pubkey());
+        assert_eq!(from_base58.pubkey(), expected.pubkey());
+    }
+
+    #[test]
+    fn rejects_bare_public_key() {
+        let address = Keypair::new().pubkey().to_string();
+        let err = parse_keypair(&address).unwrap_err();
+        assert!(err.contains("public key"), "{}", err);
+        let json = serde_json::to_string(&[7u8; 32].to_vec()).unwrap();
+        assert!(parse_keypair(&json).is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_length() {
+        let err = parse_keypair("[1, 2, 3]").unwrap_err();
+        assert!(err.contains("found 3 bytes"), "{}", err);
+        let err = parse_keypair("seed:[1, 2, 3]").unwrap_err();
+        assert!(err.contains("32-byte seed"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(parse_keypair("not a key!").is_err());
+        assert!(parse_keypair("[1, 2,").is_err());
+        assert!(parse_keypair("[256]").is_err());
+    }
+}
@@ -0,0 +1,33 @@
+use std::{collections::HashMap, str::FromStr, sync::OnceLock};
+
+use solana_program::pubkey::Pubkey;
+
+/// Names for wallets from `--labels`, shown wherever output names a wallet.
+static LABELS: OnceLock<HashMap<Pubkey, String>> = OnceLock::new();
+
+/// Reads a JSON object of wallet addresses to names, e.g.
+/// `{ "9xQe...": "hetzner-3/worker-7" }`.
+pub fn load(path: &str) -> Result<(), String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read labels file {}: {}", path, err))?;
+    let entries: HashMap<String, String> = serde_json::from_str(&json)
+        .map_err(|err| format!("Failed to parse labels file {}: {}", path, err))?;
+    let labels = entries
+        .into_iter()
+        .map(|(address, name)| {
+            Pubkey::from_str(address.trim())
+                .map(|wallet| (wallet, name))
+                .map_err(|_| format!("invalid wallet address `{}` in {}", address, path))
+        })
+        .collect::<Result<_, _>>()?;
+    LABELS.set(labels).ok();
+    Ok(())
+}
+
+/// The wallet's name from `--labels`, or its address when it has none.
+pub fn label(wallet: &Pubkey) -> String {
+    LABELS
+        .get()
+        .and_then(|labels| labels.get(wallet).cloned())
+        .unwrap_or_else(|| wallet.to_string())
+}
@@ -0,0 +1,157 @@
+use std::time::{Duration, Instant};
+
+use colored::*;
+use serde_json::json;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{clock::MAX_PROCESSING_AGE, commitment_config::CommitmentConfig};
+
+use crate::{args::BenchmarkArgs, Miner};
+
+/// Jito block engine regions, by subdomain.
+const JITO_REGIONS: [&str; 6] = ["mainnet", "amsterdam", "frankfurt", "ny", "slc", "tokyo"];
+
+/// Give up on an endpoint that takes longer than this to answer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Round trip statistics for one endpoint, in milliseconds.
+struct Latency {
+    min: f64,
+    median: f64,
+    max: f64,
+    failures: usize,
+}
+
+impl Latency {
+    fn from_samples(mut samples: Vec<f64>, failures: usize) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_by(|a, b| a.total_cmp(b));
+        Some(Latency {
+            min: samples[0],
+            median: samples[samples.len() / 2],
+            max: samples[samples.len() - 1],
+            failures,
+        })
+    }
+}
+
+impl Miner {
+    pub async fn benchmark_network(&self, args: BenchmarkArgs) {
+        let samples = args.samples.max(1);
+
+        // RPCs, with how stale the blockhash they hand out is
+        let mut rpc_urls = vec![self.rpc_client().url()];
+        rpc_urls.extend(args.compare_rpcs);
+        println!(
+            "{:<52} {:>8} {:>8} {:>8} {:>10} {:>6}",
+            "RPC".bold(),
+            "min".bold(),
+            "median".bold(),
+            "max".bold(),
+            "hash lag".bold(),
+            "fails".bold()
+        );
+        for url in rpc_urls {
+            let client = RpcClient::new_with_timeout_and_commitment(
+                url.clone(),
+                REQUEST_TIMEOUT,
+                CommitmentConfig::confirmed(),
+            );
+            let (latency, lag) = rpc_latency(&client, samples).await;
+            print_latency(&url, latency, lag.map(|lag| format!("{} blocks", lag)));
+        }
+
+        // Jito block engines
+        println!(
+            "\n{:<52} {:>8} {:>8} {:>8} {:>10} {:>6}",
+            "Jito block engine".bold(),
+            "min".bold(),
+            "median".bold(),
+            "max".bold(),
+            "",
+            "fails".bold()
+        );
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("Failed to build http client");
+        for region in JITO_REGIONS {
+            let url = format!(
+                "https://{}.mainnet.block-engine.jito.wtf/api/v1/bundles",
+                region
+            );
+            let latency = jito_latency(&client, &url, samples).await;
+            print_latency(&url, latency, None);
+        }
+        println!("\nPass the fastest block engine as --jito-url and the fastest RPC as --rpc.");
+    }
+}
+
+/// Times `getSlot` round trips, then measures how many blocks behind the tip the
+/// RPC's latest blockhash is.
+async fn rpc_latency(client: &RpcClient, samples: usize) -> (Option<Latency>, Option<u64>) {
+    let mut times = vec![];
+    let mut failures = 0;
+    for _ in 0..samples {
+        let timer = Instant::now();
+        match client.get_slot().await {
+            Ok(_) => times.push(timer.elapsed().as_secs_f64() * 1000.0),
+            Err(_) => failures += 1,
+        }
+    }
+
+    // A blockhash is valid for MAX_PROCESSING_AGE blocks, so the height it expires at
+    // tells how old it was when handed out
+    let lag = match (
+        client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await,
+        client
+            .get_block_height_with_commitment(CommitmentConfig::processed())
+            .await,
+    ) {
+        (Ok((_, last_valid_block_height)), Ok(block_height)) => {
+            Some((block_height + MAX_PROCESSING_AGE as u64).saturating_sub(last_valid_block_height))
+        }
+        _ => None,
+    };
+    (Latency::from_samples(times, failures), lag)
+}
+
+/// Times `getTipAccounts` round trips, a cheap call every block engine answers.
+async fn jito_latency(client: &reqwest::Client, url: &str, samples: usize) -> Option<Latency> {
+    let payload = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "getTipAccounts",
+        "params": [],
+    });
+    let mut times = vec![];
+    let mut failures = 0;
+    for _ in 0..samples {
+        let timer = Instant::now();
+        let res = client.post(url).json(&payload).send().await;
+        match res.and_then(|res| res.error_for_status()) {
+            Ok(_) => times.push(timer.elapsed().as_secs_f64() * 1000.0),
+            Err(_) => failures += 1,
+        }
+    }
+    Latency::from_samples(times, failures)
+}
+
+fn print_latency(endpoint: &str, latency: Option<Latency>, extra: Option<String>) {
+    let Some(latency) = latency else {
+        println!("{:<52} {}", endpoint, "unreachable".red());
+        return;
+    };
+    println!(
+        "{:<52} {:>6.0}ms {:>6.0}ms {:>6.0}ms {:>10} {:>6}",
+        endpoint,
+        latency.min,
+        latency.median,
+        latency.max,
+        extra.unwrap_or_default(),
+        latency.failures
+    );
+}
@@ -0,0 +1,99 @@
+use std::{collections::BTreeSet, time::Duration};
+
+use colored::*;
+use serde::Deserialize;
+use solana_sdk::clock::DEFAULT_MS_PER_SLOT;
+
+use crate::Miner;
+
+/// Validators known to the Jito stake pool, with whether they run the Jito client.
+const JITO_VALIDATORS_URL: &str = "https://kobe.mainnet.jito.network/api/v1/validators";
+
+/// Slots ahead of the leader's first slot to submit, so the bundle is already waiting.
+const LEAD_SLOTS: u64 = 2;
+
+#[derive(Deserialize)]
+struct JitoValidator {
+    vote_account: String,
+    running_jito: bool,
+}
+
+#[derive(Deserialize)]
+struct JitoValidators {
+    validators: Vec<JitoValidator>,
+}
+
+/// The slots of the current epoch led by a Jito-enabled validator.
+pub struct JitoLeaderSchedule {
+    pub epoch: u64,
+    slots: BTreeSet<u64>,
+}
+
+impl JitoLeaderSchedule {
+    /// The first Jito-led slot at or after `slot`.
+    pub fn next_slot(&self, slot: u64) -> Option<u64> {
+        self.slots.range(slot..).next().copied()
+    }
+}
+
+impl Miner {
+    pub async fn jito_leader_schedule(&self) -> Option<JitoLeaderSchedule> {
+        // Jito reports vote accounts; the leader schedule is keyed by node identity
+        let validators = reqwest::get(JITO_VALIDATORS_URL)
+            .await
+            .ok()?
+            .json::<JitoValidators>()
+            .await
+            .ok()?;
+        let jito_votes: BTreeSet<String> = validators
+            .validators
+            .into_iter()
+            .filter(|validator| validator.running_jito)
+            .map(|validator| validator.vote_account)
+            .collect();
+        let vote_accounts = self.rpc_client().get_vote_accounts().await.ok()?;
+        let jito_identities: BTreeSet<String> = vote_accounts
+            .current
+            .into_iter()
+            .filter(|account| jito_votes.contains(&account.vote_pubkey))
+            .map(|account| account.node_pubkey)
+            .collect();
+
+        let epoch_info = self.rpc_client().get_epoch_info().await.ok()?;
+        let first_slot = epoch_info.absolute_slot - epoch_info.slot_index;
+        let schedule = self.rpc_client().get_leader_schedule(None).await.ok()??;
+        let slots = schedule
+            .into_iter()
+            .filter(|(identity, _)| jito_identities.contains(identity))
+            .flat_map(|(_, slots)| slots.into_iter().map(move |i| first_slot + i as u64))
+            .collect();
+        Some(JitoLeaderSchedule {
+            epoch: epoch_info.epoch,
+            slots,
+        })
+    }
+
+    /// Sleeps until just before the next Jito leader's slot, if that is within `max_wait`.
+    pub async fn wait_for_jito_leader(&self, schedule: &JitoLeaderSchedule, max_wait: Duration) {
+        let Ok(slot) = self.rpc_client().get_slot().await else {
+            return;
+        };
+        let Some(leader_slot) = schedule.next_slot(slot + LEAD_SLOTS) else {
+            return;
+        };
+        let wait = Duration::from_millis((leader_slot - slot - LEAD_SLOTS) * DEFAULT_MS_PER_SLOT);
+        if wait.is_zero() {
+            return;
+        }
+        if wait.gt(&max_wait) {
+            println!(
+                "{} Next Jito leader is {:?} away. Submitting now.",
+                "NOTICE".bold().cyan(),
+                wait
+            );
+            return;
+        }
+        println!("Waiting {:?} for Jito leader at slot {}", wait, leader_slot);
+        tokio::time::sleep(wait).await;
+    }
+}
@@ -0,0 +1,186 @@
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection};
+
+use crate::notify::PassSummary;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS passes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pass INTEGER NOT NULL,
+    timestamp INTEGER NOT NULL,
+    bus TEXT NOT NULL,
+    signature TEXT,
+    landed INTEGER NOT NULL,
+    reward INTEGER NOT NULL,
+    tip INTEGER NOT NULL,
+    fees INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS solutions (
+    pass_id INTEGER NOT NULL REFERENCES passes(id),
+    wallet TEXT NOT NULL,
+    difficulty INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS solutions_wallet ON solutions(wallet);
+CREATE TABLE IF NOT EXISTS transactions (
+    signature TEXT PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    fee INTEGER NOT NULL,
+    tip INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS transactions_timestamp ON transactions(timestamp);
+";
+
+/// Local SQLite record of every mining pass.
+pub struct Ledger {
+    conn: Mutex<Connection>,
+}
+
+/// One row of the `passes` table.
+pub struct PassRecord {
+    pub pass: u64,
+    pub timestamp: i64,
+    pub signature: Option<String>,
+    pub landed: bool,
+    pub reward: u64,
+    pub tip: u64,
+    pub fees: u64,
+    pub best_difficulty: u32,
+}
+
+#[derive(Default)]
+pub struct LedgerTotals {
+    pub passes: u64,
+    pub landed: u64,
+    pub reward: u64,
+    pub tips: u64,
+    pub fees: u64,
+    pub average_difficulty: f64,
+}
+
+impl Ledger {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn record_pass(&self, summary: &PassSummary, fees: u64) -> rusqlite::Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO passes (pass, timestamp, bus, signature, landed, reward, tip, fees)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                summary.pass as i64,
+                chrono::Utc::now().timestamp(),
+                summary.bus.to_string(),
+                summary.signature.map(|sig| sig.to_string()),
+                summary.signature.is_some(),
+                summary.reward as i64,
+                summary.tip as i64,
+                fees as i64,
+            ],
+        )?;
+        let pass_id = tx.last_insert_rowid();
+        for (wallet, difficulty) in summary.difficulties.iter() {
+            tx.execute(
+                "INSERT INTO solutions (pass_id, wallet, difficulty) VALUES (?1, ?2, ?3)",
+                params![pass_id, wallet.to_string(), difficulty],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// Records the fee and tip a landed transaction paid, as read back from the chain.
+    pub fn record_transaction(&self, signature: &str, fee: u64, tip: u64) -> rusqlite::Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT OR IGNORE INTO transactions (signature, timestamp, fee, tip)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                signature,
+                chrono::Utc::now().timestamp(),
+                fee as i64,
+                tip as i64
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Timestamp and lamports spent of each transaction since `since` (unix seconds), oldest first.
+    pub fn spends(&self, since: i64) -> rusqlite::Result<Vec<(i64, u64)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT timestamp, fee + tip FROM transactions WHERE timestamp >= ?1 ORDER BY timestamp",
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok((row.get(0)?, row.get::<_, i64>(1)? as u64))
+        })?;
+        rows.collect()
+    }
+
+    /// Passes since `since` (unix seconds), newest first, optionally only those a wallet took part in.
+    pub fn passes(
+        &self,
+        since: i64,
+        wallet: Option<&str>,
+        limit: u64,
+    ) -> rusqlite::Result<Vec<PassRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT p.pass, p.timestamp, p.signature, p.landed, p.reward, p.tip, p.fees,
+                    COALESCE(MAX(s.difficulty), 0)
+             FROM passes p LEFT JOIN solutions s ON s.pass_id = p.id
+             WHERE p.timestamp >= ?1
+               AND (?2 IS NULL OR p.id IN (SELECT pass_id FROM solutions WHERE wallet = ?2))
+             GROUP BY p.id
+             ORDER BY p.id DESC
+             LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(params![since, wallet, limit as i64], |row| {
+            Ok(PassRecord {
+                pass: row.get::<_, i64>(0)? as u64,
+                timestamp: row.get(1)?,
+                signature: row.get(2)?,
+                landed: row.get(3)?,
+                reward: row.get::<_, i64>(4)? as u64,
+                tip: row.get::<_, i64>(5)? as u64,
+                fees: row.get::<_, i64>(6)? as u64,
+                best_difficulty: row.get(7)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn totals(&self, since: i64, wallet: Option<&str>) -> rusqlite::Result<LedgerTotals> {
+        let conn = self.conn.lock().unwrap();
+        let mut totals = conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(landed), 0), COALESCE(SUM(reward), 0),
+                    COALESCE(SUM(tip), 0), COALESCE(SUM(fees), 0)
+             FROM passes
+             WHERE timestamp >= ?1
+               AND (?2 IS NULL OR id IN (SELECT pass_id FROM solutions WHERE wallet = ?2))",
+            params![since, wallet],
+            |row| {
+                Ok(LedgerTotals {
+                    passes: row.get::<_, i64>(0)? as u64,
+                    landed: row.get::<_, i64>(1)? as u64,
+                    reward: row.get::<_, i64>(2)? as u64,
+                    tips: row.get::<_, i64>(3)? as u64,
+                    fees: row.get::<_, i64>(4)? as u64,
+                    average_difficulty: 0.0,
+                })
+            },
+        )?;
+        totals.average_difficulty = conn.query_row(
+            "SELECT COALESCE(AVG(s.difficulty), 0.0)
+             FROM solutions s JOIN passes p ON s.pass_id = p.id
+             WHERE p.timestamp >= ?1 AND (?2 IS NULL OR s.wallet = ?2)",
+            params![since, wallet],
+            |row| row.get(0),
+        )?;
+        Ok(totals)
+    }
+}
@@ -0,0 +1,163 @@
+use std::str::FromStr;
+
+use colored::*;
+use ore_api::consts::{
+    BUS_ADDRESSES, CONFIG_ADDRESS, MINT_ADDRESS, TREASURY_ADDRESS, TREASURY_TOKENS_ADDRESS,
+};
+use solana_program::{pubkey::Pubkey, sysvar};
+use solana_sdk::{
+    address_lookup_table::{
+        instruction::{create_lookup_table, extend_lookup_table},
+        state::AddressLookupTable,
+        AddressLookupTableAccount,
+    },
+    commitment_config::CommitmentConfig,
+    signature::Signer,
+};
+
+use crate::{
+    args::{LookupTableArgs, LookupTableCommand, LookupTableExtendArgs},
+    exit::{abort, fail_with, ExitCode},
+    utils::proof_pubkey,
+    Miner,
+};
+
+/// Addresses per extend transaction, to stay under the packet size.
+const EXTEND_CHUNK: usize = 20;
+
+impl Miner {
+    pub async fn lookup_table(&self, args: LookupTableArgs) {
+        match args.command {
+            LookupTableCommand::Create(_) => self.lookup_table_create().await,
+            LookupTableCommand::Extend(args) => self.lookup_table_extend(args).await,
+        }
+    }
+
+    async fn lookup_table_create(&self) {
+        let fee_payer = self.fee_payer();
+        let Ok(slot) = self
+            .rpc_client()
+            .get_slot_with_commitment(CommitmentConfig::finalized())
+            .await
+        else {
+            println!("{} Failed to fetch slot", "ERROR".bold().red());
+            return;
+        };
+        let (ix, address) = create_lookup_table(fee_payer.pubkey(), fee_payer.pubkey(), slot);
+        if let Err(err) = self
+            .send_and_confirm_with_key(&[ix], false, &fee_payer)
+            .await
+        {
+            fail_with(&err);
+            return;
+        }
+        println!("Lookup table {} created", address);
+        if self.sync_lookup_table(address).await {
+            println!("Mine with --lookup-table {} to use it", address);
+        }
+    }
+
+    async fn lookup_table_extend(&self, args: LookupTableExtendArgs) {
+        let address = match args.address {
+            Some(address) => Pubkey::from_str(&address)
+                .unwrap_or_else(|_| abort(ExitCode::Config, "Failed to parse table address")),
+            None => match self.lookup_table {
+                Some(address) => address,
+                None => {
+                    println!(
+                        "{} No lookup table given. Pass an address or --lookup-table.",
+                        "ERROR".bold().red()
+                    );
+                    return;
+                }
+            },
+        };
+        self.sync_lookup_table(address).await;
+    }
+
+    /// Accounts every bundle touches. Wallets sign their mine instructions so they can't
+    /// be looked up, but their proofs can.
+    fn fleet_accounts(&self) -> Vec<Pubkey> {
+        let mut accounts = BUS_ADDRESSES.to_vec();
+        accounts.extend([
+            CONFIG_ADDRESS,
+            MINT_ADDRESS,
+            TREASURY_ADDRESS,
+            TREASURY_TOKENS_ADDRESS,
+            spl_token::id(),
+            sysvar::instructions::id(),
+            sysvar::slot_hashes::id(),
+        ]);
+        accounts.extend(
+            self.multi_signers()
+                .iter()
+                .map(|signer| proof_pubkey(signer.pubkey())),
+        );
+        accounts
+    }
+
+    /// Extends the table with any fleet accounts it is missing, such as proofs of wallets
+    /// added to the folder since it was created. Returns whether the table is complete.
+    pub async fn sync_lookup_table(&self, address: Pubkey) -> bool {
+        let existing = match self.get_lookup_table(address).await {
+            Some(table) => table.addresses,
+            None => {
+                println!(
+                    "{} Failed to fetch lookup table {}",
+                    "ERROR".bold().red(),
+                    address
+                );
+                return false;
+            }
+        };
+        let missing: Vec<Pubkey> = self
+            .fleet_accounts()
+            .into_iter()
+            .filter(|account| !existing.contains(account))
+            .collect();
+        if missing.is_empty() {
+            println!("Lookup table {} is up to date", address);
+            return true;
+        }
+
+        let fee_payer = self.fee_payer();
+        for chunk in missing.chunks(EXTEND_CHUNK) {
+            let ix = extend_lookup_table(
+                address,
+                fee_payer.pubkey(),
+                Some(fee_payer.pubkey()),
+                chunk.to_vec(),
+            );
+            if let Err(err) = self
+                .send_and_confirm_with_key(&[ix], false, &fee_payer)
+                .await
+            {
+                fail_with(&err);
+                return false;
+            }
+        }
+        println!(
+            "Added {} accounts to lookup table {}",
+            missing.len(),
+            address
+        );
+        true
+    }
+
+    pub async fn get_lookup_table(&self, address: Pubkey) -> Option<AddressLookupTableAccount> {
+        let account = self.rpc_client().get_account(&address).await.ok()?;
+        let table = AddressLookupTable::deserialize(&account.data).ok()?;
+        Some(AddressLookupTableAccount {
+            key: address,
+            addresses: table.addresses.to_vec(),
+        })
+    }
+
+    /// The configured lookup table, if any, ready to compile transactions against.
+    pub async fn lookup_tables(&self) -> Vec<AddressLookupTableAccount> {
+        match self.lookup_table {
+            Some(address) => self.get_lookup_table(address).await.into_iter().collect(),
+            None => vec![],
+        }
+    }
+}
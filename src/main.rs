@@ -1,38 +1,159 @@
+// `ClientError` comes from the Solana client and is returned everywhere; boxing it isn't worth it
+#![allow(clippy::result_large_err)]
+
+mod account_stream;
 mod args;
 mod balance;
 mod benchmark;
+mod blockhash;
 mod busses;
 mod claim;
+mod cleanup;
 mod close;
 mod config;
+mod control;
+mod control_api;
+mod coordinator;
+mod costs;
 mod cu_limits;
+mod daemon;
+mod errors;
+mod exit;
+mod export;
+mod fee_guard;
+mod history;
 #[cfg(feature = "admin")]
 mod initialize;
+mod jito_tip;
+mod keys;
+mod labels;
+mod latency;
+mod leaders;
+mod ledger;
+mod lookup_table;
 mod mine;
+mod nonce;
+mod notify;
+mod numa;
 mod open;
+mod picker;
+mod price;
+mod profit;
+mod proof;
+mod receipts;
 mod rewards;
+mod rpc_sender;
+mod scan;
 mod send_and_confirm;
 mod send_and_confirm_bundle;
+mod simulate;
+mod solution_cache;
+mod spend;
 mod stake;
+mod stake_info;
+mod stats;
+mod submit;
+mod swap;
+mod systemd;
+mod telemetry;
+mod thermal;
+mod tip_stats;
+mod tpu;
 mod upgrade;
 mod utils;
-mod jito_tip;
-use std::sync::Arc;
+mod vault;
+mod wallet_filter;
+mod wallet_log;
+mod wallets;
+mod worker;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
 use args::*;
-use clap::{command, Parser, Subcommand};
+use blockhash::BlockhashCache;
+use clap::{Parser, Subcommand};
+use control::Control;
+use exit::ExitCode;
+use keys::{read_keypair, read_keypair_file};
+use ledger::Ledger;
+use notify::{Notifier, Telegram};
+use price::PriceFeed;
+use reqwest::header::{HeaderName, HeaderValue};
+use rpc_sender::{new_rpc_client, HttpOptions, RateLimiter, RpcUsage};
+use send_and_confirm::RetryPolicy;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
-    commitment_config::CommitmentConfig,
-    signature::{read_keypair_file, Keypair},
+    commitment_config::CommitmentLevel,
+    native_token::sol_to_lamports,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
 };
+use spend::SpendTracker;
+use wallet_filter::WalletFilter;
+
+/// Files in the keypairs folder that are not miner keypairs.
+const IGNORED_FOLDER_FILES: [&str; 2] = ["payer.json", wallets::ROTATION_STATE_FILE];
+
+/// Everything `Miner::new` needs from the command line.
+struct MinerConfig {
+    config_file: Option<String>,
+    rpc_client: Arc<RpcClient>,
+    http: HttpOptions,
+    rpc_limiter: Option<Arc<RateLimiter>>,
+    rpc_usage: Arc<RpcUsage>,
+    spam_rpcs: Vec<Arc<RpcClient>>,
+    sender: Option<Arc<RpcClient>>,
+    priority_fee: u64,
+    maintenance_priority_fee: Option<u64>,
+    wallet_fee_scales: HashMap<Pubkey, f64>,
+    keypair_filepath: Option<String>,
+    folder_path: Option<String>,
+    wallet_filter: WalletFilter,
+    fee_payer: Option<String>,
+    notifier: Notifier,
+    ledger: Option<Ledger>,
+    spend: SpendTracker,
+    usd: bool,
+    retry: RetryPolicy,
+    dry_run: bool,
+    skip_preflight: bool,
+    preflight_commitment: Option<CommitmentLevel>,
+    json: bool,
+    lookup_table: Option<Pubkey>,
+}
 
 struct Miner {
+    pub config_file: Option<String>,
     pub folder_path: Option<String>,
+    pub wallet_filter: WalletFilter,
     pub keypair_filepath: Option<String>,
     pub fee_payer_file_path: Option<String>,
     pub priority_fee: u64,
-    pub rpc_client: Arc<RpcClient>,
+    pub maintenance_priority_fee: Option<u64>,
+    pub wallet_fee_scales: HashMap<Pubkey, f64>,
+    rpc: Arc<RwLock<Arc<RpcClient>>>,
+    pub blockhash: Arc<BlockhashCache>,
+    pub http: HttpOptions,
+    rpc_limiter: Option<Arc<RateLimiter>>,
+    pub rpc_usage: Arc<RpcUsage>,
+    pub spam_rpcs: Vec<Arc<RpcClient>>,
+    pub sender: Option<Arc<RpcClient>>,
+    pub notifier: Arc<Notifier>,
+    pub control: Arc<Control>,
+    pub ledger: Option<Arc<Ledger>>,
+    pub spend: SpendTracker,
+    pub price_feed: Option<Arc<PriceFeed>>,
+    pub retry: RetryPolicy,
+    pub dry_run: bool,
+    pub skip_preflight: bool,
+    pub preflight_commitment: Option<CommitmentLevel>,
+    pub json: bool,
+    pub lookup_table: Option<Pubkey>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -49,29 +170,109 @@ enum Commands {
     #[command(about = "Claim your mining rewards")]
     Claim(ClaimArgs),
 
+    #[command(about = "Close empty or dust ORE token accounts to recover rent")]
+    Cleanup(CleanupArgs),
+
     #[command(about = "Close your account to recover rent")]
     Close(CloseArgs),
 
     #[command(about = "Fetch the program config")]
     Config(ConfigArgs),
 
+    #[command(about = "Mine with the keypairs here, handing challenges to remote workers")]
+    Coordinator(CoordinatorArgs),
+
+    #[command(about = "Total the rent, fees and tips mining has cost, per wallet and fleet-wide")]
+    Costs(CostsArgs),
+
+    #[command(about = "Export timestamped earnings and costs as CSV or JSON")]
+    Export(ExportArgs),
+
+    #[command(about = "Print a hardened systemd unit that mines with these arguments")]
+    GenerateUnit(GenerateUnitArgs),
+
+    #[command(about = "Summarize mining history from the --db ledger")]
+    History(HistoryArgs),
+
+    #[command(about = "Manage the address lookup table bundles compile against")]
+    LookupTable(LookupTableArgs),
+
     #[command(about = "Start mining")]
     Mine(MineArgs),
 
+    #[command(about = "Manage durable nonce accounts for offline signing")]
+    Nonce(NonceArgs),
+
+    #[command(about = "Register a proof account, optionally with a separate rent payer")]
+    Open(OpenArgs),
+
+    #[command(about = "Estimate daily earnings, costs and the break-even tip")]
+    Profit(ProfitArgs),
+
+    #[command(about = "Fetch and inspect a proof account")]
+    Proof(ProofArgs),
+
+    #[command(about = "Verify a claim receipts file against on-chain data")]
+    Receipts(ReceiptsArgs),
+
     #[command(about = "Fetch the current reward rate for each difficulty level")]
     Rewards(RewardsArgs),
 
+    #[command(about = "Move a proof to a new authority keypair right away")]
+    RotateAuthority(RotateAuthorityArgs),
+
     #[command(about = "Stake to earn a rewards multiplier")]
     Stake(StakeArgs),
 
+    #[command(
+        about = "Show each wallet's stake, multiplier and the stake needed for the next tier"
+    )]
+    StakeInfo(StakeInfoArgs),
+
+    #[command(about = "Check whether a background miner is running and what it is doing")]
+    Status(StatusArgs),
+
+    #[command(about = "Stop a background miner after its current pass")]
+    Stop(StopArgs),
+
+    #[command(about = "Swap ORE to SOL through Jupiter")]
+    Swap(SwapArgs),
+
+    #[command(about = "Show the Jito tips recent bundles landed with")]
+    TipStats(TipStatsArgs),
+
     #[command(about = "Upgrade your ORE tokens from v1 to v2")]
     Upgrade(UpgradeArgs),
 
+    #[command(about = "Manage the keypairs in the folder path")]
+    Wallets(WalletsArgs),
+
+    #[command(about = "Hash challenges for a coordinator, without any keypairs")]
+    Worker(WorkerArgs),
+
     #[cfg(feature = "admin")]
     #[command(about = "Initialize the program")]
     Initialize(InitializeArgs),
 }
 
+impl Commands {
+    /// Whether the command acts on a single keypair rather than the whole folder.
+    fn targets_single_wallet(&self) -> bool {
+        match self {
+            Commands::Balance(args) => args.address.is_none(),
+            Commands::Proof(args) => args.address.is_none(),
+            Commands::Claim(args) => !args.all,
+            Commands::Open(args) => args.authority.is_none(),
+            Commands::RotateAuthority(args) => !args.all,
+            Commands::Stake(args) => !args.all,
+            Commands::Close(_) | Commands::Nonce(_) | Commands::Swap(_) | Commands::Upgrade(_) => {
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
 #[derive(Parser, Debug)]
 #[command(about, version)]
 struct Args {
@@ -95,7 +296,7 @@ struct Args {
     #[arg(
         long,
         value_name = "KEYPAIR_FILEPATH",
-        help = "Filepath to keypair to use",
+        help = "Filepath to keypair to use, env:NAME to read it from an environment variable, or - for stdin. Holds a JSON byte array or base58 of the keypair, or seed: and its 32-byte seed",
         global = true
     )]
     keypair: Option<String>,
@@ -109,10 +310,27 @@ struct Args {
     )]
     priority_fee: u64,
 
+    #[arg(
+        long,
+        value_name = "MICROLAMPORTS",
+        help = "Priority fee for everything but mine submissions, like claims, stakes and opens. Defaults to --priority-fee",
+        global = true
+    )]
+    maintenance_priority_fee: Option<u64>,
+
+    #[arg(
+        long = "wallet-fee-scale",
+        value_name = "ADDRESS=FACTOR",
+        help = "Scale the priority fee of one wallet's mine transactions, like a wallet that often misses. Repeat for more",
+        value_parser = utils::parse_wallet_fee_scale,
+        global = true
+    )]
+    wallet_fee_scales: Vec<(Pubkey, f64)>,
+
     #[arg(
         long,
         value_name = "FOLDER_PATH",
-        help = "folder path for to keypairs",
+        help = "folder path for to keypairs, or - to read a JSON object of names to keypairs from stdin",
         global = true
     )]
     folder_path: Option<String>,
@@ -120,11 +338,325 @@ struct Args {
     #[arg(
         long,
         value_name = "FEE_PAYER_FILE_PATH",
-        help = "fee payer file path for jito",
+        help = "fee payer file path for jito, env:NAME to read it from an environment variable, or - for stdin",
         global = true
     )]
     fee_payer: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "PATTERNS",
+        help = "Only use the folder wallets matching these comma-separated addresses, or file names or labels with * and ? wildcards",
+        value_delimiter = ',',
+        global = true
+    )]
+    only: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PATTERNS",
+        help = "Leave out the folder wallets matching these comma-separated addresses, or file names or labels with * and ? wildcards",
+        value_delimiter = ',',
+        global = true
+    )]
+    exclude: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "JSON file of wallet addresses to names, shown in place of the addresses",
+        global = true
+    )]
+    labels: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Vault server to fetch vault:PATH keypairs from. Defaults to $VAULT_ADDR",
+        global = true
+    )]
+    vault_addr: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        help = "Vault token for reading keypairs. Defaults to $VAULT_TOKEN",
+        global = true
+    )]
+    vault_token: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "MOUNT",
+        help = "Mount path of the Vault KV v2 secrets engine holding the keypairs",
+        default_value = "secret",
+        global = true
+    )]
+    vault_mount: String,
+
+    #[arg(
+        long,
+        value_name = "FIELD",
+        help = "Field of each Vault secret that holds the keypair",
+        default_value = "keypair",
+        global = true
+    )]
+    vault_field: String,
+
+    #[arg(
+        long,
+        value_name = "BOT_TOKEN",
+        help = "Telegram bot token for notifications and remote commands. Defaults to $TELEGRAM_BOT_TOKEN",
+        global = true
+    )]
+    telegram_token: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "CHAT_ID",
+        help = "Telegram chat to notify and accept commands from",
+        global = true
+    )]
+    telegram_chat_id: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "WEBHOOK_URL",
+        help = "Discord webhook to post mining pass summaries to",
+        global = true
+    )]
+    discord_webhook: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "SQLite database to record mining history in",
+        global = true
+    )]
+    db: Option<String>,
+
+    #[arg(
+        long,
+        help = "Show USD values next to ORE and SOL amounts, using CoinGecko prices",
+        default_value = "false",
+        global = true
+    )]
+    usd: bool,
+
+    #[arg(
+        long,
+        help = "Simulate transactions and print their effects instead of sending them",
+        default_value = "false",
+        global = true
+    )]
+    dry_run: bool,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "Stop submitting once fees and tips paid over the last 24 hours reach this much SOL",
+        global = true
+    )]
+    max_daily_spend: Option<f64>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Commitment level to read accounts and confirm transactions at",
+        default_value = "confirmed",
+        global = true
+    )]
+    commitment: Commitment,
+
+    #[arg(
+        long,
+        value_name = "BOOL",
+        help = "Send transactions without the RPC simulating them first. Pass false to have obviously failing transactions rejected up front",
+        default_value = "true",
+        action = clap::ArgAction::Set,
+        global = true
+    )]
+    skip_preflight: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Commitment level the RPC simulates preflight at. Defaults to --commitment",
+        global = true
+    )]
+    preflight_commitment: Option<Commitment>,
+
+    #[arg(
+        long,
+        help = "Print read commands' output as JSON, for scripts and monitoring",
+        default_value = "false",
+        global = true
+    )]
+    json: bool,
+
+    #[arg(
+        long,
+        value_name = "REQUESTS_PER_SEC",
+        help = "Most requests per second to make to the RPC, to stay under provider rate limits",
+        global = true
+    )]
+    rpc_rate_limit: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "HTTP(S) or SOCKS5 proxy for RPC and block engine requests, like socks5://127.0.0.1:1080",
+        global = true
+    )]
+    proxy: Option<String>,
+
+    #[arg(
+        long = "header",
+        value_name = "NAME: VALUE",
+        help = "Extra header for RPC and block engine requests, like an API key. Repeat for more",
+        value_parser = utils::parse_header,
+        global = true
+    )]
+    headers: Vec<(HeaderName, HeaderValue)>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Longest to wait on an RPC or block engine request before retrying",
+        default_value = "30",
+        global = true
+    )]
+    timeout: u64,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Longest to wait on connecting to the RPC or block engine",
+        default_value = "10",
+        global = true
+    )]
+    connect_timeout: u64,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Times the RPC node should rebroadcast a transaction",
+        global = true
+    )]
+    rpc_retries: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Times to resubmit a transaction or bundle before giving up",
+        global = true
+    )]
+    gateway_retries: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Times to check for confirmation after each submission",
+        global = true
+    )]
+    confirm_retries: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "MILLISECONDS",
+        help = "Use exponential backoff with jitter between resubmissions, starting here",
+        global = true
+    )]
+    initial_backoff: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "MILLISECONDS",
+        help = "Longest backoff between resubmissions",
+        default_value = "10000",
+        global = true
+    )]
+    max_backoff: u64,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Raise the priority fee and Jito tip by this percent on each resubmission",
+        default_value = "0",
+        global = true
+    )]
+    fee_escalation: u64,
+
+    #[arg(
+        long,
+        value_name = "MULTIPLIER",
+        help = "Most times the original priority fee and Jito tip that escalation can reach",
+        default_value = "4",
+        global = true
+    )]
+    max_escalation: f64,
+
+    #[arg(
+        long,
+        value_name = "MILLISECONDS",
+        help = "Rebroadcast each transaction this often until it lands or its blockhash expires",
+        global = true
+    )]
+    spam_interval: Option<u64>,
+
+    #[arg(
+        long = "spam-rpc",
+        value_name = "URL",
+        help = "Extra RPC to rebroadcast spammed transactions to. Repeat for more",
+        requires = "spam_interval",
+        global = true
+    )]
+    spam_rpcs: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Provider endpoint to send transactions through instead of the RPC, like a Helius staked connection or Triton Cascade. Put any API key query params in the URL. Sends skip preflight",
+        global = true
+    )]
+    sender: Option<String>,
+
+    #[arg(
+        long = "sender-header",
+        value_name = "NAME:VALUE",
+        help = "Extra header for --sender requests only. Repeat for more",
+        value_parser = utils::parse_header,
+        requires = "sender",
+        global = true
+    )]
+    sender_headers: Vec<(HeaderName, HeaderValue)>,
+
+    #[arg(
+        long,
+        value_name = "TABLE_ADDRESS",
+        help = "Address lookup table to compile transactions against",
+        global = true
+    )]
+    lookup_table: Option<String>,
+
+    #[cfg(feature = "grpc")]
+    #[arg(
+        long,
+        value_name = "GRPC_URL",
+        help = "Yellowstone gRPC endpoint to stream bus, config and proof accounts from while mining",
+        global = true
+    )]
+    grpc_url: Option<String>,
+
+    #[cfg(feature = "grpc")]
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        help = "x-token for the Yellowstone gRPC endpoint",
+        global = true
+    )]
+    grpc_token: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -133,11 +665,20 @@ struct Args {
 async fn main() {
     let args = Args::parse();
 
+    // Hand off to a background copy of this command before doing any work here
+    match &args.command {
+        Commands::Mine(args) if args.daemon => daemon::spawn(args),
+        Commands::Coordinator(args) if args.mine.daemon => daemon::spawn(&args.mine),
+        _ => {}
+    }
+
     // Load the config file from custom path, the default path, or use default config values
     let cli_config = if let Some(config_file) = &args.config_file {
         solana_cli_config::Config::load(config_file).unwrap_or_else(|_| {
-            eprintln!("error: Could not find config file `{}`", config_file);
-            std::process::exit(1);
+            exit::abort(
+                ExitCode::Config,
+                format!("Could not find config file `{}`", config_file),
+            )
         })
     } else if let Some(config_file) = &*solana_cli_config::CONFIG_FILE {
         solana_cli_config::Config::load(config_file).unwrap_or_default()
@@ -145,18 +686,204 @@ async fn main() {
         solana_cli_config::Config::default()
     };
 
+    // Name wallets in output before anything prints them
+    if let Some(path) = &args.labels {
+        if let Err(err) = labels::load(path) {
+            exit::abort(ExitCode::Config, err);
+        }
+    }
+
+    // Fetch Vault keypairs up front, so they never touch the disk and a bad path fails early
+    let vault_sources = [&args.keypair, &args.fee_payer, &args.folder_path];
+    if vault_sources.iter().any(|source| {
+        source
+            .as_deref()
+            .is_some_and(|s| s.starts_with(vault::PREFIX))
+    }) {
+        let addr = args
+            .vault_addr
+            .clone()
+            .or_else(|| std::env::var("VAULT_ADDR").ok())
+            .unwrap_or_else(|| {
+                exit::abort(
+                    ExitCode::Config,
+                    "vault: keypairs need --vault-addr or $VAULT_ADDR",
+                )
+            });
+        let token = args
+            .vault_token
+            .clone()
+            .or_else(|| std::env::var("VAULT_TOKEN").ok())
+            .unwrap_or_else(|| {
+                exit::abort(
+                    ExitCode::Config,
+                    "vault: keypairs need --vault-token or $VAULT_TOKEN",
+                )
+            });
+        if let Err(err) = vault::connect(
+            addr,
+            token,
+            args.vault_mount.clone(),
+            args.vault_field.clone(),
+            &[args.keypair.as_deref(), args.fee_payer.as_deref()],
+            args.folder_path.as_deref(),
+        )
+        .await
+        {
+            exit::abort(ExitCode::Config, format!("Failed to read Vault: {}", err));
+        }
+    }
+
     // Initialize miner.
     let cluster = args.rpc.unwrap_or(cli_config.json_rpc_url);
-    let default_keypair = args.keypair.unwrap_or(cli_config.keypair_path);
-    let rpc_client = RpcClient::new_with_commitment(cluster, CommitmentConfig::confirmed());
+    let wallet_filter = WalletFilter {
+        only: args.only,
+        exclude: args.exclude,
+    };
+    // Stdin can only be read once
+    let stdin_sources = [&args.keypair, &args.fee_payer, &args.folder_path]
+        .iter()
+        .filter(|source| source.as_deref().eq(&Some(keys::STDIN)))
+        .count();
+    if stdin_sources.gt(&1) {
+        exit::abort(
+            ExitCode::Config,
+            "Only one of --keypair, --fee-payer and --folder-path can read from stdin",
+        );
+    }
+    let default_keypair = match args.keypair {
+        Some(keypair) => keypair,
+        None => match &args.folder_path {
+            // Let the user pick from the folder instead of copying file paths around
+            Some(folder_path)
+                if args.command.targets_single_wallet()
+                    && folder_path.ne(keys::STDIN)
+                    && !folder_path.starts_with(vault::PREFIX) =>
+            {
+                picker::pick_keypair(folder_path, &wallet_filter)
+                    .unwrap_or_else(|| std::process::exit(ExitCode::Config as i32))
+            }
+            _ => cli_config.keypair_path,
+        },
+    };
+    let rpc_limiter = args
+        .rpc_rate_limit
+        .map(|per_sec| Arc::new(RateLimiter::new(per_sec)));
+    let rpc_usage = Arc::new(RpcUsage::default());
+    let http = HttpOptions::new(
+        args.proxy,
+        args.headers,
+        Duration::from_secs(args.timeout),
+        Duration::from_secs(args.connect_timeout),
+    )
+    .unwrap_or_else(|err| exit::abort(ExitCode::Config, err));
+    let rpc_client = new_rpc_client(
+        cluster,
+        args.commitment.config(),
+        &http,
+        rpc_limiter.clone(),
+        rpc_usage.clone(),
+    );
+    let spam_rpcs = args
+        .spam_rpcs
+        .into_iter()
+        .map(|url| {
+            Arc::new(new_rpc_client(
+                url,
+                args.commitment.config(),
+                &http,
+                None,
+                rpc_usage.clone(),
+            ))
+        })
+        .collect();
+    let sender = args.sender.map(|url| {
+        Arc::new(new_rpc_client(
+            url,
+            args.commitment.config(),
+            &http.with_headers(args.sender_headers),
+            None,
+            rpc_usage.clone(),
+        ))
+    });
     let folder_path = args.folder_path;
-    let miner = Arc::new(Miner::new(
-        Arc::new(rpc_client),
-        args.priority_fee,
-        Some(default_keypair),
+    let telegram = match (
+        args.telegram_token
+            .or_else(|| std::env::var("TELEGRAM_BOT_TOKEN").ok()),
+        args.telegram_chat_id,
+    ) {
+        (Some(token), Some(chat_id)) => Some(Telegram { token, chat_id }),
+        _ => None,
+    };
+    let ledger = args.db.map(|path| {
+        Ledger::open(&path).unwrap_or_else(|err| {
+            exit::abort(
+                ExitCode::Config,
+                format!("Could not open ledger `{}`: {}", path, err),
+            )
+        })
+    });
+    let spend = SpendTracker::new(args.max_daily_spend.map(sol_to_lamports), ledger.as_ref());
+    let miner = Arc::new(Miner::new(MinerConfig {
+        config_file: args.config_file,
+        rpc_client: Arc::new(rpc_client),
+        http,
+        rpc_limiter,
+        rpc_usage,
+        spam_rpcs,
+        sender,
+        priority_fee: args.priority_fee,
+        maintenance_priority_fee: args.maintenance_priority_fee,
+        wallet_fee_scales: args.wallet_fee_scales.into_iter().collect(),
+        keypair_filepath: Some(default_keypair),
         folder_path,
-        args.fee_payer
-    ));
+        wallet_filter,
+        fee_payer: args.fee_payer,
+        notifier: Notifier::new(telegram, args.discord_webhook),
+        ledger,
+        spend,
+        usd: args.usd,
+        retry: RetryPolicy {
+            rpc_retries: args.rpc_retries,
+            gateway_retries: args.gateway_retries,
+            confirm_retries: args.confirm_retries,
+            initial_backoff: args.initial_backoff.map(Duration::from_millis),
+            max_backoff: Duration::from_millis(args.max_backoff),
+            fee_escalation: args.fee_escalation,
+            max_escalation: args.max_escalation,
+            spam_interval: args.spam_interval.map(Duration::from_millis),
+        },
+        dry_run: args.dry_run,
+        skip_preflight: args.skip_preflight,
+        preflight_commitment: args
+            .preflight_commitment
+            .map(|commitment| commitment.config().commitment),
+        json: args.json,
+        lookup_table: args.lookup_table.map(|address| {
+            Pubkey::from_str(&address).unwrap_or_else(|_| {
+                exit::abort(
+                    ExitCode::Config,
+                    format!("Invalid lookup table address `{}`", address),
+                )
+            })
+        }),
+    }));
+
+    // Stream the accounts the mine loop reads instead of polling them
+    #[cfg(feature = "grpc")]
+    if let (Some(grpc_url), Commands::Mine(_) | Commands::Coordinator(_)) =
+        (args.grpc_url, &args.command)
+    {
+        let mut addresses = vec![ore_api::consts::CONFIG_ADDRESS];
+        addresses.extend(ore_api::consts::BUS_ADDRESSES);
+        addresses.extend(
+            miner
+                .multi_signers()
+                .iter()
+                .map(|signer| utils::proof_pubkey(solana_sdk::signature::Signer::pubkey(signer))),
+        );
+        account_stream::subscribe(grpc_url, args.grpc_token, addresses);
+    }
 
     // Execute user command.
     match args.command {
@@ -166,97 +893,307 @@ async fn main() {
         Commands::Benchmark(args) => {
             miner.benchmark(args).await;
         }
-        Commands::Busses(_) => {
-            miner.busses().await;
+        Commands::Busses(args) => {
+            miner.busses(args).await;
         }
         Commands::Claim(args) => {
             miner.claim(args).await;
         }
+        Commands::Cleanup(args) => {
+            miner.cleanup(args).await;
+        }
         Commands::Close(_) => {
             miner.close().await;
         }
         Commands::Config(_) => {
             miner.config().await;
         }
+        Commands::Coordinator(args) => {
+            miner.coordinator(args).await;
+        }
+        Commands::Costs(args) => {
+            miner.costs(args).await;
+        }
+        Commands::Export(args) => {
+            miner.export(args).await;
+        }
+        Commands::GenerateUnit(args) => {
+            miner.generate_unit(args).await;
+        }
+        Commands::History(args) => {
+            miner.history(args).await;
+        }
+        Commands::LookupTable(args) => {
+            miner.lookup_table(args).await;
+        }
         Commands::Mine(args) => {
             miner.mine(args).await;
         }
-        Commands::Rewards(_) => {
-            miner.rewards().await;
+        Commands::Nonce(args) => {
+            miner.nonce(args).await;
+        }
+        Commands::Open(args) => {
+            miner.open(args).await;
+        }
+        Commands::Profit(args) => {
+            miner.profit(args).await;
+        }
+        Commands::Proof(args) => {
+            miner.proof(args).await;
+        }
+        Commands::Receipts(args) => {
+            miner.receipts(args).await;
+        }
+        Commands::Rewards(args) => {
+            miner.rewards(args).await;
+        }
+        Commands::RotateAuthority(args) => {
+            miner.rotate_authority(args).await;
         }
         Commands::Stake(args) => {
             miner.stake(args).await;
         }
+        Commands::StakeInfo(_) => {
+            miner.stake_info().await;
+        }
+        Commands::Status(args) => {
+            miner.status(args).await;
+        }
+        Commands::Stop(args) => {
+            miner.stop(args).await;
+        }
+        Commands::Swap(args) => {
+            miner.swap(args).await;
+        }
+        Commands::TipStats(args) => {
+            miner.tip_stats(args).await;
+        }
         Commands::Upgrade(args) => {
             miner.upgrade(args).await;
         }
+        Commands::Wallets(args) => {
+            miner.wallets(args).await;
+        }
+        Commands::Worker(args) => {
+            miner.worker(args).await;
+        }
         #[cfg(feature = "admin")]
         Commands::Initialize(_) => {
             miner.initialize().await;
         }
     }
+    exit::exit();
 }
 
 impl Miner {
-    pub fn new(
-        rpc_client: Arc<RpcClient>,
-        priority_fee: u64,
-        keypair_filepath: Option<String>,
-        folder_path: Option<String>,
-        fee_payer: Option<String>,
-    ) -> Self {
-        Self {
+    pub fn new(config: MinerConfig) -> Self {
+        let MinerConfig {
+            config_file,
             rpc_client,
+            http,
+            rpc_limiter,
+            rpc_usage,
+            spam_rpcs,
+            sender,
+            priority_fee,
+            maintenance_priority_fee,
+            wallet_fee_scales,
+            keypair_filepath,
+            folder_path,
+            wallet_filter,
+            fee_payer,
+            notifier,
+            ledger,
+            spend,
+            usd,
+            retry,
+            dry_run,
+            skip_preflight,
+            preflight_commitment,
+            json,
+            lookup_table,
+        } = config;
+        Self {
+            config_file,
+            rpc: Arc::new(RwLock::new(rpc_client)),
+            blockhash: Arc::new(BlockhashCache::default()),
+            http,
+            rpc_limiter,
+            rpc_usage,
+            spam_rpcs,
+            sender,
             keypair_filepath,
             priority_fee,
+            maintenance_priority_fee,
+            wallet_fee_scales,
             folder_path,
-            fee_payer_file_path: fee_payer
+            wallet_filter,
+            fee_payer_file_path: fee_payer,
+            notifier: Arc::new(notifier),
+            control: Arc::new(Control::default()),
+            ledger: ledger.map(Arc::new),
+            spend,
+            price_feed: usd.then(|| Arc::new(PriceFeed::default())),
+            retry,
+            dry_run,
+            skip_preflight,
+            preflight_commitment,
+            json,
+            lookup_table,
         }
     }
 
+    pub fn rpc_client(&self) -> Arc<RpcClient> {
+        self.rpc.read().unwrap().clone()
+    }
+
+    /// Commitment level preflight simulation runs at.
+    pub fn preflight_commitment(&self) -> CommitmentLevel {
+        self.preflight_commitment
+            .unwrap_or(self.rpc_client().commitment().commitment)
+    }
+
+    /// Priority fee for transactions other than mine submissions.
+    pub fn maintenance_fee(&self) -> u64 {
+        self.maintenance_priority_fee.unwrap_or(self.priority_fee)
+    }
+
+    /// Priority fee for a wallet's mine transaction, scaled by its `--wallet-fee-scale`.
+    pub fn mine_fee(&self, wallet: &Pubkey) -> u64 {
+        match self.wallet_fee_scales.get(wallet) {
+            Some(scale) => (self.priority_fee as f64 * scale) as u64,
+            None => self.priority_fee,
+        }
+    }
+
+    /// Replaces the RPC client with a fresh one to the same endpoint, dropping any
+    /// connections a stalled request may be holding.
+    pub fn reconnect_rpc(&self) {
+        let old = self.rpc_client();
+        *self.rpc.write().unwrap() = Arc::new(new_rpc_client(
+            old.url(),
+            old.commitment(),
+            &self.http,
+            self.rpc_limiter.clone(),
+            self.rpc_usage.clone(),
+        ));
+    }
+
     pub fn signer(&self) -> Keypair {
         match self.keypair_filepath.clone() {
-            Some(filepath) => read_keypair_file(filepath.clone())
-                .expect(format!("No keypair found at {}", filepath).as_str()),
-            None => panic!("No keypair provided"),
+            Some(source) => read_keypair(&source).unwrap_or_else(|err| {
+                exit::abort(ExitCode::Config, format!("Failed to read keypair {}", err))
+            }),
+            None => exit::abort(ExitCode::Config, "No keypair provided"),
         }
     }
     pub fn fee_payer(&self) -> Keypair {
-        let fee_payer = self.fee_payer_file_path.clone().unwrap();
-        read_keypair_file(fee_payer).unwrap()
+        let Some(fee_payer) = self.fee_payer_file_path.clone() else {
+            exit::abort(ExitCode::Config, "No fee payer provided");
+        };
+        read_keypair(&fee_payer).unwrap_or_else(|err| {
+            exit::abort(
+                ExitCode::Config,
+                format!("Failed to read fee payer keypair {}", err),
+            )
+        })
     }
-    
+
     pub fn multi_signers(&self) -> Vec<Keypair> {
+        self.multi_signer_files()
+            .into_iter()
+            .map(|(_, keypair)| keypair)
+            .collect()
+    }
+
+    /// The folder keypairs along with the files they were read from, narrowed by `--only`
+    /// and `--exclude`.
+    pub fn multi_signer_files(&self) -> Vec<(PathBuf, Keypair)> {
+        self.try_multi_signer_files()
+            .unwrap_or_else(|err| exit::abort(ExitCode::Config, err))
+    }
+
+    /// Like `multi_signer_files`, returning what's wrong with the folder instead of exiting.
+    pub fn try_multi_signer_files(&self) -> Result<Vec<(PathBuf, Keypair)>, String> {
         let mut keypairs = Vec::new();
 
+        let mut errors = Vec::new();
         match self.folder_path.clone() {
+            Some(folder_path) if folder_path.eq(keys::STDIN) => {
+                keys::read_stdin_keypairs(&mut keypairs, &mut errors);
+            }
+            Some(folder_path) if folder_path.starts_with(vault::PREFIX) => {
+                vault::folder_keypairs(
+                    &folder_path[vault::PREFIX.len()..],
+                    &mut keypairs,
+                    &mut errors,
+                );
+            }
             Some(folder_path) => {
-                self.traverse_folder(&folder_path, &mut keypairs);
+                traverse_folder(Path::new(&folder_path), &mut keypairs, &mut errors);
+            }
+            None => return Err("No keypair folder provided".to_string()),
+        }
+
+        // The same wallet twice would land one of its transactions and fail the other
+        let mut seen: HashMap<Pubkey, &PathBuf> = HashMap::new();
+        for (path, keypair) in keypairs.iter() {
+            if let Some(first) = seen.insert(keypair.pubkey(), path) {
+                errors.push(format!(
+                    "{} is the same wallet as {}",
+                    path.display(),
+                    first.display()
+                ));
             }
-            None => panic!("No keypair provided"),
         }
 
-        keypairs
+        // Mining with fewer wallets than expected is worse than not starting
+        if !errors.is_empty() {
+            return Err(format!(
+                "Invalid keypair folder:\n  {}\nFix or move these files out of the folder",
+                errors.join("\n  ")
+            ));
+        }
+
+        Ok(self.wallet_filter.apply(keypairs))
     }
+}
 
-    fn traverse_folder(&self, folder_path: &String, keypairs: &mut Vec<Keypair>) {
-        let entries = std::fs::read_dir(folder_path).unwrap();
-
-        for entry in entries {
-            let entry = entry.unwrap();
-            let path = entry.path();
-            if !path.is_file() {
-                self.traverse_folder(&path.to_str().unwrap().to_string(), keypairs);
-            } else if path.is_file() {
-                let file_name = path.file_name().unwrap().to_str().unwrap();
-                if file_name != "payer.json" {
-                    if let Ok(keypair) = read_keypair_file(&path) {
-                        keypairs.push(keypair);
-                    } else {
-                        eprintln!("Error reading keypair file: {}", file_name);
-                    }
-                }
-            }
+/// Reads every keypair under `folder`, in path order so the same wallets mine in the same
+/// order every run. Skips the fee payer, rotation state and hidden files.
+fn traverse_folder(
+    folder: &Path,
+    keypairs: &mut Vec<(PathBuf, Keypair)>,
+    errors: &mut Vec<String>,
+) {
+    let mut paths: Vec<PathBuf> = match std::fs::read_dir(folder) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .collect(),
+        Err(err) => {
+            errors.push(format!("{}: {}", folder.display(), err));
+            return;
+        }
+    };
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            traverse_folder(&path, keypairs, errors);
+            continue;
+        }
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        // Hidden files are editor and OS leftovers, not keypairs
+        if IGNORED_FOLDER_FILES.contains(&file_name.as_str()) || file_name.starts_with('.') {
+            continue;
+        }
+        match read_keypair_file(&path) {
+            Ok(keypair) => keypairs.push((path, keypair)),
+            Err(err) => errors.push(err),
         }
     }
 }
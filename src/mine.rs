@@ -1,6 +1,12 @@
 use std::{
-    sync::Arc,
-    time::{Duration, Instant},
+    cmp::Reverse,
+    collections::HashSet,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc, Once,
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
 use colored::*;
@@ -10,207 +16,1364 @@ use drillx::{
 };
 use futures::future::join_all;
 use ore_api::{
-    consts::{BUS_ADDRESSES, BUS_COUNT, EPOCH_DURATION, TOKEN_DECIMALS_V1},
+    consts::{BUS_ADDRESSES, EPOCH_DURATION, TOKEN_DECIMALS_V1},
     state::{Bus, Config, Proof},
 };
-use ore_utils::AccountDeserialize;
-use solana_client::client_error::Result;
-use solana_program::pubkey::Pubkey;
+use rand::{seq::SliceRandom, Rng};
+use solana_client::client_error::{ClientErrorKind, Result as ClientResult};
+use solana_program::{instruction::Instruction, native_token::lamports_to_sol, pubkey::Pubkey};
 use solana_rpc_client::spinner;
-use solana_sdk::signer::Signer;
+use solana_sdk::signature::{Keypair, Signature, Signer};
 
 use crate::{
-    args::MineArgs,
-    utils::{amount_u64_to_string, get_clock, get_config, get_proof_with_authority, proof_pubkey},
+    args::{BusStrategy, MineArgs, SendMode},
+    control::Control,
+    control_api,
+    coordinator::WorkerPool,
+    cu_limits::{ComputeBudget, CU_LIMIT_MINE, CU_LIMIT_RESET},
+    daemon::PidFile,
+    exit::{abort, ExitCode},
+    keys::read_keypair,
+    labels::label,
+    leaders::JitoLeaderSchedule,
+    notify::PassSummary,
+    numa,
+    price::PriceFeed,
+    profit::SIGNATURE_FEE,
+    rewards::reward_for_difficulty,
+    solution_cache::SolutionCache,
+    stats::LandingAlarm,
+    systemd, thermal,
+    tpu::QuicTpuClient,
+    utils::{
+        amount_u64_to_f64, amount_u64_to_string, get_balances, get_busses, get_clock, get_config,
+        get_proofs_with_authorities, proof_pubkey,
+    },
+    vault,
+    wallet_log::WalletLogs,
     Miner,
 };
 
+/// Nonces a solver thread claims at a time from the shared counter.
+const NONCE_CHUNK: u64 = 1024;
+
+/// How often to check on the solver threads while they hash.
+const HASH_POLL: Duration = Duration::from_millis(50);
+
+/// Niceness of solver threads with --nice.
+const NICE_LEVEL: libc::c_int = 19;
+
+/// How long short wallets keep hashing for a profitable solution once the window has passed.
+const PROFIT_GRACE_SECONDS: u64 = 10;
+
+/// Warns once, rather than every pass, that there are more threads than cores to pin them to.
+static PIN_WARNING: Once = Once::new();
+
 impl Miner {
     pub async fn mine(&self, args: MineArgs) {
+        self.mine_with_pool(args, None).await;
+    }
+
+    /// The mine loop. With a worker pool, each challenge is also handed to the connected
+    /// workers and the best solution found anywhere is submitted.
+    pub async fn mine_with_pool(&self, mut args: MineArgs, pool: Option<Arc<WorkerPool>>) {
+        // Let `ore status` and `ore stop` find this process
+        let _pid_file = args.pid_file.as_deref().map(PidFile::create);
+
         // Register, if needed.
-        let signers = self.multi_signers();
-        let fee_payer = self.fee_payer();
+        let mut signers = self.multi_signers();
+        let mut fee_payer = self.fee_payer();
         self.open_all().await;
 
         // Check num threads
         self.check_num_cores(args.threads);
 
+        // Rewards claimed while mining go here, rather than to each keypair
+        let beneficiary = args.beneficiary.as_ref().map(|address| {
+            Pubkey::from_str(address)
+                .unwrap_or_else(|_| abort(ExitCode::Config, "Failed to parse beneficiary address"))
+        });
+
+        // Keep the lookup table in step with the wallets in the folder
+        if let Some(address) = self.lookup_table {
+            self.sync_lookup_table(address).await;
+        }
+
+        // Listen for remote commands
+        self.notifier.clone().listen(self.control.clone());
+        if let Some(port) = args.control_port {
+            control_api::serve(
+                self.control.clone(),
+                &args.control_host,
+                port,
+                args.control_token.clone(),
+            )
+            .await;
+        }
+
+        // Collect bundle packing telemetry
+        if args.packing_report.is_some() {
+            self.control.enable_telemetry();
+        }
+
+        // Finish the current pass on Ctrl-C / SIGTERM
+        self.control.handle_signals();
+
+        // Connect to the leaders once, the client follows the schedule from here
+        let mut tpu = match args.send_mode {
+            SendMode::Tpu => match self.tpu_client().await {
+                Some(tpu) => Some(tpu),
+                None => return,
+            },
+            _ => None,
+        };
+
+        // Prices to weigh rewards against costs with, if guarding profitability
+        let profit_prices = args.min_profit.map(|_| PriceFeed::default());
+
+        // Set up, so let systemd know
+        if args.systemd {
+            systemd::start(self.control.clone());
+        }
+
         // Start mining loop
+        let mut pass = 0;
+        let mut last_config: Option<Config> = None;
+        let mut leaders: Option<JitoLeaderSchedule> = None;
+        let mut folder_modified = self.folder_modified();
+        let mut solution_cache = args.solution_file.as_deref().map(SolutionCache::load);
+        let mut landing_alarm = args
+            .min_landing_rate
+            .map(|rate| LandingAlarm::new(rate, args.landing_window));
+        let wallet_logs = args
+            .log_dir
+            .as_deref()
+            .map(|dir| WalletLogs::new(dir, args.log_max_mb));
+        let session_start = Instant::now();
+        let max_duration = args.duration;
+        let out_of_time = || max_duration.is_some_and(|max| session_start.elapsed().ge(&max));
         loop {
-            let mut proofs = Vec::new();
-            let mut solutions = Vec::new();
-            let mut sol_balances = Vec::new();
-            let client = self.rpc_client.clone();
+            // Wait while paused
+            while self.control.is_paused() && !self.control.is_shutting_down() && !out_of_time() {
+                self.control.heartbeat();
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+            if self.control.is_shutting_down() {
+                break;
+            }
 
-            println!("Mining for multi valid hash...\n");
-            let start = std::time::Instant::now();
+            // Stop after the requested number of passes, skipped and stalled ones included
+            if args.max_passes.is_some_and(|max_passes| pass >= max_passes) {
+                break;
+            }
 
-            for signer in &signers {
-                // Fetch proof
-                let proof = get_proof_with_authority(&client, signer.pubkey()).await;
+            // Stop once the requested time is up
+            if out_of_time() {
                 println!(
-                    "\nStake balance for {}: {} ORE",
-                    signer.pubkey(),
-                    amount_u64_to_string(proof.balance)
+                    "{} Mined for {}s, stopping",
+                    "NOTICE".bold().cyan(),
+                    session_start.elapsed().as_secs()
                 );
-                proofs.push(proof.clone());
+                break;
+            }
 
-                let sol_balance = client
-                    .get_balance(&signer.pubkey())
-                    .await
-                    .unwrap_or_else(|_| 0);
-                let sol_balance_normal =
-                    (sol_balance as f64) / (10f64.powf(TOKEN_DECIMALS_V1 as f64));
-                sol_balances.push(sol_balance_normal);
-
-                // Run drillx
-                let config = get_config(&client).await;
-                let min_difficulty = if args.min_difficulty == 0 {
-                    config.min_difficulty as u32
-                } else {
-                    args.min_difficulty
+            // Hold off while the daily spend cap is used up
+            if self.spend.exhausted() {
+                let resume_at =
+                    Instant::now() + self.spend.resumes_in().max(Duration::from_secs(1));
+                println!(
+                    "{} Daily spend cap reached, resuming in {}m",
+                    "NOTICE".bold().cyan(),
+                    resume_at.duration_since(Instant::now()).as_secs() / 60
+                );
+                while Instant::now() < resume_at
+                    && !self.control.is_shutting_down()
+                    && !out_of_time()
+                {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+                continue;
+            }
+            pass += 1;
+
+            // Pick up wallets added to or removed from the folder, on SIGHUP or folder change
+            let modified = self.folder_modified();
+            if self.control.take_reload() || modified.ne(&folder_modified) {
+                folder_modified = modified;
+                self.reload_wallets(&mut signers, &mut fee_payer).await;
+            }
+
+            // Apply requests made through the control API
+            if let Some(tip) = self.control.take_jito_tip() {
+                let event = format!("Jito tip changed {} -> {} lamports", args.jito_tip, tip);
+                println!("{} {}", "NOTICE".bold().cyan(), event);
+                self.control.stats().timeline.annotate(event);
+                args.jito_tip = tip;
+            }
+            if self.control.take_claim() {
+                self.claim_all_unattended(beneficiary).await;
+            }
+
+            // Don't even hash while the network is too expensive, if asked
+            if let (Some(ceiling), true) = (args.fee_ceiling, args.fee_ceiling_pause_hashing) {
+                self.wait_for_calm_fees(ceiling, &args.send_mode).await;
+                if self.control.is_shutting_down() {
+                    break;
+                }
+            }
+
+            // Abort the pass if it stops making progress, such as on a hung RPC call
+            let outcome = {
+                let pass_future = async {
+                    self.control.heartbeat();
+                    // Only count the tips this pass's submissions pay
+                    self.spend.take_tips();
+                    let mut proofs = Vec::new();
+                    let mut solutions = Vec::new();
+                    let mut sol_balances = Vec::new();
+                    let client = self.rpc_client();
+
+                    // Watch for reward rate changes
+                    let config = get_config(&client).await;
+                    if let Some(last_config) = last_config {
+                        if let Some(change) = Self::describe_rate_change(&last_config, &config) {
+                            println!("{} {}", "NOTICE".bold().cyan(), change);
+                            self.control.stats().timeline.annotate(change.clone());
+                            self.notifier.send(&change).await;
+                        }
+                    }
+                    last_config = Some(config);
+
+                    let fee_payer_balance =
+                        client.get_balance(&fee_payer.pubkey()).await.unwrap_or(0);
+
+                    println!("Mining for multi valid hash...\n");
+                    let start = std::time::Instant::now();
+
+                    // Fetch every proof and wallet balance in one go
+                    let authorities: Vec<Pubkey> =
+                        signers.iter().map(|signer| signer.pubkey()).collect();
+                    let fetched_proofs = get_proofs_with_authorities(&client, &authorities).await;
+                    let balances = get_balances(&client, &authorities).await;
+
+                    for (i, signer) in signers.iter().enumerate() {
+                        let proof = fetched_proofs[i].expect("Failed to get miner account");
+                        println!(
+                            "\nStake balance for {}: {} ORE",
+                            label(&signer.pubkey()),
+                            amount_u64_to_string(proof.balance)
+                        );
+                        proofs.push(proof);
+                        if let Some(logs) = &wallet_logs {
+                            logs.log(
+                                &signer.pubkey(),
+                                format!(
+                                    "Fetched proof: challenge {}, last hash at {}, stake {} ORE",
+                                    bs58::encode(proof.challenge).into_string(),
+                                    proof.last_hash_at,
+                                    amount_u64_to_string(proof.balance)
+                                ),
+                            );
+                        }
+
+                        let sol_balance_normal =
+                            (balances[i] as f64) / (10f64.powf(TOKEN_DECIMALS_V1 as f64));
+                        sol_balances.push(sol_balance_normal);
+
+                        // Run drillx
+                        let min_difficulty = if args.min_difficulty == 0 {
+                            config.min_difficulty as u32
+                        } else {
+                            args.min_difficulty
+                        };
+                        // With a target or workers, hash through this wallet's share of the window
+                        // unless the target turns up first. Otherwise stop at the minimum difficulty.
+                        let cutoff_time = match (args.target_difficulty, &pool) {
+                            (None, None) => 0, // We'll handle cutoff time later
+                            _ => {
+                                self.get_cutoff(proof, args.buffer_time).await
+                                    / (signers.len() - i) as u64
+                            }
+                        };
+
+                        // Pick up work saved before a restart, if the challenge hasn't moved on
+                        if let Some(solution) = solution_cache
+                            .as_ref()
+                            .and_then(|cache| cache.get(&signer.pubkey(), &proof.challenge))
+                        {
+                            println!(
+                                "Resuming saved solution (difficulty {})",
+                                solution.to_hash().difficulty()
+                            );
+                            if let Some(logs) = &wallet_logs {
+                                logs.log(
+                                    &signer.pubkey(),
+                                    format!(
+                                        "Resumed saved solution, difficulty {}",
+                                        solution.to_hash().difficulty()
+                                    ),
+                                );
+                            }
+                            solutions.push(solution);
+                            continue;
+                        }
+
+                        // Hand the challenge to the workers while hashing it here too
+                        let dispatch = pool.as_ref().and_then(|pool| {
+                            pool.dispatch(
+                                proof.challenge,
+                                cutoff_time,
+                                min_difficulty,
+                                args.target_difficulty,
+                            )
+                        });
+                        let solution = Self::find_hash_par(
+                            proof.challenge,
+                            rand::random(),
+                            cutoff_time,
+                            args.threads,
+                            min_difficulty,
+                            args.target_difficulty,
+                            SolverOptions::from_args(&args),
+                            Some(&*self.control),
+                        )
+                        .await;
+                        let solution = match (pool.as_ref(), dispatch) {
+                            (Some(pool), Some(dispatch)) => pool.collect(dispatch, solution).await,
+                            _ => solution,
+                        };
+                        if let Some(cache) = solution_cache.as_mut() {
+                            cache.save(&signer.pubkey(), proof.challenge, &solution);
+                        }
+                        if let Some(logs) = &wallet_logs {
+                            logs.log(
+                                &signer.pubkey(),
+                                format!(
+                                    "Found solution, difficulty {}",
+                                    solution.to_hash().difficulty()
+                                ),
+                            );
+                        }
+                        solutions.push(solution);
+                        self.control.heartbeat();
+                    }
+                    println!("Sol Balances: {:?} SOL", sol_balances);
+                    println!("fee payer address: {}", fee_payer.pubkey());
+
+                    let duration = start.elapsed();
+                    println!("\nHash generation took {:?}", duration);
+
+                    // Calc cutoff time and wait if necessary, leaving room for the observed
+                    // time it takes a bundle to reach the block engine
+                    let latency = self.control.submit_latency();
+                    let buffer_time = match self.control.landing_latency() {
+                        Some(landing) if args.adaptive_buffer => {
+                            let buffer_time = ((latency + landing).as_secs_f64().ceil() as u64)
+                                .max(args.min_buffer_time)
+                                .min(args.max_buffer_time);
+                            println!(
+                                "Adaptive buffer {}s ({:?} submit, {:?} landing)",
+                                buffer_time, latency, landing
+                            );
+                            buffer_time
+                        }
+                        _ => {
+                            if latency.gt(&Duration::ZERO) {
+                                println!("Compensating for {:?} submit latency", latency);
+                            }
+                            args.buffer_time + latency.as_secs_f64().ceil() as u64
+                        }
+                    };
+                    let groups = args.stagger_groups.clamp(1, signers.len().max(1));
+                    let cutoff_time = self
+                        .get_cutoff(*proofs.last().unwrap(), buffer_time)
+                        .await
+                        .saturating_sub((groups as u64 - 1) * args.stagger_seconds);
+
+                    // Don't pay more to submit than the solutions are worth
+                    if let (Some(min_profit), Some(price_feed)) =
+                        (args.min_profit, profit_prices.as_ref())
+                    {
+                        let budget = cutoff_time.saturating_sub(start.elapsed().as_secs());
+                        let tip = self.planned_tip(&args, &fee_payer, &signers).await;
+                        let profitable = self
+                            .ensure_profitable(
+                                &args,
+                                &config,
+                                price_feed,
+                                min_profit,
+                                tip,
+                                &proofs,
+                                &mut solutions,
+                                budget,
+                            )
+                            .await;
+                        if !profitable {
+                            return PassOutcome::Skipped;
+                        }
+                    }
+
+                    // Hold the submission while fees are above the ceiling
+                    if let Some(ceiling) = args.fee_ceiling {
+                        if let Some(reason) = self
+                            .fees_over_ceiling(ceiling, &args.send_mode, args.jito_tip)
+                            .await
+                        {
+                            println!("{} {}. Skipping pass.", "NOTICE".bold().cyan(), reason);
+                            return PassOutcome::Skipped;
+                        }
+                    }
+                    let elapsed = start.elapsed().as_secs();
+                    let progress_bar = Arc::new(spinner::new_progress_bar());
+                    if elapsed < cutoff_time {
+                        let wait_time = cutoff_time - elapsed;
+                        println!("Waiting for {} seconds before submitting...", wait_time);
+
+                        let wait_start = Instant::now();
+                        while wait_start.elapsed().as_secs() < wait_time {
+                            // You can add a small sleep here to prevent busy-waiting
+                            tokio::time::sleep(Duration::from_millis(1000)).await;
+                            self.control.heartbeat();
+
+                            // Update the progress bar with the remaining time
+                            let remaining = wait_time - wait_start.elapsed().as_secs();
+                            progress_bar
+                                .set_message(format!("Time remaining: {} seconds", remaining));
+                        }
+                    }
+
+                    // Trade a slightly later submission for a better reward
+                    if let Some(desired) = args.desired_difficulty {
+                        self.extend_hashing(&args, desired, &proofs, &mut solutions)
+                            .await;
+                    }
+
+                    // Time the bundle for the next Jito leader
+                    if args.align_leaders {
+                        let epoch = self
+                            .rpc_client()
+                            .get_epoch_info()
+                            .await
+                            .ok()
+                            .map(|info| info.epoch);
+                        if leaders.as_ref().map(|schedule| schedule.epoch).ne(&epoch) {
+                            leaders = self.jito_leader_schedule().await;
+                            if leaders.is_none() {
+                                println!(
+                                    "{} Could not fetch the Jito leader schedule",
+                                    "WARNING".bold().yellow()
+                                );
+                            }
+                        }
+                        if let Some(schedule) = leaders.as_ref() {
+                            self.wait_for_jito_leader(
+                                schedule,
+                                Duration::from_millis(args.max_leader_wait),
+                            )
+                            .await;
+                        }
+                    }
+
+                    // Check every solution against the current proofs before spending on it
+                    self.verify_solutions(&args, &config, &signers, &mut proofs, &mut solutions)
+                        .await;
+
+                    // Keep any improvements made since hashing
+                    if let Some(cache) = solution_cache.as_mut() {
+                        for ((signer, proof), solution) in
+                            signers.iter().zip(proofs.iter()).zip(solutions.iter())
+                        {
+                            cache.save(&signer.pubkey(), proof.challenge, solution);
+                        }
+                    }
+
+                    // Submit mine tx
+                    progress_bar.finish_with_message("\n\nSubmitting hash...");
+                    let busses = self.find_busses(&args.bus_strategy, pass).await;
+                    let highest_bus_pubkey = busses[0];
+
+                    // Reset the epoch ourselves rather than wait for someone else to
+                    let reset = self.should_reset(config).await;
+                    if reset {
+                        println!("Epoch has ended. Resetting with this pass.");
+                    }
+
+                    let mut wallet_ixs = Vec::new();
+                    for (i, (signer, solution)) in signers.iter().zip(solutions.iter()).enumerate()
+                    {
+                        // Spread wallets over busses so one running dry doesn't fail the whole bundle
+                        let bus = match args.bus_per_wallet {
+                            true => busses[i % busses.len()],
+                            false => highest_bus_pubkey,
+                        };
+
+                        wallet_ixs.push(mine_ixs(signer.pubkey(), bus, *solution));
+                    }
+
+                    // Leave out wallets that would fail, and with them the whole bundle
+                    let bundled = matches!(args.send_mode, SendMode::Jito | SendMode::Both);
+                    let included = match args.simulate_wallets && bundled {
+                        true => {
+                            let reset_ixs = match reset {
+                                true => vec![ore_api::instruction::reset(fee_payer.pubkey())],
+                                false => vec![],
+                            };
+                            self.simulate_wallets(&fee_payer, &signers, &reset_ixs, &wallet_ixs)
+                                .await
+                        }
+                        false => vec![true; signers.len()],
+                    };
+                    if let Some(logs) = &wallet_logs {
+                        for (signer, included) in signers.iter().zip(included.iter()) {
+                            if !included {
+                                logs.log(
+                                    &signer.pubkey(),
+                                    format!("Left out of pass {} after failing simulation", pass),
+                                );
+                            }
+                        }
+                    }
+
+                    // Each group submits on its own, a few seconds after the one before, the
+                    // last at the cutoff
+                    let group_members: Vec<Vec<usize>> = (0..groups)
+                        .map(|group| {
+                            (group..signers.len())
+                                .step_by(groups)
+                                .filter(|i| included[*i])
+                                .collect()
+                        })
+                        .collect();
+
+                    // The reset goes out with the first group that has anyone left to submit
+                    let reset_group = group_members.iter().position(|members| !members.is_empty());
+                    let submissions =
+                        group_members
+                            .into_iter()
+                            .enumerate()
+                            .map(|(group, members)| {
+                                let group_signers: Vec<&Keypair> =
+                                    members.iter().map(|i| &signers[*i]).collect();
+                                let group_wallet_ixs: Vec<Vec<Instruction>> =
+                                    members.iter().map(|i| wallet_ixs[*i].clone()).collect();
+                                let group_reset = reset && reset_group == Some(group);
+                                let delay =
+                                    Duration::from_secs(group as u64 * args.stagger_seconds);
+                                let args = &args;
+                                let fee_payer = &fee_payer;
+                                let tpu = tpu.as_deref();
+                                let wallet_logs = wallet_logs.as_ref();
+                                async move {
+                                    if members.is_empty() {
+                                        return Err(ClientErrorKind::Custom(
+                                            "Every wallet failed simulation".into(),
+                                        )
+                                        .into());
+                                    }
+                                    tokio::time::sleep(delay).await;
+                                    if groups.gt(&1) {
+                                        println!("Submitting group {} of {}", group + 1, groups);
+                                    }
+                                    let res = self
+                                        .submit_mine(
+                                            args,
+                                            tpu,
+                                            group_reset,
+                                            fee_payer,
+                                            &group_signers,
+                                            &group_wallet_ixs,
+                                        )
+                                        .await;
+                                    if let Some(logs) = wallet_logs {
+                                        let event = match &res {
+                                            Ok(sig) => format!("Submitted pass {}: {}", pass, sig),
+                                            Err(err) => {
+                                                format!("Failed to submit pass {}: {}", pass, err)
+                                            }
+                                        };
+                                        for signer in group_signers.iter() {
+                                            logs.log(&signer.pubkey(), &event);
+                                        }
+                                    }
+                                    res
+                                }
+                            });
+                    let results = join_all(submissions).await;
+                    let landed = results.iter().filter(|res| res.is_ok()).count() as u64;
+                    if landed.eq(&(groups as u64)) {
+                        if let Some(cache) = solution_cache.as_mut() {
+                            cache.clear();
+                        }
+                    }
+                    let signature = match results.into_iter().find_map(|res| res.ok()) {
+                        Some(sig) => {
+                            println!("\n\n");
+                            Some(sig)
+                        }
+                        None => {
+                            println!("Failed to send, let's try again.\n\n");
+                            None
+                        }
+                    };
+
+                    // A dry run only needs to show one pass
+                    if self.dry_run {
+                        return PassOutcome::Stop;
+                    }
+
+                    // Report pass
+                    let wallet_results = match signature {
+                        Some(_) => self.wallet_results(&signers, &proofs).await,
+                        None => signers
+                            .iter()
+                            .map(|signer| (signer.pubkey(), None))
+                            .collect(),
+                    };
+                    if let Some(logs) = &wallet_logs {
+                        for (wallet, reward) in wallet_results.iter() {
+                            let event = match reward {
+                                Some(reward) => format!(
+                                    "Landed pass {}, earning {} ORE",
+                                    pass,
+                                    amount_u64_to_string(*reward)
+                                ),
+                                None => format!("Missed pass {}", pass),
+                            };
+                            logs.log(wallet, event);
+                        }
+                    }
+                    let reward = wallet_results
+                        .iter()
+                        .filter_map(|(_, reward)| *reward)
+                        .sum();
+                    let summary = PassSummary {
+                        pass,
+                        difficulties: signers
+                            .iter()
+                            .zip(solutions.iter())
+                            .map(|(signer, solution)| {
+                                (signer.pubkey(), solution.to_hash().difficulty())
+                            })
+                            .collect(),
+                        bus: highest_bus_pubkey,
+                        tip: self.spend.take_tips(),
+                        signature,
+                        reward,
+                        session_reward: 0,
+                        prices: self.prices().await,
+                    };
+                    let spent = fee_payer_balance.saturating_sub(
+                        client
+                            .get_balance(&fee_payer.pubkey())
+                            .await
+                            .unwrap_or(fee_payer_balance),
+                    );
+                    let summary = {
+                        let mut stats = self.control.stats();
+                        stats.record_pass(&summary, spent);
+                        stats.record_wallets(&wallet_results);
+                        let rpc_calls = self.rpc_usage.take();
+                        let pass_calls: u64 = rpc_calls.values().sum();
+                        stats.record_rpc(rpc_calls);
+                        println!(
+                            "RPC: {} requests this pass, {} this session",
+                            pass_calls,
+                            stats.rpc_total()
+                        );
+                        PassSummary {
+                            session_reward: stats.rewards,
+                            ..summary
+                        }
+                    };
+                    if let Some(ledger) = &self.ledger {
+                        if let Err(err) =
+                            ledger.record_pass(&summary, spent.saturating_sub(summary.tip))
+                        {
+                            println!(
+                                "{} Failed to record pass: {}",
+                                "WARNING".bold().yellow(),
+                                err
+                            );
+                        }
+                    }
+                    self.control.set_status(summary.to_text());
+                    self.notifier.pass(&summary).await;
+
+                    // Raise the alarm once too few recent passes land
+                    if let Some(alert) = landing_alarm
+                        .as_mut()
+                        .and_then(|alarm| alarm.record(summary.signature.is_some()))
+                    {
+                        println!("{} {}", "WARNING".bold().yellow(), alert);
+                        self.control.stats().timeline.annotate(alert.clone());
+                        self.notifier.send(&alert).await;
+                    }
+
+                    // Report bundle packing efficiency
+                    if let Some(every) = args.packing_report {
+                        if every > 0 && pass % every == 0 {
+                            if let Some(telemetry) = self.control.telemetry().as_ref() {
+                                println!("\n{}\n", telemetry.report(self.priority_fee));
+                            }
+                        }
+                    }
+                    PassOutcome::Done
                 };
-                let solution = Self::find_hash_par(
-                    proof,
-                    0, // We'll handle cutoff time later
-                    args.threads,
-                    min_difficulty,
-                )
-                .await;
-                solutions.push(solution);
+                match args.watchdog {
+                    0 => pass_future.await,
+                    timeout => tokio::select! {
+                        biased;
+                        outcome = pass_future => outcome,
+                        _ = self.control.stalled(Duration::from_secs(timeout)) => PassOutcome::Stalled,
+                    },
+                }
+            };
+            match outcome {
+                PassOutcome::Done | PassOutcome::Skipped => {}
+                PassOutcome::Stop => break,
+                PassOutcome::Stalled => {
+                    let event = format!(
+                        "Pass {} made no progress for {}s. Aborted it and reconnected to the RPC.",
+                        pass, args.watchdog
+                    );
+                    println!("\n{} {}", "WARNING".bold().yellow(), event);
+                    self.control.stats().timeline.annotate(event.clone());
+                    self.notifier.send(&event).await;
+                    self.reconnect_rpc();
+                    if tpu.is_some() {
+                        if let Some(new_tpu) = self.tpu_client().await {
+                            tpu = Some(new_tpu);
+                        }
+                    }
+                }
+            }
+        }
+
+        if args.systemd {
+            systemd::notify("STOPPING=1");
+        }
+        println!("\n{}", self.control.stats().to_text());
+    }
 
+    fn folder_modified(&self) -> Option<SystemTime> {
+        let folder_path = self.folder_path.as_ref()?;
+        std::fs::metadata(folder_path).ok()?.modified().ok()
+    }
+
+    async fn reload_wallets(&self, signers: &mut Vec<Keypair>, fee_payer: &mut Keypair) {
+        let old: HashSet<Pubkey> = signers.iter().map(|signer| signer.pubkey()).collect();
+        // Pick up keys rotated in Vault, or carry on with the ones already held
+        if let Err(err) = vault::refresh().await {
+            println!(
+                "{} Failed to refresh Vault keypairs: {}",
+                "WARNING".bold().yellow(),
+                err
+            );
+        }
+        // Carry on with the wallets already loaded if the folder can't be read right now
+        match self.try_multi_signer_files() {
+            Ok(files) => *signers = files.into_iter().map(|(_, keypair)| keypair).collect(),
+            Err(err) => {
+                println!(
+                    "{} Failed to reload wallets, keeping the current ones: {}",
+                    "WARNING".bold().yellow(),
+                    err
+                );
+                return;
+            }
+        }
+        if let Some(source) = &self.fee_payer_file_path {
+            match read_keypair(source) {
+                Ok(keypair) => *fee_payer = keypair,
+                Err(err) => println!(
+                    "{} Failed to reload fee payer, keeping the current one: {}",
+                    "WARNING".bold().yellow(),
+                    err
+                ),
             }
-            println!("Sol Balances: {:?} SOL", sol_balances);
-            println!("fee payer address: {}", fee_payer.pubkey());
-
-            let duration = start.elapsed();
-            println!("\nHash generation took {:?}", duration);
-
-            // Calc cutoff time and wait if necessary
-            let cutoff_time = self.get_cutoff(proofs.last().unwrap().clone(), args.buffer_time).await;
-            let elapsed = start.elapsed().as_secs();
-            let progress_bar = Arc::new(spinner::new_progress_bar());
-            if elapsed < cutoff_time {
-                let wait_time = cutoff_time - elapsed;
-                println!("Waiting for {} seconds before submitting...", wait_time);
-                
-                let wait_start = Instant::now();
-                while wait_start.elapsed().as_secs() < wait_time {
-                    // You can add a small sleep here to prevent busy-waiting
-                    tokio::time::sleep(Duration::from_millis(1000)).await;
-                    
-                    // Update the progress bar with the remaining time
-                    let remaining = wait_time - wait_start.elapsed().as_secs();
-                    progress_bar.set_message(format!("Time remaining: {} seconds", remaining));
+        }
+        let added = signers
+            .iter()
+            .filter(|signer| !old.contains(&signer.pubkey()))
+            .count();
+        let removed = (old.len() + added).saturating_sub(signers.len());
+
+        // Register new wallets
+        if added.gt(&0) {
+            self.open_all().await;
+            if let Some(address) = self.lookup_table {
+                self.sync_lookup_table(address).await;
+            }
+        }
+
+        // The RPC client lives for the whole process, so only report a changed URL
+        if let Some(config_file) = &self.config_file {
+            if let Ok(config) = solana_cli_config::Config::load(config_file) {
+                if config.json_rpc_url.ne(&self.rpc_client().url()) {
+                    println!(
+                        "{} RPC URL changed in {}; restart to use {}",
+                        "WARNING".bold().yellow(),
+                        config_file,
+                        config.json_rpc_url
+                    );
                 }
             }
+        }
+
+        let event = format!(
+            "Reloaded wallets: {} added, {} removed, {} mining",
+            added,
+            removed,
+            signers.len()
+        );
+        println!("{} {}", "NOTICE".bold().cyan(), event);
+        self.control.stats().timeline.annotate(event);
+    }
 
-            // Submit mine tx
-            progress_bar.finish_with_message(format!(
-                "\n\nSubmitting hash...",
+    /// Each wallet's reward this pass, as its stake balance increase since `proofs` were
+    /// fetched, or none if its mine didn't land. A wallet landed when its proof records a newer
+    /// hash than the one fetched before submitting.
+    async fn wallet_results(
+        &self,
+        signers: &[Keypair],
+        proofs: &[Proof],
+    ) -> Vec<(Pubkey, Option<u64>)> {
+        let authorities: Vec<Pubkey> = signers.iter().map(|signer| signer.pubkey()).collect();
+        let new_proofs = get_proofs_with_authorities(&self.rpc_client(), &authorities).await;
+        authorities
+            .into_iter()
+            .zip(new_proofs.iter().zip(proofs.iter()))
+            .map(|(authority, (new_proof, proof))| {
+                let reward = new_proof
+                    .filter(|new_proof| new_proof.last_hash_at.gt(&proof.last_hash_at))
+                    .map(|new_proof| new_proof.balance.saturating_sub(proof.balance));
+                (authority, reward)
+            })
+            .collect()
+    }
+
+    fn describe_rate_change(old: &Config, new: &Config) -> Option<String> {
+        let mut changes = vec![];
+        if old.base_reward_rate.ne(&new.base_reward_rate) {
+            changes.push(format!(
+                "base reward rate {} -> {} ORE",
+                amount_u64_to_string(old.base_reward_rate),
+                amount_u64_to_string(new.base_reward_rate)
+            ));
+        }
+        if old.min_difficulty.ne(&new.min_difficulty) {
+            changes.push(format!(
+                "min difficulty {} -> {}",
+                old.min_difficulty, new.min_difficulty
             ));
-            let highest_bus_pubkey = self.find_highest_reward_bus().await;
-
-            let mut all_ixs = Vec::new();
-            for (signer, solution) in signers.iter().zip(solutions.iter()) {
-                
-                all_ixs.push(ore_api::instruction::auth(proof_pubkey(signer.pubkey())));
-                
-                all_ixs.push(ore_api::instruction::mine(
-                    signer.pubkey(),
-                    signer.pubkey(),
-                    highest_bus_pubkey,
-                    *solution,
-                ));
+        }
+        if changes.is_empty() {
+            None
+        } else {
+            Some(format!("Reward rate changed: {}", changes.join(", ")))
+        }
+    }
+
+    /// Simulates each wallet's auth and mine, after the reset if the bundle has one, and
+    /// returns which wallets passed. Wallets are kept when the simulation couldn't run.
+    async fn simulate_wallets(
+        &self,
+        fee_payer: &Keypair,
+        signers: &[Keypair],
+        reset_ixs: &[Instruction],
+        wallet_ixs: &[Vec<Instruction>],
+    ) -> Vec<bool> {
+        let simulations = signers.iter().zip(wallet_ixs.iter()).map(|(signer, ixs)| {
+            let mut sim_ixs = reset_ixs.to_vec();
+            sim_ixs.extend_from_slice(ixs);
+            async move {
+                let failure = self.simulate_failure(&sim_ixs, &fee_payer.pubkey()).await;
+                if let Some(failure) = &failure {
+                    println!(
+                        "{} Leaving {} out of the bundle: {}",
+                        "WARNING".bold().yellow(),
+                        label(&signer.pubkey()),
+                        failure
+                    );
+                }
+                failure.is_none()
             }
-            let jito_url = args.jito_url.clone();
-            match self
-                .send_and_confirm_bundle(all_ixs.as_slice(), false, args.jito_tip, jito_url)
+        });
+        join_all(simulations).await
+    }
+
+    /// Submits a pass's instructions by the chosen route. With `reset`, bundles carry an epoch
+    /// reset signed by the fee payer ahead of each wallet's auth and mine from `wallet_ixs`,
+    /// and direct sends carry it in the first wallet's transaction.
+    async fn submit_mine(
+        &self,
+        args: &MineArgs,
+        tpu: Option<&QuicTpuClient>,
+        reset: bool,
+        fee_payer: &Keypair,
+        signers: &[&Keypair],
+        wallet_ixs: &[Vec<Instruction>],
+    ) -> ClientResult<Signature> {
+        let mut groups = Vec::new();
+        if reset {
+            groups.push(vec![ore_api::instruction::reset(fee_payer.pubkey())]);
+        }
+        groups.extend_from_slice(wallet_ixs);
+        match args.send_mode {
+            SendMode::Jito => {
+                self.send_and_confirm_bundle(
+                    &groups,
+                    signers,
+                    fee_payer,
+                    false,
+                    args.jito_tip,
+                    args.jito_url.clone(),
+                    args.jito_uuid.as_deref(),
+                    args.ixs_per_tx,
+                )
                 .await
-            {
-                Ok(_sig) => {
-                    println!("\n\n");
+            }
+            SendMode::Rpc => {
+                self.send_mine_direct(None, reset, signers, wallet_ixs)
+                    .await
+            }
+            SendMode::Tpu => self.send_mine_direct(tpu, reset, signers, wallet_ixs).await,
+            SendMode::Both => {
+                // Race both routes, falling back to the other if the first to finish failed
+                let jito = self.send_and_confirm_bundle(
+                    &groups,
+                    signers,
+                    fee_payer,
+                    false,
+                    args.jito_tip,
+                    args.jito_url.clone(),
+                    args.jito_uuid.as_deref(),
+                    args.ixs_per_tx,
+                );
+                let rpc = self.send_mine_direct(None, reset, signers, wallet_ixs);
+                tokio::pin!(jito);
+                tokio::pin!(rpc);
+                tokio::select! {
+                    res = &mut jito => match res {
+                        Ok(sig) => Ok(sig),
+                        Err(_) => rpc.await,
+                    },
+                    res = &mut rpc => match res {
+                        Ok(sig) => Ok(sig),
+                        Err(_) => jito.await,
+                    },
                 }
-                Err(_err) => {
-                    println!("Failed to send, let's try again.\n\n");
+            }
+        }
+    }
+
+    /// Sends each wallet's auth and mine as its own transaction, paid by the wallet itself,
+    /// through the RPC or straight to the leaders when given a TPU client. With `reset`, the
+    /// first transaction resets the epoch first. Returns the first signature that landed.
+    async fn send_mine_direct(
+        &self,
+        tpu: Option<&QuicTpuClient>,
+        reset: bool,
+        signers: &[&Keypair],
+        wallet_ixs: &[Vec<Instruction>],
+    ) -> ClientResult<Signature> {
+        let sends = signers.iter().zip(wallet_ixs.iter()).enumerate().map(
+            |(i, (signer, ixs))| async move {
+                let mut ixs = ixs.clone();
+                let mut cu_limit = CU_LIMIT_MINE;
+                if reset && i == 0 {
+                    ixs.insert(0, ore_api::instruction::reset(signer.pubkey()));
+                    cu_limit += CU_LIMIT_RESET;
                 }
+                match tpu {
+                    Some(tpu) => self.send_and_confirm_tpu(tpu, &ixs, signer).await,
+                    None => {
+                        let ixs = self
+                            .with_compute_budget(
+                                &ixs,
+                                &signer.pubkey(),
+                                ComputeBudget::Fixed(cu_limit),
+                                self.mine_fee(&signer.pubkey()),
+                            )
+                            .await;
+                        self.send_and_confirm_with_key(&ixs, false, signer).await
+                    }
+                }
+            },
+        );
+        let mut last_err = None;
+        for res in join_all(sends).await {
+            match res {
+                Ok(sig) => return Ok(sig),
+                Err(err) => last_err = Some(err),
             }
         }
+        Err(last_err.unwrap_or_else(|| ClientErrorKind::Custom("No wallets to send".into()).into()))
     }
 
-    pub async fn get_bus(&self, id: usize) -> Result<Bus> {
-        let client = self.rpc_client.clone();
-        let data = client.get_account_data(&BUS_ADDRESSES[id]).await?;
-        Ok(*Bus::try_from_bytes(&data).unwrap())
+    /// The tip a pass will pay: one per bundle over every stagger group, or none when nothing
+    /// goes through Jito.
+    async fn planned_tip(&self, args: &MineArgs, fee_payer: &Keypair, signers: &[Keypair]) -> u64 {
+        if matches!(args.send_mode, SendMode::Rpc | SendMode::Tpu) {
+            return 0;
+        }
+        let groups = args.stagger_groups.clamp(1, signers.len().max(1));
+        let mut bundles = 0;
+        for group in 0..groups {
+            // Every solution makes the same size of instruction, so any will do
+            let wallet_ixs: Vec<Vec<Instruction>> = signers
+                .iter()
+                .skip(group)
+                .step_by(groups)
+                .map(|signer| {
+                    mine_ixs(
+                        signer.pubkey(),
+                        BUS_ADDRESSES[0],
+                        Solution::new([0; 16], [0; 8]),
+                    )
+                })
+                .collect();
+            bundles += self
+                .bundle_count(&fee_payer.pubkey(), &wallet_ixs, args.ixs_per_tx)
+                .await;
+        }
+        args.jito_tip * bundles as u64
     }
 
-    async fn find_highest_reward_bus(&self) -> Pubkey {
-        let bus_futures: Vec<_> = (0..BUS_COUNT).map(|bus_id| self.get_bus(bus_id)).collect();
+    /// Checks the pass's expected reward against its tip and fees. Wallets whose solutions
+    /// fall short keep hashing toward the difficulty that would pay off, within `budget`
+    /// seconds (or a short grace period once the window has passed). Returns whether to submit.
+    #[allow(clippy::too_many_arguments)]
+    async fn ensure_profitable(
+        &self,
+        args: &MineArgs,
+        config: &Config,
+        price_feed: &PriceFeed,
+        min_profit: f64,
+        tip: u64,
+        proofs: &[Proof],
+        solutions: &mut [Solution],
+        budget: u64,
+    ) -> bool {
+        let Some(prices) = price_feed.prices().await else {
+            println!(
+                "{} Could not fetch prices, submitting without a profit check",
+                "WARNING".bold().yellow()
+            );
+            return true;
+        };
+        let wallets = solutions.len() as u64;
+        let fees = SIGNATURE_FEE * (wallets + 1)
+            + self.priority_fee * (CU_LIMIT_MINE as u64 * wallets) / 1_000_000;
+        let cost = lamports_to_sol(tip + fees);
+        let value = |solutions: &[Solution]| -> f64 {
+            let grains: u64 = solutions
+                .iter()
+                .map(|solution| reward_for_difficulty(config, solution.to_hash().difficulty()))
+                .sum();
+            amount_u64_to_f64(grains) * prices.ore_in_sol()
+        };
+        if value(solutions) - cost >= min_profit {
+            return true;
+        }
 
-        let buses: Vec<_> = join_all(bus_futures)
-            .await
-            .into_iter()
-            .filter_map(Result::ok)
+        // The lowest difficulty that pays off if every wallet reaches it
+        let needed = (cost + min_profit) / wallets.max(1) as f64;
+        let target = (config.min_difficulty as u32..64)
+            .find(|difficulty| {
+                amount_u64_to_f64(reward_for_difficulty(config, *difficulty)) * prices.ore_in_sol()
+                    >= needed
+            })
+            .unwrap_or(64);
+        let short: Vec<usize> = (0..solutions.len())
+            .filter(|i| solutions[*i].to_hash().difficulty().lt(&target))
             .collect();
+        println!(
+            "{} Solutions worth {:.6} SOL against {:.6} SOL cost. Hashing {} wallets toward difficulty {}",
+            "NOTICE".bold().cyan(),
+            value(solutions),
+            cost,
+            short.len(),
+            target
+        );
+        let per_wallet = budget.max(PROFIT_GRACE_SECONDS) / short.len().max(1) as u64;
+        for i in short {
+            let solution = Self::find_hash_par(
+                proofs[i].challenge,
+                rand::random(),
+                per_wallet,
+                args.threads,
+                0,
+                Some(target),
+                SolverOptions::from_args(args),
+                Some(&*self.control),
+            )
+            .await;
+            if solution
+                .to_hash()
+                .difficulty()
+                .gt(&solutions[i].to_hash().difficulty())
+            {
+                solutions[i] = solution;
+            }
+        }
+
+        let profit = value(solutions) - cost;
+        if profit < min_profit {
+            println!(
+                "{} Skipping pass, expected profit {:.6} SOL is below --min-profit {}",
+                "NOTICE".bold().cyan(),
+                profit,
+                min_profit
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Keeps hashing wallets whose best difficulty is below `desired`, for a time that grows
+    /// with how far short they are, within the per-pass extension budget.
+    async fn extend_hashing(
+        &self,
+        args: &MineArgs,
+        desired: u32,
+        proofs: &[Proof],
+        solutions: &mut [Solution],
+    ) {
+        let started = Instant::now();
+        for i in 0..solutions.len() {
+            let difficulty = solutions[i].to_hash().difficulty();
+            if difficulty.ge(&desired) {
+                continue;
+            }
+            let remaining = args
+                .max_extend_seconds
+                .saturating_sub(started.elapsed().as_secs());
+            let extra = (args.extend_seconds_per_missing_difficulty
+                * (desired - difficulty) as u64)
+                .min(remaining);
+            if extra.eq(&0) {
+                break;
+            }
+            println!(
+                "Difficulty {} is {} short of {}, hashing {}s more",
+                difficulty,
+                desired - difficulty,
+                desired,
+                extra
+            );
+            let solution = Self::find_hash_par(
+                proofs[i].challenge,
+                rand::random(),
+                extra,
+                args.threads,
+                0,
+                Some(desired),
+                SolverOptions::from_args(args),
+                Some(&*self.control),
+            )
+            .await;
+            if solution.to_hash().difficulty().gt(&difficulty) {
+                solutions[i] = solution;
+            }
+        }
+    }
+
+    /// Refetches the proofs and verifies each solution against its wallet's current challenge
+    /// and the minimum difficulty. Wallets whose proof moved on, or whose solution fails, are
+    /// hashed again until they beat the minimum.
+    async fn verify_solutions(
+        &self,
+        args: &MineArgs,
+        config: &Config,
+        signers: &[Keypair],
+        proofs: &mut [Proof],
+        solutions: &mut [Solution],
+    ) {
+        let authorities: Vec<Pubkey> = signers.iter().map(|signer| signer.pubkey()).collect();
+        let current = get_proofs_with_authorities(&self.rpc_client(), &authorities).await;
+        let min_difficulty = if args.min_difficulty == 0 {
+            config.min_difficulty as u32
+        } else {
+            args.min_difficulty
+        };
+        for (i, signer) in signers.iter().enumerate() {
+            // Keep the proof we hashed against when it can't be read
+            if let Some(proof) = current[i] {
+                if proof.challenge.ne(&proofs[i].challenge) {
+                    println!(
+                        "{} Proof for {} was updated since hashing",
+                        "NOTICE".bold().cyan(),
+                        label(&signer.pubkey())
+                    );
+                }
+                proofs[i] = proof;
+            }
+            let valid = solutions[i].is_valid(&proofs[i].challenge);
+            let difficulty = solutions[i].to_hash().difficulty();
+            if valid && difficulty.ge(&min_difficulty) {
+                continue;
+            }
+            println!(
+                "{} Re-hashing for {}: {}",
+                "NOTICE".bold().cyan(),
+                label(&signer.pubkey()),
+                match valid {
+                    true => format!("difficulty {} is below {}", difficulty, min_difficulty),
+                    false => "solution does not match the current challenge".to_string(),
+                }
+            );
+            solutions[i] = Self::find_hash_par(
+                proofs[i].challenge,
+                rand::random(),
+                0,
+                args.threads,
+                min_difficulty,
+                None,
+                SolverOptions::from_args(args),
+                Some(&*self.control),
+            )
+            .await;
+            self.control.heartbeat();
+        }
+    }
 
-        let highest_bus = buses.into_iter().max_by_key(|bus| bus.rewards).unwrap();
-        let id = highest_bus.id;
-        BUS_ADDRESSES[id as usize]
+    /// Orders the busses to submit to this pass, best first. Everyone else targets the
+    /// richest bus, so the other strategies spread submissions over the busses that still
+    /// hold rewards.
+    async fn find_busses(&self, strategy: &BusStrategy, pass: u64) -> Vec<Pubkey> {
+        let buses = get_busses(&self.rpc_client()).await;
+        order_busses(buses, strategy, pass, &mut rand::thread_rng())
     }
 
-    async fn find_hash_par(
-        proof: Proof,
+    /// Hashes until the cutoff, and past it until `min_difficulty` is beaten. With a target,
+    /// all threads stop as soon as one of them reaches it. Nonces are taken in order from
+    /// `first_nonce`. Given `control`, heartbeats while the threads make progress, and the
+    /// threads stop if the pass is abandoned.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn find_hash_par(
+        challenge: [u8; 32],
+        first_nonce: u64,
         cutoff_time: u64,
         threads: u64,
         min_difficulty: u32,
+        target_difficulty: Option<u32>,
+        options: SolverOptions,
+        control: Option<&Control>,
     ) -> Solution {
-        // Dispatch job to each thread
+        // Threads claim chunks of nonces from a shared counter, so a fast or lucky thread
+        // takes on more work instead of sitting idle. Callers start at random so repeated
+        // runs on the same challenge don't redo the same nonces.
         let progress_bar = Arc::new(spinner::new_progress_bar());
         progress_bar.set_message("Mining...");
+        let next_nonce = Arc::new(AtomicU64::new(first_nonce));
+        let global_best = Arc::new(AtomicU32::new(0));
+        let target_reached = Arc::new(AtomicBool::new(false));
+        let cores = options.pinning.cores(threads as usize);
+        let active_threads = Arc::new(AtomicU64::new(threads));
+        let hashing_done = Arc::new(AtomicBool::new(false));
+        if let Some(ceiling) = options.max_temp {
+            thermal::spawn_throttle(
+                ceiling,
+                threads,
+                active_threads.clone(),
+                hashing_done.clone(),
+            );
+        }
         let handles: Vec<_> = (0..threads)
             .map(|i| {
+                let core = cores.get(i as usize).copied();
                 std::thread::spawn({
-                    let proof = proof.clone();
                     let progress_bar = progress_bar.clone();
-                    let mut memory = equix::SolverMemory::new();
+                    let next_nonce = next_nonce.clone();
+                    let global_best = global_best.clone();
+                    let target_reached = target_reached.clone();
+                    let active_threads = active_threads.clone();
+                    let hashing_done = hashing_done.clone();
                     move || {
+                        // Pin before allocating, so the solver memory lands on this
+                        // core's NUMA node
+                        if let Some(core) = core {
+                            core_affinity::set_for_current(core);
+                        }
+                        let mut memory = equix::SolverMemory::new();
+                        if options.low_priority {
+                            lower_thread_priority();
+                        }
+                        let busy =
+                            Duration::from_secs(1).mul_f64(options.duty_cycle as f64 / 100.0);
+                        let mut duty_window = Instant::now();
                         let timer = Instant::now();
-                        let mut nonce = u64::MAX.saturating_div(threads).saturating_mul(i);
-                        let mut best_nonce = nonce;
+                        let mut best_nonce = 0;
                         let mut best_difficulty = 0;
                         let mut best_hash = Hash::default();
-                        loop {
-                            // Create hash
-                            if let Ok(hx) = drillx::hash_with_memory(
-                                &mut memory,
-                                &proof.challenge,
-                                &nonce.to_le_bytes(),
-                            ) {
-                                let difficulty = hx.difficulty();
-                                if difficulty.gt(&best_difficulty) {
-                                    best_nonce = nonce;
-                                    best_difficulty = difficulty;
-                                    best_hash = hx;
+                        'chunks: loop {
+                            let chunk = next_nonce.fetch_add(NONCE_CHUNK, Ordering::Relaxed);
+                            for offset in 0..NONCE_CHUNK {
+                                // Create hash
+                                let nonce = chunk.wrapping_add(offset);
+                                if let Ok(hx) = drillx::hash_with_memory(
+                                    &mut memory,
+                                    &challenge,
+                                    &nonce.to_le_bytes(),
+                                ) {
+                                    let difficulty = hx.difficulty();
+                                    if difficulty.gt(&best_difficulty) {
+                                        best_nonce = nonce;
+                                        best_difficulty = difficulty;
+                                        best_hash = hx;
+                                        global_best.fetch_max(difficulty, Ordering::Relaxed);
+                                        if target_difficulty
+                                            .is_some_and(|target| difficulty.ge(&target))
+                                        {
+                                            target_reached.store(true, Ordering::Relaxed);
+                                            break 'chunks;
+                                        }
+                                    }
                                 }
-                            }
 
-                            // Exit if time has elapsed
-                            if nonce % 100 == 0 {
-                                if timer.elapsed().as_secs().ge(&cutoff_time) {
-                                    if best_difficulty.gt(&min_difficulty) {
-                                        // Mine until min difficulty has been met
-                                        break;
+                                // Exit if time has elapsed or another thread hit the target
+                                if nonce % 100 == 0 {
+                                    // Rest for the rest of each second past the duty cycle
+                                    if options.duty_cycle.lt(&100) {
+                                        let worked = duty_window.elapsed();
+                                        if worked.ge(&busy) {
+                                            std::thread::sleep(
+                                                Duration::from_secs(1).saturating_sub(worked),
+                                            );
+                                            duty_window = Instant::now();
+                                        }
+                                    }
+                                    if target_reached.load(Ordering::Relaxed)
+                                        || hashing_done.load(Ordering::Relaxed)
+                                    {
+                                        break 'chunks;
+                                    }
+
+                                    // Sit out while the CPU is too hot for this many threads
+                                    while i.ge(&active_threads.load(Ordering::Relaxed))
+                                        && !target_reached.load(Ordering::Relaxed)
+                                        && !hashing_done.load(Ordering::Relaxed)
+                                        && (timer.elapsed().as_secs().lt(&cutoff_time)
+                                            || global_best
+                                                .load(Ordering::Relaxed)
+                                                .le(&min_difficulty))
+                                    {
+                                        std::thread::sleep(Duration::from_millis(500));
+                                    }
+                                    if timer.elapsed().as_secs().ge(&cutoff_time) {
+                                        // Mine until some thread has met the min difficulty
+                                        if global_best.load(Ordering::Relaxed).gt(&min_difficulty) {
+                                            break 'chunks;
+                                        }
+                                    } else if i == 0 {
+                                        progress_bar.set_message(format!(
+                                            "Mining... ({} sec remaining)",
+                                            cutoff_time.saturating_sub(timer.elapsed().as_secs()),
+                                        ));
                                     }
-                                } else if i == 0 {
-                                    progress_bar.set_message(format!(
-                                        "Mining... ({} sec remaining)",
-                                        cutoff_time.saturating_sub(timer.elapsed().as_secs()),
-                                    ));
                                 }
                             }
-
-                            // Increment nonce
-                            nonce += 1;
                         }
 
                         // Return the best nonce
@@ -220,6 +1383,21 @@ impl Miner {
             })
             .collect();
 
+        // Wait on the threads without blocking the runtime, so the watchdog can still fire.
+        // Nonces being claimed show the threads are hashing rather than hung.
+        let _done = SetOnDrop(hashing_done);
+        let mut claimed = next_nonce.load(Ordering::Relaxed);
+        while !handles.iter().all(|h| h.is_finished()) {
+            tokio::time::sleep(HASH_POLL).await;
+            let now_claimed = next_nonce.load(Ordering::Relaxed);
+            if now_claimed.ne(&claimed) {
+                claimed = now_claimed;
+                if let Some(control) = control {
+                    control.heartbeat();
+                }
+            }
+        }
+
         // Join handles and return best nonce
         let mut best_nonce = 0;
         let mut best_difficulty = 0;
@@ -233,7 +1411,6 @@ impl Miner {
                 }
             }
         }
-
         // Update log
         progress_bar.finish_with_message(format!(
             "Best hash: {} (difficulty: {})",
@@ -257,17 +1434,17 @@ impl Miner {
         }
     }
 
-    // async fn should_reset(&self, config: Config) -> bool {
-    //     let clock = get_clock(&self.rpc_client).await;
-    //     config
-    //         .last_reset_at
-    //         .saturating_add(EPOCH_DURATION)
-    //         .saturating_sub(5) // Buffer
-    //         .le(&clock.unix_timestamp)
-    // }
+    async fn should_reset(&self, config: Config) -> bool {
+        let clock = get_clock(&self.rpc_client()).await;
+        config
+            .last_reset_at
+            .saturating_add(EPOCH_DURATION)
+            .saturating_sub(5) // Buffer
+            .le(&clock.unix_timestamp)
+    }
 
     async fn get_cutoff(&self, proof: Proof, buffer_time: u64) -> u64 {
-        let clock = get_clock(&self.rpc_client).await;
+        let clock = get_clock(&self.rpc_client()).await;
         proof
             .last_hash_at
             .saturating_add(60)
@@ -277,8 +1454,271 @@ impl Miner {
     }
 }
 
-// // TODO Pick a better strategy (avoid draining bus)
-// fn find_bus() -> Pubkey {
-//     let i = rand::thread_rng().gen_range(0..BUS_COUNT);
-//     BUS_ADDRESSES[i]
-// }
+/// How a pass of the mine loop ended.
+enum PassOutcome {
+    Done,
+    /// Not worth submitting, move on to the next pass
+    Skipped,
+    /// Stop mining
+    Stop,
+    /// Made no progress within the watchdog timeout
+    Stalled,
+}
+
+/// How solver threads share the machine.
+#[derive(Clone, Copy)]
+pub struct SolverOptions {
+    pinning: Pinning,
+    low_priority: bool,
+    /// Percent of each second spent hashing
+    duty_cycle: u8,
+    /// CPU temperature in °C to throttle threads above
+    max_temp: Option<f64>,
+}
+
+impl Default for SolverOptions {
+    fn default() -> Self {
+        Self {
+            pinning: Pinning::None,
+            low_priority: false,
+            duty_cycle: 100,
+            max_temp: None,
+        }
+    }
+}
+
+impl SolverOptions {
+    fn from_args(args: &MineArgs) -> Self {
+        Self {
+            pinning: Pinning::from_args(args),
+            low_priority: args.nice,
+            duty_cycle: args.duty_cycle,
+            max_temp: args.max_temp,
+        }
+    }
+}
+
+/// Where solver threads run.
+#[derive(Clone, Copy)]
+enum Pinning {
+    None,
+    /// Consecutive cores from an offset
+    Cores(usize),
+    /// Balanced across NUMA nodes
+    Numa,
+}
+
+impl Pinning {
+    fn from_args(args: &MineArgs) -> Self {
+        if args.numa {
+            Pinning::Numa
+        } else if args.pin_cores {
+            Pinning::Cores(args.core_offset)
+        } else {
+            Pinning::None
+        }
+    }
+
+    /// The core for each of `threads` solver threads, or none to leave them unpinned.
+    fn cores(&self, threads: usize) -> Vec<core_affinity::CoreId> {
+        match self {
+            Pinning::None => vec![],
+            Pinning::Cores(offset) => pinned_cores(*offset, threads),
+            Pinning::Numa => {
+                let cores = numa::spread_cores(threads);
+                if cores.is_empty() {
+                    println!(
+                        "{} NUMA topology not available, not pinning",
+                        "WARNING".bold().yellow()
+                    );
+                }
+                cores
+            }
+        }
+    }
+}
+
+/// Raises a flag when dropped, including when a pass is abandoned mid-hash.
+struct SetOnDrop(Arc<AtomicBool>);
+
+impl Drop for SetOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// A wallet's auth and mine, which must share a transaction.
+fn mine_ixs(signer: Pubkey, bus: Pubkey, solution: Solution) -> Vec<Instruction> {
+    vec![
+        ore_api::instruction::auth(proof_pubkey(signer)),
+        ore_api::instruction::mine(signer, signer, bus, solution),
+    ]
+}
+
+/// Bus addresses in the order `strategy` submits to them on `pass`, funded busses only, or the
+/// richest bus alone if none are funded.
+fn order_busses(
+    mut buses: Vec<Bus>,
+    strategy: &BusStrategy,
+    pass: u64,
+    rng: &mut impl Rng,
+) -> Vec<Pubkey> {
+    buses.sort_by_key(|bus| Reverse(bus.rewards));
+    let mut funded: Vec<_> = buses.iter().filter(|bus| bus.rewards.gt(&0)).collect();
+    if funded.is_empty() {
+        return vec![BUS_ADDRESSES[buses[0].id as usize]];
+    }
+
+    match strategy {
+        BusStrategy::Highest => {}
+        BusStrategy::Top3 => {
+            let top = funded.len().min(3);
+            funded[..top].shuffle(rng);
+        }
+        BusStrategy::Random => funded.shuffle(rng),
+        BusStrategy::RoundRobin => {
+            funded.sort_by_key(|bus| bus.id);
+            let len = funded.len();
+            funded.rotate_left(pass as usize % len);
+        }
+    };
+    funded
+        .into_iter()
+        .map(|bus| BUS_ADDRESSES[bus.id as usize])
+        .collect()
+}
+
+/// Drops the calling thread to the lowest scheduling priority, so interactive work comes first.
+fn lower_thread_priority() {
+    // On Linux this applies to the calling thread only; elsewhere to the whole process,
+    // which is what solver threads want anyway.
+    unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, 0, NICE_LEVEL);
+    }
+}
+
+/// A physical core each for up to `threads` solver threads, from `offset` on and skipping SMT
+/// siblings. Threads beyond the free physical cores are left unpinned.
+fn pinned_cores(offset: usize, threads: usize) -> Vec<core_affinity::CoreId> {
+    let mut available: Vec<usize> = core_affinity::get_core_ids()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|core| core.id)
+        .collect();
+    available.sort();
+    let cores = match numa::physical_cores() {
+        // Without the topology every CPU counts as its own core
+        physical if physical.is_empty() => available
+            .into_iter()
+            .filter(|cpu| cpu.ge(&offset))
+            .collect(),
+        physical => numa::free_cores(&physical, &available, offset),
+    };
+    if cores.len().lt(&threads) {
+        PIN_WARNING.call_once(|| {
+            println!(
+                "{} {} solver threads but only {} free physical cores from core {}, leaving the rest unpinned",
+                "WARNING".bold().yellow(),
+                threads,
+                cores.len(),
+                offset
+            );
+        });
+    }
+    cores
+        .into_iter()
+        .take(threads)
+        .map(|id| core_affinity::CoreId { id })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    /// Busses numbered from 0, holding the given rewards.
+    fn busses(rewards: &[u64]) -> Vec<Bus> {
+        rewards
+            .iter()
+            .enumerate()
+            .map(|(id, rewards)| Bus {
+                id: id as u64,
+                rewards: *rewards,
+                theoretical_rewards: 0,
+                top_balance: 0,
+            })
+            .collect()
+    }
+
+    fn order(rewards: &[u64], strategy: BusStrategy, pass: u64) -> Vec<Pubkey> {
+        let mut rng = StdRng::seed_from_u64(pass);
+        order_busses(busses(rewards), &strategy, pass, &mut rng)
+    }
+
+    fn ids(ids: &[usize]) -> Vec<Pubkey> {
+        ids.iter().map(|id| BUS_ADDRESSES[*id]).collect()
+    }
+
+    #[test]
+    fn highest_orders_funded_busses_by_rewards() {
+        assert_eq!(
+            order(&[30, 0, 50, 10, 40], BusStrategy::Highest, 0),
+            ids(&[2, 4, 0, 3])
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_first_bus_when_none_are_funded() {
+        for strategy in [
+            BusStrategy::Highest,
+            BusStrategy::Random,
+            BusStrategy::Top3,
+            BusStrategy::RoundRobin,
+        ] {
+            assert_eq!(order(&[0, 0, 0], strategy, 3), ids(&[0]));
+        }
+    }
+
+    #[test]
+    fn top3_only_shuffles_the_three_richest() {
+        for pass in 0..20 {
+            let order = order(&[30, 0, 50, 10, 40], BusStrategy::Top3, pass);
+            let mut top: Vec<_> = order[..3].to_vec();
+            top.sort();
+            let mut richest = ids(&[2, 4, 0]);
+            richest.sort();
+            assert_eq!(top, richest);
+            assert_eq!(order[3..], ids(&[3]));
+        }
+    }
+
+    #[test]
+    fn random_keeps_every_funded_bus() {
+        for pass in 0..20 {
+            let mut order = order(&[30, 0, 50, 10, 40], BusStrategy::Random, pass);
+            order.sort();
+            let mut funded = ids(&[0, 2, 3, 4]);
+            funded.sort();
+            assert_eq!(order, funded);
+        }
+    }
+
+    #[test]
+    fn round_robin_starts_one_bus_later_each_pass() {
+        let rewards = [30, 0, 50, 10, 40];
+        assert_eq!(
+            order(&rewards, BusStrategy::RoundRobin, 0),
+            ids(&[0, 2, 3, 4])
+        );
+        assert_eq!(
+            order(&rewards, BusStrategy::RoundRobin, 1),
+            ids(&[2, 3, 4, 0])
+        );
+        assert_eq!(
+            order(&rewards, BusStrategy::RoundRobin, 4),
+            order(&rewards, BusStrategy::RoundRobin, 0)
+        );
+    }
+}
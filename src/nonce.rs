@@ -0,0 +1,250 @@
+use std::str::FromStr;
+
+use base64::Engine;
+use colored::*;
+use solana_client::client_error::{ClientError, ClientErrorKind, Result as ClientResult};
+use solana_program::{
+    hash::Hash, instruction::Instruction, native_token::lamports_to_sol, pubkey::Pubkey,
+    system_instruction,
+};
+use solana_rpc_client::spinner;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    nonce::state::{Data, State, Versions},
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+
+use crate::{
+    args::{
+        DurableNonceArgs, NonceArgs, NonceBroadcastArgs, NonceCloseArgs, NonceCommand,
+        NonceCreateArgs, NonceShowArgs,
+    },
+    cu_limits::{ComputeBudget, CU_LIMIT_MAX},
+    exit::{abort, fail_with, ExitCode},
+    keys::read_keypair,
+    Miner,
+};
+
+impl Miner {
+    pub async fn nonce(&self, args: NonceArgs) {
+        match args.command {
+            NonceCommand::Create(args) => self.nonce_create(args).await,
+            NonceCommand::Show(args) => self.nonce_show(args).await,
+            NonceCommand::Close(args) => self.nonce_close(args).await,
+            NonceCommand::Broadcast(args) => self.nonce_broadcast(args).await,
+        }
+    }
+
+    async fn nonce_create(&self, args: NonceCreateArgs) {
+        let signer = self.signer();
+        let nonce_keypair = match args.nonce_keypair {
+            Some(source) => read_keypair(&source).unwrap_or_else(|err| {
+                abort(
+                    ExitCode::Config,
+                    format!("Failed to read nonce keypair {}", err),
+                )
+            }),
+            None => Keypair::new(),
+        };
+        let Ok(rent) = self
+            .rpc_client()
+            .get_minimum_balance_for_rent_exemption(State::size())
+            .await
+        else {
+            println!("{} Failed to fetch rent", "ERROR".bold().red());
+            return;
+        };
+        let ixs = system_instruction::create_nonce_account(
+            &signer.pubkey(),
+            &nonce_keypair.pubkey(),
+            &signer.pubkey(),
+            rent,
+        );
+        match self
+            .send_and_confirm_with_keys(&ixs, false, &[&signer, &nonce_keypair])
+            .await
+        {
+            Ok(_) => println!(
+                "Nonce account {} created with {} SOL",
+                nonce_keypair.pubkey(),
+                lamports_to_sol(rent)
+            ),
+            Err(err) => fail_with(&err),
+        }
+    }
+
+    async fn nonce_show(&self, args: NonceShowArgs) {
+        let address = Pubkey::from_str(&args.address)
+            .unwrap_or_else(|_| abort(ExitCode::Config, "Failed to parse nonce address"));
+        match self.get_nonce_data(&address).await {
+            Ok(data) => {
+                println!("{}: {}", "Address".bold(), address);
+                println!("{}: {}", "Authority".bold(), data.authority);
+                println!("{}: {}", "Blockhash".bold(), data.blockhash());
+                println!(
+                    "{}: {} lamports",
+                    "Fee per signature".bold(),
+                    data.fee_calculator.lamports_per_signature
+                );
+            }
+            Err(err) => {
+                println!("{} {}", "ERROR".bold().red(), err);
+                fail_with(&err);
+            }
+        }
+    }
+
+    async fn nonce_close(&self, args: NonceCloseArgs) {
+        let signer = self.signer();
+        let address = Pubkey::from_str(&args.address)
+            .unwrap_or_else(|_| abort(ExitCode::Config, "Failed to parse nonce address"));
+        let to = match args.to {
+            Some(to) => Pubkey::from_str(&to)
+                .unwrap_or_else(|_| abort(ExitCode::Config, "Failed to parse wallet address")),
+            None => signer.pubkey(),
+        };
+        let Ok(lamports) = self.rpc_client().get_balance(&address).await else {
+            println!("{} Failed to fetch nonce balance", "ERROR".bold().red());
+            return;
+        };
+        let ix =
+            system_instruction::withdraw_nonce_account(&address, &signer.pubkey(), &to, lamports);
+        match self
+            .send_and_confirm(&[ix], ComputeBudget::Dynamic, false)
+            .await
+        {
+            Ok(_) => println!(
+                "Closed {}, {} SOL sent to {}",
+                address,
+                lamports_to_sol(lamports),
+                to
+            ),
+            Err(err) => fail_with(&err),
+        }
+    }
+
+    async fn nonce_broadcast(&self, args: NonceBroadcastArgs) {
+        // Accept the transaction itself or a file holding it
+        let encoded = match std::fs::read_to_string(&args.transaction) {
+            Ok(contents) => contents.trim().to_string(),
+            Err(_) => args.transaction.trim().to_string(),
+        };
+        let tx: Transaction = match base64::prelude::BASE64_STANDARD
+            .decode(encoded)
+            .ok()
+            .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        {
+            Some(tx) => tx,
+            None => {
+                println!("{} Failed to decode transaction", "ERROR".bold().red());
+                return;
+            }
+        };
+
+        // Show what would happen instead of sending
+        if self.dry_run {
+            let signers = tx
+                .message
+                .signer_keys()
+                .into_iter()
+                .copied()
+                .collect::<Vec<_>>();
+            self.simulate_transaction(&tx, &signers)
+                .await
+                .print("transaction");
+            return;
+        }
+
+        match self
+            .rpc_client()
+            .send_and_confirm_transaction_with_spinner(&tx)
+            .await
+        {
+            Ok(sig) => println!("{} {}", "OK".bold().green(), sig),
+            Err(err) => {
+                println!("{} {}", "ERROR".bold().red(), err);
+                fail_with(&err);
+            }
+        }
+    }
+
+    /// Sends as usual, or, with a nonce account, builds a transaction that stays valid until
+    /// the nonce advances and optionally stops after signing so it can be broadcast later.
+    pub async fn send_and_confirm_durable(
+        &self,
+        ixs: &[Instruction],
+        compute_budget: ComputeBudget,
+        durable: &DurableNonceArgs,
+    ) -> ClientResult<Signature> {
+        let Some(nonce) = &durable.nonce else {
+            return self.send_and_confirm(ixs, compute_budget, false).await;
+        };
+        let signer = self.signer();
+        let nonce = Pubkey::from_str(nonce)
+            .map_err(|err| ClientError::from(ClientErrorKind::Custom(err.to_string())))?;
+
+        // Advancing the nonce must come first. Compute units can't be simulated offline.
+        let cu_limit = match compute_budget {
+            ComputeBudget::Dynamic => CU_LIMIT_MAX,
+            ComputeBudget::Fixed(cus) => cus,
+        };
+        let mut final_ixs = vec![
+            system_instruction::advance_nonce_account(&nonce, &signer.pubkey()),
+            ComputeBudgetInstruction::set_compute_unit_limit(cu_limit),
+            ComputeBudgetInstruction::set_compute_unit_price(self.maintenance_fee()),
+        ];
+        final_ixs.extend_from_slice(ixs);
+
+        // Sign against the nonce value instead of a recent blockhash
+        let blockhash = match &durable.nonce_blockhash {
+            Some(blockhash) => Hash::from_str(blockhash)
+                .map_err(|err| ClientError::from(ClientErrorKind::Custom(err.to_string())))?,
+            None => self.get_nonce_data(&nonce).await?.blockhash(),
+        };
+        let mut tx = Transaction::new_with_payer(&final_ixs, Some(&signer.pubkey()));
+        tx.sign(&[&signer], blockhash);
+
+        if durable.sign_only {
+            let encoded = base64::prelude::BASE64_STANDARD.encode(bincode::serialize(&tx).unwrap());
+            println!("\n{}\n{}", "Signed transaction".bold(), encoded);
+            println!("\nSend it from any machine with `ore nonce broadcast <TRANSACTION>`");
+            return Ok(tx.signatures[0]);
+        }
+
+        // Show what would happen instead of sending
+        if self.dry_run {
+            self.simulate_transaction(&tx, &[signer.pubkey()])
+                .await
+                .print("transaction");
+            return Ok(Signature::default());
+        }
+
+        let progress_bar = spinner::new_progress_bar();
+        progress_bar.set_message("Submitting durable transaction...");
+        match self.rpc_client().send_and_confirm_transaction(&tx).await {
+            Ok(sig) => {
+                progress_bar.finish_with_message(format!("{} {}", "OK".bold().green(), sig));
+                Ok(sig)
+            }
+            Err(err) => {
+                progress_bar.finish_with_message(format!("{}: {}", "ERROR".bold().red(), err));
+                Err(err)
+            }
+        }
+    }
+
+    async fn get_nonce_data(&self, address: &Pubkey) -> ClientResult<Data> {
+        let account = self.rpc_client().get_account(address).await?;
+        let versions: Versions = bincode::deserialize(&account.data)
+            .map_err(|err| ClientError::from(ClientErrorKind::Custom(err.to_string())))?;
+        match versions.state() {
+            State::Initialized(data) => Ok(data.clone()),
+            State::Uninitialized => Err(ClientErrorKind::Custom(format!(
+                "{} is not an initialized nonce account",
+                address
+            ))
+            .into()),
+        }
+    }
+}
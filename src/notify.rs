@@ -0,0 +1,179 @@
+use std::{sync::Arc, time::Duration};
+
+use colored::*;
+use serde_json::{json, Value};
+use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
+use solana_sdk::signature::Signature;
+
+use crate::{
+    control::Control,
+    labels::label,
+    price::{ore_usd, sol_usd, Prices},
+    utils::{amount_u64_to_f64, amount_u64_to_string},
+};
+
+const TELEGRAM_API: &str = "https://api.telegram.org";
+const TELEGRAM_POLL_SECONDS: u64 = 30;
+const EXPLORER_TX_URL: &str = "https://solscan.io/tx";
+const DISCORD_COLOR_LANDED: u32 = 0x2ecc71;
+const DISCORD_COLOR_FAILED: u32 = 0xe74c3c;
+
+pub struct Telegram {
+    pub token: String,
+    pub chat_id: String,
+}
+
+/// What happened in a single mining pass.
+pub struct PassSummary {
+    pub pass: u64,
+    pub difficulties: Vec<(Pubkey, u32)>,
+    pub bus: Pubkey,
+    pub tip: u64,
+    pub signature: Option<Signature>,
+    pub reward: u64,
+    pub session_reward: u64,
+    pub prices: Option<Prices>,
+}
+
+impl PassSummary {
+    pub fn best_difficulty(&self) -> u32 {
+        self.difficulties.iter().map(|(_, d)| *d).max().unwrap_or(0)
+    }
+
+    pub fn to_text(&self) -> String {
+        format!(
+            "Pass {}: {}\nBest difficulty: {}\nReward: {} ORE{}\nSession: {} ORE{}\nTip paid: {} lamports{}",
+            self.pass,
+            match self.signature {
+                Some(sig) => format!("landed {}", sig),
+                None => "failed".to_string(),
+            },
+            self.best_difficulty(),
+            amount_u64_to_string(self.reward),
+            ore_usd(self.prices, amount_u64_to_f64(self.reward)),
+            amount_u64_to_string(self.session_reward),
+            ore_usd(self.prices, amount_u64_to_f64(self.session_reward)),
+            self.tip,
+            sol_usd(self.prices, lamports_to_sol(self.tip)),
+        )
+    }
+
+    fn to_discord_embed(&self) -> Value {
+        let difficulties = self
+            .difficulties
+            .iter()
+            .map(|(pubkey, difficulty)| format!("`{}` {}", label(pubkey), difficulty))
+            .collect::<Vec<_>>()
+            .join("\n");
+        json!({
+            "title": match self.signature {
+                Some(_) => format!("Pass {} landed", self.pass),
+                None => format!("Pass {} failed", self.pass),
+            },
+            "url": self.signature.map(|sig| format!("{}/{}", EXPLORER_TX_URL, sig)),
+            "color": if self.signature.is_some() { DISCORD_COLOR_LANDED } else { DISCORD_COLOR_FAILED },
+            "description": difficulties,
+            "fields": [
+                { "name": "Bus", "value": self.bus.to_string(), "inline": false },
+                { "name": "Tip paid", "value": format!("{} lamports{}", self.tip, sol_usd(self.prices, lamports_to_sol(self.tip))), "inline": true },
+                { "name": "Reward", "value": format!("{} ORE{}", amount_u64_to_string(self.reward), ore_usd(self.prices, amount_u64_to_f64(self.reward))), "inline": true },
+                { "name": "Session earnings", "value": format!("{} ORE{}", amount_u64_to_string(self.session_reward), ore_usd(self.prices, amount_u64_to_f64(self.session_reward))), "inline": true },
+            ],
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct Notifier {
+    pub telegram: Option<Telegram>,
+    pub discord_webhook: Option<String>,
+    http: reqwest::Client,
+}
+
+impl Notifier {
+    pub fn new(telegram: Option<Telegram>, discord_webhook: Option<String>) -> Self {
+        Self {
+            telegram,
+            discord_webhook,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Sends a plain text message to every configured channel.
+    pub async fn send(&self, text: &str) {
+        self.telegram(text).await;
+        self.discord(json!({ "content": text })).await;
+    }
+
+    pub async fn pass(&self, summary: &PassSummary) {
+        self.telegram(&summary.to_text()).await;
+        self.discord(json!({ "embeds": [summary.to_discord_embed()] }))
+            .await;
+    }
+
+    async fn telegram(&self, text: &str) {
+        if let Some(telegram) = &self.telegram {
+            let url = format!("{}/bot{}/sendMessage", TELEGRAM_API, telegram.token);
+            let body = json!({ "chat_id": telegram.chat_id, "text": text });
+            if let Err(err) = self.http.post(url).json(&body).send().await {
+                println!("{} Telegram: {}", "WARNING".bold().yellow(), err);
+            }
+        }
+    }
+
+    async fn discord(&self, body: Value) {
+        if let Some(webhook) = &self.discord_webhook {
+            if let Err(err) = self.http.post(webhook).json(&body).send().await {
+                println!("{} Discord: {}", "WARNING".bold().yellow(), err);
+            }
+        }
+    }
+
+    /// Polls the Telegram bot for /status, /pause and /resume commands.
+    pub fn listen(self: Arc<Self>, control: Arc<Control>) {
+        if self.telegram.is_none() {
+            return;
+        }
+        tokio::spawn(async move {
+            let telegram = self.telegram.as_ref().unwrap();
+            let mut offset = 0i64;
+            loop {
+                let url = format!(
+                    "{}/bot{}/getUpdates?timeout={}&offset={}",
+                    TELEGRAM_API, telegram.token, TELEGRAM_POLL_SECONDS, offset
+                );
+                let updates = match self.http.get(url).send().await {
+                    Ok(res) => res.json::<Value>().await.unwrap_or_default(),
+                    Err(_) => {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+                };
+                let Some(updates) = updates["result"].as_array() else {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    continue;
+                };
+                for update in updates {
+                    offset = offset.max(update["update_id"].as_i64().unwrap_or(0) + 1);
+                    let message = &update["message"];
+                    if message["chat"]["id"].to_string().ne(&telegram.chat_id) {
+                        continue;
+                    }
+                    let reply = match message["text"].as_str().unwrap_or_default().trim() {
+                        "/status" => control.status(),
+                        "/pause" => {
+                            control.pause();
+                            "Mining paused".to_string()
+                        }
+                        "/resume" => {
+                            control.resume();
+                            "Mining resumed".to_string()
+                        }
+                        _ => "Commands: /status, /pause, /resume".to_string(),
+                    };
+                    self.telegram(&reply).await;
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,129 @@
+use core_affinity::CoreId;
+
+/// Where the kernel lists the CPUs of each NUMA node.
+const NODES_DIR: &str = "/sys/devices/system/node";
+
+/// Where the kernel describes each CPU, including which hardware threads share its core.
+const CPUS_DIR: &str = "/sys/devices/system/cpu";
+
+/// The CPUs of each NUMA node, or nothing where the topology isn't exposed.
+pub fn nodes() -> Vec<Vec<usize>> {
+    let Ok(entries) = std::fs::read_dir(NODES_DIR) else {
+        return vec![];
+    };
+    let mut nodes: Vec<(usize, Vec<usize>)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let id = name.strip_prefix("node")?.parse::<usize>().ok()?;
+            let cpulist = std::fs::read_to_string(entry.path().join("cpulist")).ok()?;
+            Some((id, parse_cpulist(cpulist.trim())))
+        })
+        .filter(|(_, cpus)| !cpus.is_empty())
+        .collect();
+    nodes.sort_by_key(|(id, _)| *id);
+    nodes.into_iter().map(|(_, cpus)| cpus).collect()
+}
+
+/// Spreads `threads` over the NUMA nodes in turn, taking each node's CPUs in order so
+/// physical cores fill before their SMT siblings.
+pub fn spread_cores(threads: usize) -> Vec<CoreId> {
+    let nodes = nodes();
+    if nodes.is_empty() {
+        return vec![];
+    }
+    let mut next = vec![0; nodes.len()];
+    (0..threads)
+        .map(|i| {
+            let node = i % nodes.len();
+            let cpus = &nodes[node];
+            let cpu = cpus[next[node] % cpus.len()];
+            next[node] += 1;
+            CoreId { id: cpu }
+        })
+        .collect()
+}
+
+/// The hardware threads of each physical core, lowest CPU first, in core order. Nothing where
+/// the topology isn't exposed.
+pub fn physical_cores() -> Vec<Vec<usize>> {
+    let Ok(entries) = std::fs::read_dir(CPUS_DIR) else {
+        return vec![];
+    };
+    let mut cores: Vec<Vec<usize>> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_prefix("cpu")?.parse::<usize>().ok()?;
+            let siblings =
+                std::fs::read_to_string(entry.path().join("topology").join("thread_siblings_list"))
+                    .ok()?;
+            let mut siblings = parse_cpulist(siblings.trim());
+            siblings.sort();
+            Some(siblings)
+        })
+        .filter(|siblings| !siblings.is_empty())
+        .collect();
+    cores.sort();
+    cores.dedup();
+    cores
+}
+
+/// One CPU of each physical core that is available and lies wholly at or past `offset`, so
+/// no two threads share a core and none lands on the cores left to the runtime.
+pub fn free_cores(physical: &[Vec<usize>], available: &[usize], offset: usize) -> Vec<usize> {
+    physical
+        .iter()
+        .filter(|siblings| siblings.iter().all(|cpu| cpu.ge(&offset)))
+        .filter_map(|siblings| siblings.iter().find(|cpu| available.contains(cpu)).copied())
+        .collect()
+}
+
+/// Parses a kernel CPU list like `0-15,32-47`.
+fn parse_cpulist(cpulist: &str) -> Vec<usize> {
+    cpulist
+        .split(',')
+        .filter_map(|range| match range.split_once('-') {
+            Some((start, end)) => Some((start.parse().ok()?..=end.parse().ok()?).collect()),
+            None => Some(vec![range.parse().ok()?]),
+        })
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cpulists() {
+        assert_eq!(parse_cpulist("0-3,8,10-11"), vec![0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpulist("5"), vec![5]);
+        assert!(parse_cpulist("").is_empty());
+    }
+
+    #[test]
+    fn takes_one_cpu_per_core_with_adjacent_siblings() {
+        let physical = vec![vec![0, 1], vec![2, 3], vec![4, 5], vec![6, 7]];
+        let available: Vec<usize> = (0..8).collect();
+        assert_eq!(free_cores(&physical, &available, 0), vec![0, 2, 4, 6]);
+        assert_eq!(free_cores(&physical, &available, 2), vec![2, 4, 6]);
+    }
+
+    #[test]
+    fn takes_one_cpu_per_core_with_split_siblings() {
+        let physical = vec![vec![0, 4], vec![1, 5], vec![2, 6], vec![3, 7]];
+        let available: Vec<usize> = (0..8).collect();
+        assert_eq!(free_cores(&physical, &available, 0), vec![0, 1, 2, 3]);
+        assert_eq!(free_cores(&physical, &available, 2), vec![2, 3]);
+        // A core with a sibling before the offset stays with the runtime
+        assert!(free_cores(&physical, &available, 4).is_empty());
+    }
+
+    #[test]
+    fn skips_unavailable_cpus() {
+        let physical = vec![vec![0, 1], vec![2, 3]];
+        assert_eq!(free_cores(&physical, &[1, 3], 0), vec![1, 3]);
+        assert_eq!(free_cores(&physical, &[0], 0), vec![0]);
+    }
+}
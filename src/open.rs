@@ -1,28 +1,126 @@
-use solana_sdk::{signature::Signer, compute_budget::ComputeBudgetInstruction};
+use colored::*;
+use futures::future::join_all;
+use solana_sdk::signature::{Keypair, Signer};
 
-use crate::{send_and_confirm::ComputeBudget, utils::proof_pubkey, Miner};
+use crate::{
+    args::OpenArgs,
+    exit::{abort, fail_with, ExitCode},
+    keys::read_keypair,
+    labels::label,
+    utils::{get_accounts, proof_pubkey},
+    Miner,
+};
+
+/// Wallets registered per transaction. Each adds a signature and two accounts, so this
+/// keeps the transaction well under the size limit.
+const OPENS_PER_TX: usize = 4;
 
 impl Miner {
+    /// Registers a single proof. The authority owns and mines it, while the payer covers the
+    /// rent, so a cold authority needs no SOL of its own.
+    pub async fn open(&self, args: OpenArgs) {
+        let authority = match args.authority {
+            Some(source) => read_keypair(&source).unwrap_or_else(|err| {
+                abort(
+                    ExitCode::Config,
+                    format!("Failed to read authority keypair {}", err),
+                )
+            }),
+            None => self.signer(),
+        };
+        let payer = args.payer.map(|source| {
+            read_keypair(&source).unwrap_or_else(|err| {
+                abort(
+                    ExitCode::Config,
+                    format!("Failed to read payer keypair {}", err),
+                )
+            })
+        });
+        let payer = payer.as_ref().unwrap_or(&authority);
+
+        let proof_address = proof_pubkey(authority.pubkey());
+        if self.rpc_client().get_account(&proof_address).await.is_ok() {
+            println!(
+                "{} already has a proof at {}",
+                authority.pubkey(),
+                proof_address
+            );
+            return;
+        }
+
+        println!(
+            "Opening proof for {}, rent paid by {}",
+            authority.pubkey(),
+            payer.pubkey()
+        );
+        let ixs = [ore_api::instruction::open(
+            authority.pubkey(),
+            authority.pubkey(),
+            payer.pubkey(),
+        )];
+        let signers: Vec<&Keypair> = if payer.pubkey().eq(&authority.pubkey()) {
+            vec![&authority]
+        } else {
+            vec![payer, &authority]
+        };
+        if let Err(err) = self.send_and_confirm_with_keys(&ixs, false, &signers).await {
+            println!("{} Failed to open proof: {}", "ERROR".bold().red(), err);
+            fail_with(&err);
+        }
+    }
+
     pub async fn open_all(&self) {
         let signers = self.multi_signers();
-        let client = self.rpc_client.clone();
-    
-        for signer in signers {
-            // Return early if miner is already registered
-            let proof_address = proof_pubkey(signer.pubkey());
-            println!("{}", signer.pubkey());
-    
-            if client.get_account(&proof_address).await.is_err() {
-                // Sign and send transaction.
-                println!("Generating proof account... for {}", signer.pubkey());
-                // let cu_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(CU_LIMIT_REGISTER + 1000);
-                let cu_price_ix = ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee);
-                let ix = ore_api::instruction::open(signer.pubkey(), signer.pubkey(), signer.pubkey());
-    
-                self.send_and_confirm_with_key(&mut [cu_price_ix, ix], false, &signer)
-                .await
-                .ok();
-            }
+        let client = self.rpc_client();
+
+        // Check every proof in one batch, and only register the wallets without one
+        let proof_addresses: Vec<_> = signers
+            .iter()
+            .map(|signer| proof_pubkey(signer.pubkey()))
+            .collect();
+        let proofs = get_accounts(&client, &proof_addresses).await;
+        let missing: Vec<&Keypair> = signers
+            .iter()
+            .zip(proofs.iter())
+            .filter(|(_, proof)| proof.is_none())
+            .map(|(signer, _)| signer)
+            .collect();
+        if missing.is_empty() {
+            return;
         }
+        println!(
+            "Generating proof accounts for {} of {} wallets...",
+            missing.len(),
+            signers.len()
+        );
+
+        // Pack several opens per transaction, each wallet paying its own rent, and send them all at once
+        let sends = missing.chunks(OPENS_PER_TX).map(|chunk| async move {
+            let ixs: Vec<_> = chunk
+                .iter()
+                .map(|signer| {
+                    ore_api::instruction::open(signer.pubkey(), signer.pubkey(), signer.pubkey())
+                })
+                .collect();
+            match self.send_and_confirm_with_keys(&ixs, false, chunk).await {
+                Ok(_) => chunk.len(),
+                Err(err) => {
+                    println!(
+                        "{} Failed to open proofs for {}: {}",
+                        "ERROR".bold().red(),
+                        chunk
+                            .iter()
+                            .map(|signer| label(&signer.pubkey()))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        err
+                    );
+                    fail_with(&err);
+                    0
+                }
+            }
+        });
+        let opened: usize = join_all(sends).await.into_iter().sum();
+        println!("Opened {} of {} missing proofs", opened, missing.len());
     }
 }
@@ -0,0 +1,69 @@
+use std::{io::Write, path::Path};
+
+use colored::*;
+use solana_sdk::signature::Signer;
+
+use crate::{traverse_folder, wallet_filter::WalletFilter};
+
+const MAX_SHOWN: usize = 20;
+
+/// Prompts the user to pick one keypair from the folder, returning its filepath.
+pub fn pick_keypair(folder_path: &str, filter: &WalletFilter) -> Option<String> {
+    // The same wallets in the same order as the folder is mined in, so the numbers stay put
+    // between runs. Files that aren't keypairs are just not offered.
+    let mut keypairs = vec![];
+    traverse_folder(Path::new(folder_path), &mut keypairs, &mut vec![]);
+    let candidates: Vec<(String, String)> = keypairs
+        .into_iter()
+        .filter(|(path, keypair)| filter.includes(path, &keypair.pubkey()))
+        .map(|(path, keypair)| (path.display().to_string(), keypair.pubkey().to_string()))
+        .collect();
+    if candidates.is_empty() {
+        println!("No keypairs found in {}", folder_path);
+        return None;
+    }
+    // Filtering down to one wallet picks it
+    if candidates.len() == 1 && !filter.is_empty() {
+        return Some(candidates[0].0.clone());
+    }
+
+    let mut query = String::new();
+    loop {
+        let matches: Vec<&(String, String)> = candidates
+            .iter()
+            .filter(|(path, pubkey)| fuzzy_match(&query, pubkey) || fuzzy_match(&query, path))
+            .collect();
+        if matches.len() == 1 && !query.is_empty() {
+            return Some(matches[0].0.clone());
+        }
+        for (i, (path, pubkey)) in matches.iter().take(MAX_SHOWN).enumerate() {
+            println!("{:>3}: {} {}", i + 1, pubkey.bold(), path.dimmed());
+        }
+        if matches.len() > MAX_SHOWN {
+            println!("     ... {} more", matches.len() - MAX_SHOWN);
+        }
+        print!("Search, or pick a number: ");
+        std::io::stdout().flush().ok();
+
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input).unwrap_or(0) == 0 {
+            return None;
+        }
+        let input = input.trim();
+        if let Ok(i) = input.parse::<usize>() {
+            if let Some((path, _)) = matches.get(i.wrapping_sub(1)) {
+                return Some(path.clone());
+            }
+        }
+        query = input.to_string();
+    }
+}
+
+/// Case-insensitive subsequence match.
+fn fuzzy_match(query: &str, candidate: &str) -> bool {
+    let mut chars = candidate.chars().map(|c| c.to_ascii_lowercase());
+    query
+        .chars()
+        .map(|c| c.to_ascii_lowercase())
+        .all(|q| chars.any(|c| c == q))
+}
@@ -0,0 +1,97 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::Miner;
+
+const COINGECKO_URL: &str =
+    "https://api.coingecko.com/api/v3/simple/price?ids=ore,solana&vs_currencies=usd";
+
+/// How long fetched prices are reused before asking CoinGecko again.
+const PRICE_TTL: Duration = Duration::from_secs(60);
+
+/// USD prices of ORE and SOL.
+#[derive(Clone, Copy, Debug)]
+pub struct Prices {
+    pub ore_usd: f64,
+    pub sol_usd: f64,
+}
+
+impl Prices {
+    /// How many SOL one ORE is worth.
+    pub fn ore_in_sol(&self) -> f64 {
+        self.ore_usd / self.sol_usd
+    }
+}
+
+#[derive(Deserialize)]
+struct UsdQuote {
+    usd: f64,
+}
+
+#[derive(Deserialize)]
+struct CoinGeckoResponse {
+    ore: UsdQuote,
+    solana: UsdQuote,
+}
+
+pub async fn fetch_prices() -> Option<Prices> {
+    let res = reqwest::get(COINGECKO_URL).await.ok()?;
+    let quotes = res.json::<CoinGeckoResponse>().await.ok()?;
+    Some(Prices {
+        ore_usd: quotes.ore.usd,
+        sol_usd: quotes.solana.usd,
+    })
+}
+
+/// Prices fetched on demand and cached for a minute, so a mining loop doesn't hammer the API.
+#[derive(Default)]
+pub struct PriceFeed {
+    cached: Mutex<Option<(Instant, Prices)>>,
+}
+
+impl PriceFeed {
+    pub async fn prices(&self) -> Option<Prices> {
+        let cached = *self.cached.lock().unwrap();
+        if let Some((fetched_at, prices)) = cached {
+            if fetched_at.elapsed().lt(&PRICE_TTL) {
+                return Some(prices);
+            }
+        }
+        match fetch_prices().await {
+            Some(prices) => {
+                *self.cached.lock().unwrap() = Some((Instant::now(), prices));
+                Some(prices)
+            }
+            // Stale prices beat none at all
+            None => cached.map(|(_, prices)| prices),
+        }
+    }
+}
+
+/// ` ($1.23)` for an ORE amount, or nothing without prices.
+pub fn ore_usd(prices: Option<Prices>, ore: f64) -> String {
+    prices
+        .map(|prices| format!(" (${:.2})", ore * prices.ore_usd))
+        .unwrap_or_default()
+}
+
+/// ` ($1.23)` for a SOL amount, or nothing without prices.
+pub fn sol_usd(prices: Option<Prices>, sol: f64) -> String {
+    prices
+        .map(|prices| format!(" (${:.2})", sol * prices.sol_usd))
+        .unwrap_or_default()
+}
+
+impl Miner {
+    /// Current prices if USD display is enabled with --usd.
+    pub async fn prices(&self) -> Option<Prices> {
+        match &self.price_feed {
+            Some(price_feed) => price_feed.prices().await,
+            None => None,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use colored::*;
+use solana_program::native_token::{lamports_to_sol, sol_to_lamports};
+use solana_rpc_client::spinner;
+
+use crate::{
+    args::ProfitArgs,
+    benchmark::{measure_hashrate, TEST_DURATION},
+    cu_limits::CU_LIMIT_CLAIM,
+    price::fetch_prices,
+    rewards::{expected_reward, PASS_SECONDS},
+    utils::{amount_u64_to_f64, get_config},
+    Miner,
+};
+
+/// Lamports charged per transaction signature.
+pub const SIGNATURE_FEE: u64 = 5000;
+
+impl Miner {
+    pub async fn profit(&self, args: ProfitArgs) {
+        let hashrate = match args.hashrate {
+            Some(hashrate) => hashrate,
+            None => {
+                self.check_num_cores(args.threads);
+                let progress_bar = spinner::new_progress_bar();
+                progress_bar.set_message(format!(
+                    "Benchmarking. This will take {} sec...",
+                    TEST_DURATION
+                ));
+                let hashrate = measure_hashrate(args.threads, TEST_DURATION);
+                progress_bar.finish_with_message(format!("Hashpower: {} H/sec", hashrate));
+                hashrate
+            }
+        };
+        let wallets = args.wallets.unwrap_or_else(|| match self.folder_path {
+            Some(_) => self.multi_signer_files().len().max(1) as u64,
+            None => 1,
+        });
+        let config = get_config(&self.rpc_client()).await;
+
+        // Wallets are hashed one after another within the same pass window
+        let window = PASS_SECONDS.saturating_sub(args.buffer_time) as f64 / wallets as f64;
+        let hashes = hashrate as f64 * window;
+        let ore_per_pass = amount_u64_to_f64(1) * expected_reward(hashes, &config) * wallets as f64;
+        let passes_per_day = (86_400 / PASS_SECONDS) as f64;
+        let ore_per_day = ore_per_pass * passes_per_day;
+
+        // The bundle carries one transaction per wallet plus the fee payer's signature
+        let signature_fees = SIGNATURE_FEE * (wallets + 1);
+        let tips_per_day = args.jito_tip as f64 * passes_per_day;
+        let fees_per_day = signature_fees as f64 * passes_per_day
+            + (SIGNATURE_FEE + self.maintenance_fee() * CU_LIMIT_CLAIM as u64 / 1_000_000) as f64;
+        let sol_per_day = lamports_to_sol((tips_per_day + fees_per_day) as u64);
+
+        println!(
+            "{}: {} H/sec over {:.0}s per wallet ({} wallets)",
+            "Hashrate".bold(),
+            hashrate,
+            window,
+            wallets
+        );
+        println!(
+            "{}: {:.6} ORE per pass, {:.4} ORE per day",
+            "Expected".bold(),
+            ore_per_pass,
+            ore_per_day
+        );
+        println!(
+            "{}: {:.6} SOL per day ({} SOL tips, {} SOL fees incl. one daily claim)",
+            "Cost".bold(),
+            sol_per_day,
+            lamports_to_sol(tips_per_day as u64),
+            lamports_to_sol(fees_per_day as u64),
+        );
+
+        let Some(prices) = fetch_prices().await else {
+            println!(
+                "{} Could not fetch ORE/SOL prices. Skipping break-even estimate.",
+                "WARNING".bold().yellow()
+            );
+            return;
+        };
+        let earned_sol = ore_per_day * prices.ore_in_sol();
+        let net_sol = earned_sol - sol_per_day;
+        println!(
+            "{}: {:.6} SOL (${:.2}) per day at ${:.2}/ORE, ${:.2}/SOL",
+            "Net".bold(),
+            net_sol,
+            net_sol * prices.sol_usd,
+            prices.ore_usd,
+            prices.sol_usd
+        );
+
+        // Highest tip at which a pass still pays for itself
+        let earned_per_pass = sol_to_lamports(ore_per_pass * prices.ore_in_sol());
+        let break_even_tip = earned_per_pass.saturating_sub(signature_fees);
+        println!(
+            "{}: {} lamports (currently tipping {})",
+            "Break-even tip".bold(),
+            break_even_tip,
+            args.jito_tip
+        );
+        if args.jito_tip.gt(&break_even_tip) {
+            println!(
+                "{} Your tip costs more than a pass is expected to earn.",
+                "WARNING".bold().yellow()
+            );
+        }
+    }
+}
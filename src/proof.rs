@@ -0,0 +1,90 @@
+use std::str::FromStr;
+
+use chrono::{TimeZone, Utc};
+use colored::*;
+use serde_json::json;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+use crate::{
+    args::ProofArgs,
+    utils::{amount_u64_to_string, get_clock, get_proof, print_json, proof_pubkey},
+    Miner,
+};
+
+impl Miner {
+    pub async fn proof(&self, args: ProofArgs) {
+        let authority = match args.address {
+            Some(address) => match Pubkey::from_str(&address) {
+                Ok(address) => address,
+                Err(_) => {
+                    println!("Invalid address: {:?}", address);
+                    return;
+                }
+            },
+            None => self.signer().pubkey(),
+        };
+        let address = proof_pubkey(authority);
+        let proof = get_proof(&self.rpc_client(), address).await;
+        let clock = get_clock(&self.rpc_client()).await;
+
+        // A proof may submit once a minute after its last hash
+        let next_window = proof
+            .last_hash_at
+            .saturating_add(60)
+            .saturating_sub(clock.unix_timestamp);
+        let last_hash_at = Utc
+            .timestamp_opt(proof.last_hash_at, 0)
+            .single()
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default();
+
+        if self.json {
+            print_json(&json!({
+                "address": address.to_string(),
+                "authority": proof.authority.to_string(),
+                "miner": proof.miner.to_string(),
+                "balance": proof.balance,
+                "challenge": bs58::encode(proof.challenge).into_string(),
+                "last_hash": bs58::encode(proof.last_hash).into_string(),
+                "last_hash_at": proof.last_hash_at,
+                "last_stake_at": proof.last_stake_at,
+                "total_hashes": proof.total_hashes,
+                "total_rewards": proof.total_rewards,
+                "next_submission_in": next_window.max(0),
+            }));
+            return;
+        }
+
+        println!("{}: {}", "Address".bold(), address);
+        println!("{}: {}", "Authority".bold(), proof.authority);
+        println!("{}: {}", "Miner".bold(), proof.miner);
+        println!(
+            "{}: {} ORE",
+            "Balance".bold(),
+            amount_u64_to_string(proof.balance)
+        );
+        println!(
+            "{}: {}",
+            "Challenge".bold(),
+            bs58::encode(proof.challenge).into_string()
+        );
+        println!(
+            "{}: {}",
+            "Last hash".bold(),
+            bs58::encode(proof.last_hash).into_string()
+        );
+        println!("{}: {}", "Last hash at".bold(), last_hash_at);
+        println!("{}: {}", "Total hashes".bold(), proof.total_hashes);
+        println!(
+            "{}: {} ORE",
+            "Total rewards".bold(),
+            amount_u64_to_string(proof.total_rewards)
+        );
+        if next_window > 0 {
+            println!("{}: in {}s", "Next submission".bold(), next_window);
+        } else {
+            println!("{}: {}", "Next submission".bold(), "open now".green());
+        }
+    }
+}
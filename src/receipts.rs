@@ -0,0 +1,316 @@
+use std::str::FromStr;
+
+use colored::*;
+use serde::{Deserialize, Serialize};
+use solana_program::{
+    hash::{hashv, Hash},
+    pubkey::Pubkey,
+};
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_transaction_status::{EncodedTransaction, UiMessage, UiTransactionTokenBalance};
+
+use crate::{args::ReceiptsArgs, utils::amount_u64_to_string, Miner};
+
+/// Domain separators for leaves and interior nodes of the receipts tree.
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// A single payout: `amount` grains claimed by `wallet` into `beneficiary` in tx `signature`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReceiptEntry {
+    pub wallet: String,
+    pub beneficiary: String,
+    pub amount: u64,
+    pub signature: String,
+}
+
+impl ReceiptEntry {
+    fn leaf(&self) -> Hash {
+        hashv(&[
+            LEAF_PREFIX,
+            self.wallet.as_bytes(),
+            self.beneficiary.as_bytes(),
+            &self.amount.to_le_bytes(),
+            self.signature.as_bytes(),
+        ])
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Receipt {
+    pub entry: ReceiptEntry,
+    pub proof: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Receipts {
+    pub operator: String,
+    pub created_at: i64,
+    pub root: String,
+    pub operator_signature: String,
+    pub receipts: Vec<Receipt>,
+}
+
+impl Receipts {
+    /// Builds the tree over `entries` and signs its root with the operator keypair.
+    pub fn build(entries: Vec<ReceiptEntry>, operator: &Keypair) -> Self {
+        let leaves: Vec<Hash> = entries.iter().map(|e| e.leaf()).collect();
+        let levels = build_levels(leaves);
+        let root = levels
+            .last()
+            .and_then(|level| level.first())
+            .copied()
+            .unwrap_or_default();
+        let created_at = chrono::Utc::now().timestamp();
+        let operator_signature = operator.sign_message(&signed_message(&root, created_at));
+        let receipts = entries
+            .into_iter()
+            .enumerate()
+            .map(|(i, entry)| Receipt {
+                entry,
+                proof: proof_for(&levels, i)
+                    .iter()
+                    .map(|h| h.to_string())
+                    .collect(),
+            })
+            .collect();
+        Self {
+            operator: operator.pubkey().to_string(),
+            created_at,
+            root: root.to_string(),
+            operator_signature: operator_signature.to_string(),
+            receipts,
+        }
+    }
+
+    pub fn write(&self, path: &str) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    pub fn read(path: &str) -> std::io::Result<Self> {
+        let data = std::fs::read(path)?;
+        serde_json::from_slice(&data).map_err(std::io::Error::other)
+    }
+
+    /// Checks the operator signature over the root.
+    pub fn verify_root(&self) -> bool {
+        let (Ok(operator), Ok(root), Ok(sig)) = (
+            Pubkey::from_str(&self.operator),
+            Hash::from_str(&self.root),
+            Signature::from_str(&self.operator_signature),
+        ) else {
+            return false;
+        };
+        sig.verify(operator.as_ref(), &signed_message(&root, self.created_at))
+    }
+
+    /// Checks that a receipt's proof path leads to the signed root.
+    pub fn verify_receipt(&self, receipt: &Receipt) -> bool {
+        let Ok(root) = Hash::from_str(&self.root) else {
+            return false;
+        };
+        let mut node = receipt.entry.leaf();
+        for sibling in receipt.proof.iter() {
+            let Ok(sibling) = Hash::from_str(sibling) else {
+                return false;
+            };
+            node = parent(&node, &sibling);
+        }
+        node == root
+    }
+}
+
+fn signed_message(root: &Hash, created_at: i64) -> Vec<u8> {
+    let mut message = b"ore-receipts".to_vec();
+    message.extend_from_slice(root.as_ref());
+    message.extend_from_slice(&created_at.to_le_bytes());
+    message
+}
+
+// Pairs are hashed in sorted order so proofs don't need left/right flags.
+fn parent(a: &Hash, b: &Hash) -> Hash {
+    if a.as_ref() <= b.as_ref() {
+        hashv(&[NODE_PREFIX, a.as_ref(), b.as_ref()])
+    } else {
+        hashv(&[NODE_PREFIX, b.as_ref(), a.as_ref()])
+    }
+}
+
+fn build_levels(leaves: Vec<Hash>) -> Vec<Vec<Hash>> {
+    let mut levels = vec![leaves];
+    while levels.last().unwrap().len() > 1 {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => parent(a, b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+fn proof_for(levels: &[Vec<Hash>], mut index: usize) -> Vec<Hash> {
+    let mut proof = vec![];
+    for level in levels.iter().take(levels.len().saturating_sub(1)) {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            proof.push(level[sibling]);
+        }
+        index /= 2;
+    }
+    proof
+}
+
+impl Miner {
+    pub async fn receipts(&self, args: ReceiptsArgs) {
+        let receipts = match Receipts::read(&args.file) {
+            Ok(receipts) => receipts,
+            Err(err) => {
+                println!(
+                    "{} Failed to read {}: {}",
+                    "ERROR".bold().red(),
+                    args.file,
+                    err
+                );
+                return;
+            }
+        };
+        if !receipts.verify_root() {
+            println!(
+                "{} Operator signature does not match root {}",
+                "ERROR".bold().red(),
+                receipts.root
+            );
+            return;
+        }
+        println!(
+            "Operator {} signed root {}",
+            receipts.operator, receipts.root
+        );
+
+        for receipt in receipts.receipts.iter() {
+            let entry = &receipt.entry;
+            if let Some(wallet) = &args.wallet {
+                if entry.wallet.ne(wallet) {
+                    continue;
+                }
+            }
+            let included = receipts.verify_receipt(receipt);
+            let paid = self.paid_amount(entry).await;
+            let ok = included && paid == Some(entry.amount);
+            println!(
+                "{} {} {} ORE -> {} ({})",
+                if ok {
+                    "OK".bold().green()
+                } else {
+                    "FAIL".bold().red()
+                },
+                entry.wallet,
+                amount_u64_to_string(entry.amount),
+                entry.beneficiary,
+                match (included, paid) {
+                    (false, _) => "not included in root".to_string(),
+                    (true, None) => "transaction not found".to_string(),
+                    (true, Some(paid)) if paid != entry.amount => {
+                        format!("on-chain delta {} ORE", amount_u64_to_string(paid))
+                    }
+                    _ => entry.signature.clone(),
+                }
+            );
+        }
+    }
+
+    /// Returns the token balance delta of the beneficiary in the receipt's transaction.
+    async fn paid_amount(&self, entry: &ReceiptEntry) -> Option<u64> {
+        let signature = Signature::from_str(&entry.signature).ok()?;
+        let tx = self.get_json_transaction(&signature).await?;
+        let meta = tx.transaction.meta?;
+        if meta.err.is_some() {
+            return None;
+        }
+        let EncodedTransaction::Json(ui_tx) = tx.transaction.transaction else {
+            return None;
+        };
+        let UiMessage::Raw(message) = ui_tx.message else {
+            return None;
+        };
+        let index = message
+            .account_keys
+            .iter()
+            .position(|key| key.eq(&entry.beneficiary))? as u8;
+        let pre = token_balance(meta.pre_token_balances.into(), index);
+        let post = token_balance(meta.post_token_balances.into(), index);
+        Some(post.saturating_sub(pre))
+    }
+}
+
+fn token_balance(balances: Option<Vec<UiTransactionTokenBalance>>, index: u8) -> u64 {
+    balances
+        .unwrap_or_default()
+        .iter()
+        .find(|b| b.account_index == index)
+        .and_then(|b| u64::from_str(&b.ui_token_amount.amount).ok())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(count: usize) -> Vec<ReceiptEntry> {
+        (0..count)
+            .map(|i| ReceiptEntry {
+                wallet: Pubkey::new_unique().to_string(),
+                beneficiary: Pubkey::new_unique().to_string(),
+                amount: 1_000 * (i as u64 + 1),
+                signature: Signature::new_unique().to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_receipt_proves_against_the_root() {
+        let operator = Keypair::new();
+        for count in [1, 2, 3, 5, 8] {
+            let receipts = Receipts::build(entries(count), &operator);
+            assert!(receipts.verify_root());
+            for receipt in receipts.receipts.iter() {
+                assert!(receipts.verify_receipt(receipt), "{} entries", count);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_tampered_receipts() {
+        let mut receipts = Receipts::build(entries(4), &Keypair::new());
+        receipts.receipts[1].entry.amount += 1;
+        assert!(!receipts.verify_receipt(&receipts.receipts[1]));
+
+        receipts.receipts[2].proof.pop();
+        assert!(!receipts.verify_receipt(&receipts.receipts[2]));
+
+        receipts.receipts[3].proof[0] = "not a hash".to_string();
+        assert!(!receipts.verify_receipt(&receipts.receipts[3]));
+    }
+
+    #[test]
+    fn rejects_a_root_the_operator_did_not_sign() {
+        let mut receipts = Receipts::build(entries(3), &Keypair::new());
+        receipts.operator = Keypair::new().pubkey().to_string();
+        assert!(!receipts.verify_root());
+
+        let mut receipts = Receipts::build(entries(3), &Keypair::new());
+        receipts.root = Hash::new_unique().to_string();
+        assert!(!receipts.verify_root());
+
+        let mut receipts = Receipts::build(entries(3), &Keypair::new());
+        receipts.created_at += 1;
+        assert!(!receipts.verify_root());
+    }
+}
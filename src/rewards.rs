@@ -1,28 +1,147 @@
+use colored::*;
+use ore_api::state::Config;
+use serde_json::json;
+use solana_rpc_client::spinner;
+
 use crate::{
-    utils::{amount_u64_to_string, get_config},
+    args::RewardsArgs,
+    benchmark::{measure_hashrate, TEST_DURATION},
+    price::ore_usd,
+    utils::{amount_u64_to_f64, amount_u64_to_string, get_config, print_json},
     Miner,
 };
 
+/// A proof can submit at most one solution per minute.
+pub const PASS_SECONDS: u64 = 60;
+
 impl Miner {
-    pub async fn rewards(&self) {
-        let config = get_config(&self.rpc_client).await;
+    pub async fn rewards(&self, args: RewardsArgs) {
+        let hashrate = match args.hashrate {
+            Some(hashrate) => Some(hashrate),
+            None if args.benchmark => {
+                self.check_num_cores(args.threads);
+                let progress_bar = spinner::new_progress_bar();
+                progress_bar.set_message(format!(
+                    "Benchmarking. This will take {} sec...",
+                    TEST_DURATION
+                ));
+                let hashrate = measure_hashrate(args.threads, TEST_DURATION);
+                progress_bar.finish_with_message(format!("Hashpower: {} H/sec", hashrate));
+                Some(hashrate)
+            }
+            None => None,
+        };
+        let hashes = hashrate
+            .map(|hashrate| hashrate as f64 * PASS_SECONDS.saturating_sub(args.buffer_time) as f64);
+
+        let config = get_config(&self.rpc_client()).await;
+        let prices = self.prices().await;
         let base_reward_rate = config.base_reward_rate;
+        if self.json {
+            let rewards: Vec<_> = (0..32)
+                .map(|i| {
+                    let difficulty = config.min_difficulty as u32 + i;
+                    json!({
+                        "difficulty": difficulty,
+                        "reward": base_reward_rate.saturating_mul(2u64.saturating_pow(i)),
+                        "chance": hashes.map(|hashes| p_at_least(hashes, difficulty)),
+                    })
+                })
+                .collect();
+            print_json(&json!({
+                "min_difficulty": config.min_difficulty,
+                "base_reward_rate": base_reward_rate,
+                "rewards": rewards,
+                "expected_per_pass": hashes.map(|hashes| expected_reward(hashes, &config)),
+            }));
+            return;
+        }
         println!("{}", config.top_balance);
         let mut s = format!(
-            "{}: {} ORE",
+            "{}: {} ORE{}{}",
             config.min_difficulty,
-            amount_u64_to_string(base_reward_rate)
+            amount_u64_to_string(base_reward_rate),
+            ore_usd(prices, amount_u64_to_f64(base_reward_rate)),
+            chance(hashes, config.min_difficulty as u32),
         )
         .to_string();
         for i in 1..32 {
             let reward_rate = base_reward_rate.saturating_mul(2u64.saturating_pow(i));
             s = format!(
-                "{}\n{}: {} ORE",
+                "{}\n{}: {} ORE{}{}",
                 s,
                 config.min_difficulty as u32 + i,
-                amount_u64_to_string(reward_rate)
+                amount_u64_to_string(reward_rate),
+                ore_usd(prices, amount_u64_to_f64(reward_rate)),
+                chance(hashes, config.min_difficulty as u32 + i),
             );
         }
         println!("{}", s);
+
+        // Weigh the table by how likely this machine is to land on each row
+        if let Some(hashes) = hashes {
+            let ore_per_pass = amount_u64_to_f64(1) * expected_reward(hashes, &config);
+            let ore_per_day = ore_per_pass * (86_400 / PASS_SECONDS) as f64;
+            println!(
+                "\n{}: {:.6} ORE per pass, {:.4} ORE per day{}",
+                "Expected".bold(),
+                ore_per_pass,
+                ore_per_day,
+                ore_usd(prices, ore_per_day),
+            );
+        }
+    }
+}
+
+/// `  (12.5% per pass)` when a hashrate is known.
+fn chance(hashes: Option<f64>, difficulty: u32) -> String {
+    hashes
+        .map(|hashes| {
+            format!(
+                "  ({:.1}% per pass)",
+                p_at_least(hashes, difficulty) * 100.0
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// Expected reward in grains for the best of `hashes` attempts, following the reward table
+/// where each difficulty above the minimum doubles the base rate. The miner keeps hashing
+/// until it beats the minimum, so lower outcomes count as the first rewarded difficulty.
+pub fn expected_reward(hashes: f64, config: &Config) -> f64 {
+    let min_difficulty = config.min_difficulty as i32;
+    let mut expected = 0.0;
+    let mut p_current = 1.0;
+    for difficulty in (min_difficulty + 1)..64 {
+        let p_next = p_at_least(hashes, (difficulty + 1) as u32);
+        let reward = config.base_reward_rate as f64 * 2f64.powi(difficulty - min_difficulty);
+        expected += (p_current - p_next) * reward;
+        p_current = p_next;
+    }
+    expected
+}
+
+/// Reward in grains for a solution of `difficulty`, before any stake multiplier.
+pub fn reward_for_difficulty(config: &Config, difficulty: u32) -> u64 {
+    let min_difficulty = config.min_difficulty as u32;
+    if difficulty.lt(&min_difficulty) {
+        return 0;
     }
+    config
+        .base_reward_rate
+        .saturating_mul(2u64.saturating_pow(difficulty - min_difficulty))
+}
+
+/// Rewards multiplier for a proof staking `balance`. Stake adds to rewards in proportion to
+/// the top staker's balance, so it tops out at 2x once a proof matches the top stake.
+pub fn stake_multiplier(balance: u64, top_balance: u64) -> f64 {
+    if top_balance.eq(&0) {
+        return 1.0;
+    }
+    1.0 + balance.min(top_balance) as f64 / top_balance as f64
+}
+
+/// Chance that the best of `hashes` attempts reaches `difficulty`.
+pub fn p_at_least(hashes: f64, difficulty: u32) -> f64 {
+    1.0 - (hashes * (-(2f64.powi(-(difficulty as i32)))).ln_1p()).exp()
 }
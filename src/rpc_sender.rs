@@ -0,0 +1,192 @@
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use reqwest::{
+    header::{HeaderName, HeaderValue},
+    Proxy,
+};
+use serde_json::Value;
+use solana_client::{client_error::Result as ClientResult, rpc_request::RpcRequest};
+use solana_rpc_client::{
+    http_sender::HttpSender,
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::RpcClientConfig,
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use tokio::sync::Mutex;
+
+/// Proxy, extra headers and timeouts for the RPC and block engine requests, for miners
+/// behind a corporate proxy or on providers that authenticate with a header.
+#[derive(Clone)]
+pub struct HttpOptions {
+    proxy: Option<String>,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    timeout: Duration,
+    connect_timeout: Duration,
+}
+
+impl HttpOptions {
+    /// Checks the proxy URL up front, so a typo fails at startup rather than on the first request.
+    pub fn new(
+        proxy: Option<String>,
+        headers: Vec<(HeaderName, HeaderValue)>,
+        timeout: Duration,
+        connect_timeout: Duration,
+    ) -> Result<Self, String> {
+        if let Some(proxy) = &proxy {
+            Proxy::all(proxy.as_str())
+                .map_err(|err| format!("invalid proxy `{}`: {}", proxy, err))?;
+        }
+        Ok(Self {
+            proxy,
+            headers,
+            timeout,
+            connect_timeout,
+        })
+    }
+
+    /// The same options with more headers, for an endpoint that needs its own.
+    pub fn with_headers(&self, headers: Vec<(HeaderName, HeaderValue)>) -> Self {
+        let mut options = self.clone();
+        options.headers.extend(headers);
+        options
+    }
+
+    /// A client for the block engine, going through the proxy and sending the extra headers.
+    /// Requests that outlast the timeouts fail, so a dead endpoint can't stall a submission.
+    pub fn client(&self) -> reqwest::Client {
+        let mut builder = reqwest::Client::builder()
+            .default_headers(self.headers.iter().cloned().collect())
+            .timeout(self.timeout)
+            .connect_timeout(self.connect_timeout);
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(Proxy::all(proxy.as_str()).unwrap());
+        }
+        builder.build().expect("Failed to build HTTP client")
+    }
+
+    /// The same for the RPC, which is built on the reqwest version the Solana client uses.
+    fn rpc_client(&self) -> rpc_reqwest::Client {
+        let mut headers = rpc_reqwest::header::HeaderMap::new();
+        for (name, value) in &self.headers {
+            headers.append(
+                rpc_reqwest::header::HeaderName::from_bytes(name.as_ref()).unwrap(),
+                rpc_reqwest::header::HeaderValue::from_bytes(value.as_bytes()).unwrap(),
+            );
+        }
+        let mut builder = rpc_reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(self.timeout)
+            .connect_timeout(self.connect_timeout)
+            .pool_idle_timeout(self.timeout);
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(rpc_reqwest::Proxy::all(proxy.as_str()).unwrap());
+        }
+        builder.build().expect("Failed to build RPC client")
+    }
+}
+
+/// Token bucket shared by every RPC client the miner makes, so reconnecting doesn't reset it.
+/// Holds up to a second's worth of requests, so short bursts go out unthrottled.
+pub struct RateLimiter {
+    per_sec: f64,
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    pub fn new(per_sec: f64) -> Self {
+        Self {
+            per_sec,
+            bucket: Mutex::new((per_sec.max(1.0), Instant::now())),
+        }
+    }
+
+    /// Waits until a request may be sent. Waiters queue on the lock, so they go out in turn.
+    async fn acquire(&self) {
+        let mut bucket = self.bucket.lock().await;
+        let (tokens, refilled_at) = &mut *bucket;
+        let now = Instant::now();
+        *tokens = (*tokens + now.duration_since(*refilled_at).as_secs_f64() * self.per_sec)
+            .min(self.per_sec.max(1.0));
+        *refilled_at = now;
+        if *tokens < 1.0 {
+            tokio::time::sleep(Duration::from_secs_f64((1.0 - *tokens) / self.per_sec)).await;
+            *tokens = 1.0;
+            *refilled_at = Instant::now();
+        }
+        *tokens -= 1.0;
+    }
+}
+
+/// RPC requests made by method, so users on credit-metered providers can estimate costs.
+#[derive(Default)]
+pub struct RpcUsage {
+    calls: std::sync::Mutex<BTreeMap<String, u64>>,
+}
+
+impl RpcUsage {
+    fn record(&self, request: RpcRequest) {
+        *self
+            .calls
+            .lock()
+            .unwrap()
+            .entry(request.to_string())
+            .or_default() += 1;
+    }
+
+    /// Counts since the last call.
+    pub fn take(&self) -> BTreeMap<String, u64> {
+        std::mem::take(&mut *self.calls.lock().unwrap())
+    }
+}
+
+/// The standard HTTP sender, holding each request until the rate limiter lets it through
+/// and counting it.
+struct LimitedSender {
+    inner: HttpSender,
+    limiter: Option<Arc<RateLimiter>>,
+    usage: Arc<RpcUsage>,
+}
+
+#[async_trait]
+impl RpcSender for LimitedSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        if let Some(limiter) = &self.limiter {
+            limiter.acquire().await;
+        }
+        self.usage.record(request);
+        self.inner.send(request, params).await
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+/// An RPC client for `url` that counts its requests into `usage`, throttled by `limiter`
+/// when one is given.
+pub fn new_rpc_client(
+    url: String,
+    commitment: CommitmentConfig,
+    http: &HttpOptions,
+    limiter: Option<Arc<RateLimiter>>,
+    usage: Arc<RpcUsage>,
+) -> RpcClient {
+    RpcClient::new_sender(
+        LimitedSender {
+            inner: HttpSender::new_with_client(url, http.rpc_client()),
+            limiter,
+            usage,
+        },
+        RpcClientConfig::with_commitment(commitment),
+    )
+}
@@ -0,0 +1,173 @@
+use std::str::FromStr;
+
+use base64::Engine;
+use ore_api::instruction::OreInstruction;
+use solana_client::{
+    rpc_client::GetConfirmedSignaturesForAddress2Config, rpc_config::RpcTransactionConfig,
+};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, UiMessage,
+    UiTransactionEncoding, UiTransactionReturnData,
+};
+
+use crate::{utils::proof_pubkey, Miner};
+
+/// Most signatures `getSignaturesForAddress` returns per call.
+const SIGNATURES_PAGE_SIZE: usize = 1000;
+
+/// An ORE transaction touching a wallet's proof, as seen on chain.
+pub struct ScannedTx {
+    pub signature: Signature,
+    pub timestamp: Option<i64>,
+    pub reward: u64,
+    pub difficulty: Option<u64>,
+    pub claimed: u64,
+    pub fee_paid: u64,
+}
+
+/// The ORE program sets a mine event as return data: difficulty, reward and timing, each 8 bytes LE.
+fn decode_mine_event(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Option<(u64, u64)> {
+    let meta = tx.transaction.meta.as_ref()?;
+    let return_data: Option<UiTransactionReturnData> = meta.return_data.clone().into();
+    let return_data = return_data?;
+    if return_data.program_id.ne(&ore_api::ID.to_string()) {
+        return None;
+    }
+    let data = base64::prelude::BASE64_STANDARD
+        .decode(return_data.data.0)
+        .ok()?;
+    if data.len() < 16 {
+        return None;
+    }
+    let difficulty = u64::from_le_bytes(data[0..8].try_into().ok()?);
+    let reward = u64::from_le_bytes(data[8..16].try_into().ok()?);
+    Some((difficulty, reward))
+}
+
+/// ORE claimed by `wallet` in the transaction, read from its claim instructions: a
+/// discriminator byte, then the amount as 8 bytes LE.
+fn decode_claims(tx: &EncodedConfirmedTransactionWithStatusMeta, wallet: &Pubkey) -> u64 {
+    let EncodedTransaction::Json(ui_tx) = &tx.transaction.transaction else {
+        return 0;
+    };
+    let UiMessage::Raw(message) = &ui_tx.message else {
+        return 0;
+    };
+    let keys = &message.account_keys;
+    let (program_id, wallet) = (ore_api::ID.to_string(), wallet.to_string());
+    message
+        .instructions
+        .iter()
+        .filter(|ix| {
+            keys.get(ix.program_id_index as usize)
+                .eq(&Some(&program_id))
+        })
+        .filter(|ix| {
+            ix.accounts
+                .first()
+                .and_then(|index| keys.get(*index as usize))
+                .eq(&Some(&wallet))
+        })
+        .filter_map(|ix| bs58::decode(&ix.data).into_vec().ok())
+        .filter(|data| data.len() >= 9 && data[0].eq(&(OreInstruction::Claim as u8)))
+        .filter_map(|data| data[1..9].try_into().ok().map(u64::from_le_bytes))
+        .sum()
+}
+
+/// Account keys of a JSON-encoded transaction, fee payer first.
+pub fn account_keys(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Vec<String> {
+    match &tx.transaction.transaction {
+        EncodedTransaction::Json(ui_tx) => match &ui_tx.message {
+            UiMessage::Raw(message) => message.account_keys.clone(),
+            UiMessage::Parsed(message) => message
+                .account_keys
+                .iter()
+                .map(|key| key.pubkey.clone())
+                .collect(),
+        },
+        _ => vec![],
+    }
+}
+
+impl Miner {
+    /// Signatures of transactions touching the wallet's proof, newest first.
+    pub async fn proof_signatures(&self, wallet: Pubkey, limit: usize) -> Vec<Signature> {
+        let proof_address = proof_pubkey(wallet);
+        let mut signatures = vec![];
+        let mut before = None;
+        while signatures.len() < limit {
+            let page = self
+                .rpc_client()
+                .get_signatures_for_address_with_config(
+                    &proof_address,
+                    GetConfirmedSignaturesForAddress2Config {
+                        before,
+                        until: None,
+                        limit: Some(SIGNATURES_PAGE_SIZE.min(limit - signatures.len())),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                    },
+                )
+                .await
+                .unwrap_or_default();
+            let Some(last) = page.last() else {
+                break;
+            };
+            before = Signature::from_str(&last.signature).ok();
+            signatures.extend(
+                page.iter()
+                    .filter(|status| status.err.is_none())
+                    .filter_map(|status| Signature::from_str(&status.signature).ok()),
+            );
+            if before.is_none() {
+                break;
+            }
+        }
+        signatures
+    }
+
+    pub async fn get_json_transaction(
+        &self,
+        signature: &Signature,
+    ) -> Option<EncodedConfirmedTransactionWithStatusMeta> {
+        self.rpc_client()
+            .get_transaction_with_config(
+                signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Json),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await
+            .ok()
+    }
+
+    /// Walks the wallet's proof history, decoding mine rewards, claims and fees it paid.
+    pub async fn scan_wallet(&self, wallet: Pubkey, limit: usize) -> Vec<ScannedTx> {
+        let mut scanned = vec![];
+        for signature in self.proof_signatures(wallet, limit).await {
+            let Some(tx) = self.get_json_transaction(&signature).await else {
+                continue;
+            };
+            let Some(meta) = tx.transaction.meta.as_ref() else {
+                continue;
+            };
+            let fee_paid = match account_keys(&tx).first() {
+                Some(payer) if payer.eq(&wallet.to_string()) => meta.fee,
+                _ => 0,
+            };
+            let event = decode_mine_event(&tx);
+            scanned.push(ScannedTx {
+                signature,
+                timestamp: tx.block_time,
+                reward: event.map(|(_, reward)| reward).unwrap_or(0),
+                difficulty: event.map(|(difficulty, _)| difficulty),
+                claimed: decode_claims(&tx, &wallet),
+                fee_paid,
+            });
+        }
+        scanned
+    }
+}
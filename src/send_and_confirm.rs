@@ -1,38 +1,155 @@
 use std::time::Duration;
 
 use colored::*;
-use solana_client::{
-    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
-    rpc_config::RpcSendTransactionConfig,
-};
+use rand::Rng;
+use solana_client::client_error::{ClientErrorKind, Result as ClientResult};
 use solana_program::{
     instruction::Instruction,
     native_token::{lamports_to_sol, sol_to_lamports},
 };
-use solana_rpc_client::spinner;
 use solana_sdk::{
-    commitment_config::CommitmentLevel,
-    compute_budget::ComputeBudgetInstruction,
-    signature::{Signature, Signer},
-    transaction::Transaction,
+    address_lookup_table::AddressLookupTableAccount,
+    compute_budget::{self, ComputeBudgetInstruction},
+    hash::Hash,
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    system_instruction::{self, SystemInstruction},
+    system_program,
+    transaction::VersionedTransaction,
 };
-use solana_transaction_status::{TransactionConfirmationStatus, UiTransactionEncoding};
 
-use crate::Miner;
+use crate::{
+    cu_limits::ComputeBudget,
+    jito_tip::JITO_TIP_ACCOUNTS,
+    submit::{Resign, SubmitPolicy},
+    Miner,
+};
 
 const MIN_SOL_BALANCE: f64 = 0.005;
 
+/// Error for a signer too low on SOL to send, which exits with `ExitCode::InsufficientFunds`.
+pub const INSUFFICIENT_BALANCE: &str = "Insufficient balance";
+
 const RPC_RETRIES: usize = 0;
-const _SIMULATION_RETRIES: usize = 4;
 const GATEWAY_RETRIES: usize = 150;
 const CONFIRM_RETRIES: usize = 1;
 
 const CONFIRM_DELAY: u64 = 0;
 const GATEWAY_DELAY: u64 = 300;
 
-pub enum ComputeBudget {
-    Dynamic,
-    Fixed(u32),
+/// Retry tuning from the command line. Anything left unset keeps each send path's defaults.
+#[derive(Clone, Debug, Default)]
+pub struct RetryPolicy {
+    pub rpc_retries: Option<usize>,
+    pub gateway_retries: Option<usize>,
+    pub confirm_retries: Option<usize>,
+    pub initial_backoff: Option<Duration>,
+    pub max_backoff: Duration,
+    pub fee_escalation: u64,
+    pub max_escalation: f64,
+    pub spam_interval: Option<Duration>,
+}
+
+impl RetryPolicy {
+    pub fn rpc_retries(&self, default: usize) -> usize {
+        self.rpc_retries.unwrap_or(default)
+    }
+
+    pub fn gateway_retries(&self, default: usize) -> usize {
+        self.gateway_retries.unwrap_or(default)
+    }
+
+    pub fn confirm_retries(&self, default: usize) -> usize {
+        self.confirm_retries.unwrap_or(default)
+    }
+
+    /// Delay before retrying after `attempt` failures: the path's fixed delay, or
+    /// exponential backoff with jitter once an initial backoff is configured.
+    pub fn backoff(&self, attempt: usize, default: Duration) -> Duration {
+        let Some(initial) = self.initial_backoff else {
+            return default;
+        };
+        let backoff = initial
+            .saturating_mul(2u32.saturating_pow(attempt.min(16) as u32))
+            .min(self.max_backoff);
+        backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+
+    pub fn escalates(&self) -> bool {
+        self.fee_escalation > 0
+    }
+
+    /// Instructions for the `attempt`th retry, with the compute unit price and any Jito tip
+    /// raised by `--fee-escalation` percent per retry, up to `--max-escalation` times the
+    /// original.
+    pub fn escalate(&self, ixs: &[Instruction], attempt: usize) -> Vec<Instruction> {
+        if !self.escalates() {
+            return ixs.to_vec();
+        }
+        let factor = (1.0 + self.fee_escalation as f64 / 100.0)
+            .powi(attempt.min(i32::MAX as usize) as i32)
+            .min(self.max_escalation.max(1.0));
+        ixs.iter()
+            .map(|ix| escalate_instruction(ix, factor))
+            .collect()
+    }
+
+    /// A send path's retry and confirmation defaults, with any overrides applied.
+    pub fn submit_policy(
+        &self,
+        gateway_retries: usize,
+        confirm_retries: usize,
+        confirm_delay: Duration,
+        retry_delay: Duration,
+    ) -> SubmitPolicy {
+        SubmitPolicy {
+            gateway_retries: self.gateway_retries(gateway_retries),
+            confirm_retries: self.confirm_retries(confirm_retries),
+            confirm_delay,
+            retry_delay,
+            spam_interval: self.spam_interval,
+        }
+    }
+}
+
+/// `ix` with its price scaled by `factor`, if it sets a compute unit price or tips Jito.
+fn escalate_instruction(ix: &Instruction, factor: f64) -> Instruction {
+    let scale = |amount: u64| (amount as f64 * factor) as u64;
+
+    // SetComputeUnitPrice is tag 3 followed by the price as a little-endian u64
+    if ix.program_id.eq(&compute_budget::id()) && ix.data.len() == 9 && ix.data[0] == 3 {
+        let price = u64::from_le_bytes(ix.data[1..9].try_into().unwrap());
+        return ComputeBudgetInstruction::set_compute_unit_price(scale(price));
+    }
+    if ix.program_id.eq(&system_program::id())
+        && ix.accounts.len() == 2
+        && JITO_TIP_ACCOUNTS.contains(&ix.accounts[1].pubkey)
+    {
+        if let Ok(SystemInstruction::Transfer { lamports }) = bincode::deserialize(&ix.data) {
+            return system_instruction::transfer(
+                &ix.accounts[0].pubkey,
+                &ix.accounts[1].pubkey,
+                scale(lamports),
+            );
+        }
+    }
+    ix.clone()
+}
+
+/// Compiles a v0 transaction paid by `payer`, against the lookup tables if any, signed by
+/// `signers`. Single sends and bundles share this, so both get the same size headroom.
+pub fn compile_transaction(
+    payer: &Pubkey,
+    ixs: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    hash: Hash,
+    signers: &[&Keypair],
+) -> ClientResult<VersionedTransaction> {
+    let message = v0::Message::try_compile(payer, ixs, lookup_tables, hash)
+        .map_err(|err| ClientErrorKind::Custom(format!("Failed to compile transaction: {err}")))?;
+    VersionedTransaction::try_new(VersionedMessage::V0(message), signers)
+        .map_err(|err| ClientErrorKind::SigningError(err).into())
 }
 
 impl Miner {
@@ -42,192 +159,174 @@ impl Miner {
         compute_budget: ComputeBudget,
         skip_confirm: bool,
     ) -> ClientResult<Signature> {
-        let progress_bar = spinner::new_progress_bar();
         let signer = self.signer();
-        let client = self.rpc_client.clone();
+        let client = self.rpc_client();
 
         // Return error, if balance is zero
         if let Ok(balance) = client.get_balance(&signer.pubkey()).await {
             if balance <= sol_to_lamports(MIN_SOL_BALANCE) {
-                panic!(
-                    "{} Insufficient balance: {} SOL\nPlease top up with at least {} SOL",
-                    "ERROR".bold().red(),
+                let message = format!(
+                    "{}: {} SOL\nPlease top up with at least {} SOL",
+                    INSUFFICIENT_BALANCE,
                     lamports_to_sol(balance),
                     MIN_SOL_BALANCE
                 );
+                println!("{} {}", "ERROR".bold().red(), message);
+                return Err(ClientErrorKind::Custom(message).into());
             }
         }
 
-        // Set compute units
-        let mut final_ixs = vec![];
-        match compute_budget {
-            ComputeBudget::Dynamic => {
-                // TODO simulate
-                final_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(1_400_000))
-            }
-            ComputeBudget::Fixed(cus) => {
-                final_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(500_000))
-            }
-        }
-        final_ixs.push(ComputeBudgetInstruction::set_compute_unit_price(
-            self.priority_fee,
-        ));
-        final_ixs.extend_from_slice(ixs);
-
-        // Build tx
-        let send_cfg = RpcSendTransactionConfig {
-            skip_preflight: true,
-            preflight_commitment: Some(CommitmentLevel::Confirmed),
-            encoding: Some(UiTransactionEncoding::Base64),
-            max_retries: Some(RPC_RETRIES),
-            min_context_slot: None,
-        };
-        let mut tx = Transaction::new_with_payer(&final_ixs, Some(&signer.pubkey()));
+        // Set compute units to simulated usage, falling back to the budget if simulation fails
+        let final_ixs = self
+            .with_compute_budget(
+                ixs,
+                &signer.pubkey(),
+                compute_budget,
+                self.maintenance_fee(),
+            )
+            .await;
 
         // Sign tx
-        let (hash, _slot) = client
-            .get_latest_blockhash_with_commitment(self.rpc_client.commitment())
-            .await
-            .unwrap();
-        tx.sign(&[&signer], hash);
+        let hash = self.recent_blockhash().await?.hash;
+        let lookup_tables = self.lookup_tables().await;
+        let tx = compile_transaction(
+            &signer.pubkey(),
+            &final_ixs,
+            &lookup_tables,
+            hash,
+            &[&signer],
+        )?;
+
+        // Show what would happen instead of sending
+        if self.dry_run {
+            self.simulate_transaction(&tx, &[signer.pubkey()])
+                .await
+                .print("transaction");
+            return Ok(Signature::default());
+        }
 
         // Submit tx
-        let mut attempts = 0;
-        loop {
-            progress_bar.set_message(format!("Submitting transaction... (attempt {})", attempts));
-            match client.send_transaction_with_config(&tx, send_cfg).await {
-                Ok(sig) => {
-                    // Skip confirmation
-                    if skip_confirm {
-                        progress_bar.finish_with_message(format!("Sent: {}", sig));
-                        return Ok(sig);
-                    }
-
-                    // Confirm the tx landed
-                    for _ in 0..CONFIRM_RETRIES {
-                        std::thread::sleep(Duration::from_millis(CONFIRM_DELAY));
-                        match client.get_signature_statuses(&[sig]).await {
-                            Ok(signature_statuses) => {
-                                for status in signature_statuses.value {
-                                    if let Some(status) = status {
-                                        if let Some(err) = status.err {
-                                            progress_bar.finish_with_message(format!(
-                                                "{}: {}",
-                                                "ERROR".bold().red(),
-                                                err
-                                            ));
-                                            return Err(ClientError {
-                                                request: None,
-                                                kind: ClientErrorKind::Custom(err.to_string()),
-                                            });
-                                        }
-                                        if let Some(confirmation) = status.confirmation_status {
-                                            match confirmation {
-                                                TransactionConfirmationStatus::Processed => {}
-                                                TransactionConfirmationStatus::Confirmed
-                                                | TransactionConfirmationStatus::Finalized => {
-                                                    progress_bar.finish_with_message(format!(
-                                                        "{} {}",
-                                                        "OK".bold().green(),
-                                                        sig
-                                                    ));
-                                                    return Ok(sig);
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-
-                            // Handle confirmation errors
-                            Err(err) => {
-                                progress_bar.set_message(format!(
-                                    "{}: {}",
-                                    "ERROR".bold().red(),
-                                    err.kind().to_string()
-                                ));
-                            }
-                        }
-                    }
-                }
-
-                // Handle submit errors
-                Err(err) => {
-                    progress_bar.set_message(format!(
-                        "{}: {}",
-                        "ERROR".bold().red(),
-                        err.kind().to_string()
-                    ));
-                }
-            }
+        let transport = self.rpc_transport(RPC_RETRIES);
+        let policy = self.retry.submit_policy(
+            GATEWAY_RETRIES,
+            CONFIRM_RETRIES,
+            Duration::from_millis(CONFIRM_DELAY),
+            Duration::from_millis(GATEWAY_DELAY),
+        );
+        let resign = |hash, attempt| {
+            compile_transaction(
+                &signer.pubkey(),
+                &self.retry.escalate(&final_ixs, attempt),
+                &lookup_tables,
+                hash,
+                &[&signer],
+            )
+            .map(|tx| vec![tx])
+        };
+        self.submit(
+            &transport,
+            vec![tx],
+            self.retry.escalates().then_some(&resign as &Resign),
+            policy,
+            skip_confirm,
+            None,
+        )
+        .await
+    }
+}
 
-            // Retry
-            std::thread::sleep(Duration::from_millis(GATEWAY_DELAY));
-            attempts += 1;
-            if attempts > GATEWAY_RETRIES {
-                progress_bar.finish_with_message(format!("{}: Max retries", "ERROR".bold().red()));
-                return Err(ClientError {
-                    request: None,
-                    kind: ClientErrorKind::Custom("Max retries".into()),
-                });
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_backoff: Duration::from_secs(2),
+            max_escalation: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn keeps_the_default_delay_without_backoff() {
+        let default = Duration::from_millis(300);
+        for attempt in 0..5 {
+            assert_eq!(policy().backoff(attempt, default), default);
+        }
+    }
+
+    #[test]
+    fn backs_off_exponentially_with_jitter_up_to_the_max() {
+        let policy = RetryPolicy {
+            initial_backoff: Some(Duration::from_millis(100)),
+            ..policy()
+        };
+        for (attempt, full) in [
+            (0, 100),
+            (1, 200),
+            (2, 400),
+            (3, 800),
+            (5, 2000),
+            (40, 2000),
+        ] {
+            let full = Duration::from_millis(full);
+            for _ in 0..20 {
+                let backoff = policy.backoff(attempt, Duration::ZERO);
+                assert!(backoff <= full, "attempt {}: {:?}", attempt, backoff);
+                assert!(backoff >= full / 2, "attempt {}: {:?}", attempt, backoff);
             }
         }
     }
 
-    // TODO
-    fn _simulate(&self) {
-
-        // Simulate tx
-        // let mut sim_attempts = 0;
-        // 'simulate: loop {
-        //     let sim_res = client
-        //         .simulate_transaction_with_config(
-        //             &tx,
-        //             RpcSimulateTransactionConfig {
-        //                 sig_verify: false,
-        //                 replace_recent_blockhash: true,
-        //                 commitment: Some(self.rpc_client.commitment()),
-        //                 encoding: Some(UiTransactionEncoding::Base64),
-        //                 accounts: None,
-        //                 min_context_slot: Some(slot),
-        //                 inner_instructions: false,
-        //             },
-        //         )
-        //         .await;
-        //     match sim_res {
-        //         Ok(sim_res) => {
-        //             if let Some(err) = sim_res.value.err {
-        //                 println!("Simulaton error: {:?}", err);
-        //                 sim_attempts += 1;
-        //             } else if let Some(units_consumed) = sim_res.value.units_consumed {
-        //                 if dynamic_cus {
-        //                     println!("Dynamic CUs: {:?}", units_consumed);
-        //                     let cu_budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(
-        //                         units_consumed as u32 + 1000,
-        //                     );
-        //                     let cu_price_ix =
-        //                         ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee);
-        //                     let mut final_ixs = vec![];
-        //                     final_ixs.extend_from_slice(&[cu_budget_ix, cu_price_ix]);
-        //                     final_ixs.extend_from_slice(ixs);
-        //                     tx = Transaction::new_with_payer(&final_ixs, Some(&signer.pubkey()));
-        //                 }
-        //                 break 'simulate;
-        //             }
-        //         }
-        //         Err(err) => {
-        //             println!("Simulaton error: {:?}", err);
-        //             sim_attempts += 1;
-        //         }
-        //     }
-
-        //     // Abort if sim fails
-        //     if sim_attempts.gt(&SIMULATION_RETRIES) {
-        //         return Err(ClientError {
-        //             request: None,
-        //             kind: ClientErrorKind::Custom("Simulation failed".into()),
-        //         });
-        //     }
-        // }
+    fn price(ix: &Instruction) -> u64 {
+        u64::from_le_bytes(ix.data[1..9].try_into().unwrap())
+    }
+
+    fn tip(ix: &Instruction) -> u64 {
+        match bincode::deserialize(&ix.data).unwrap() {
+            SystemInstruction::Transfer { lamports } => lamports,
+            _ => panic!("not a transfer"),
+        }
+    }
+
+    #[test]
+    fn escalates_the_price_and_tip_per_attempt() {
+        let policy = RetryPolicy {
+            fee_escalation: 50,
+            max_escalation: 10.0,
+            ..policy()
+        };
+        let payer = Pubkey::new_unique();
+        let other = system_instruction::transfer(&payer, &Pubkey::new_unique(), 1_000);
+        let ixs = vec![
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            system_instruction::transfer(&payer, &JITO_TIP_ACCOUNTS[0], 10_000),
+            other.clone(),
+        ];
+        let escalated = policy.escalate(&ixs, 2);
+        assert_eq!(price(&escalated[0]), 2_250);
+        assert_eq!(tip(&escalated[1]), 22_500);
+        assert_eq!(escalated[2], other);
+        assert_eq!(policy.escalate(&ixs, 0), ixs);
+    }
+
+    #[test]
+    fn caps_escalation() {
+        let policy = RetryPolicy {
+            fee_escalation: 100,
+            max_escalation: 3.0,
+            ..policy()
+        };
+        let ixs = vec![ComputeBudgetInstruction::set_compute_unit_price(1_000)];
+        assert_eq!(price(&policy.escalate(&ixs, 1)[0]), 2_000);
+        assert_eq!(price(&policy.escalate(&ixs, 10)[0]), 3_000);
+        assert_eq!(price(&policy.escalate(&ixs, usize::MAX)[0]), 3_000);
+    }
+
+    #[test]
+    fn leaves_instructions_alone_without_escalation() {
+        let ixs = vec![ComputeBudgetInstruction::set_compute_unit_price(1_000)];
+        assert!(!policy().escalates());
+        assert_eq!(policy().escalate(&ixs, 5), ixs);
     }
 }
@@ -1,472 +1,344 @@
-use crate::{jito_tip::JITO_COUNT, Miner};
+use crate::{
+    cu_limits::{has_compute_budget, with_margin, ComputeBudget},
+    jito_tip::JITO_COUNT,
+    send_and_confirm::compile_transaction,
+    submit::{JitoTransport, Resign},
+    telemetry::TxSample,
+    Miner,
+};
 use colored::Colorize;
+use futures::future::join_all;
 use rand::Rng;
-use serde_json::json;
 use solana_client::{
-    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
-    nonblocking::rpc_client::RpcClient,
-    rpc_client::SerializableTransaction,
-    rpc_config::RpcSendTransactionConfig,
-    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
-    rpc_response::RpcSimulateTransactionResult,
+    client_error::{ClientErrorKind, Result as ClientResult},
+    rpc_config::RpcTransactionConfig,
 };
 use solana_program::instruction::Instruction;
-use solana_rpc_client::spinner;
 use solana_sdk::{
-    commitment_config::{CommitmentConfig, CommitmentLevel},
+    address_lookup_table::AddressLookupTableAccount,
+    commitment_config::CommitmentConfig,
     compute_budget::ComputeBudgetInstruction,
     hash::Hash,
-    message::{v0, Message, VersionedMessage},
+    message::{v0, VersionedMessage},
+    packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
     signature::{Keypair, Signature, Signer},
     system_instruction,
-    transaction::{Transaction, VersionedTransaction},
-};
-use solana_transaction_status::{TransactionConfirmationStatus, UiTransactionEncoding};
-use std::{
-    io::{stdout, Write},
-    time::Duration,
+    transaction::VersionedTransaction,
 };
+use solana_transaction_status::UiTransactionEncoding;
+use std::time::{Duration, Instant};
 
 const RPC_RETRIES: usize = 1;
 const GATEWAY_RETRIES: usize = 4;
 const CONFIRM_RETRIES: usize = 4;
+const CONFIRM_DELAY: u64 = 2000;
+const GATEWAY_DELAY: u64 = 2000;
+const BUNDLE_GATEWAY_DELAY: u64 = 300;
+const BUNDLE_CU_LIMIT: u32 = 500_000;
+const MAX_BUNDLE_TXS: usize = 5;
 
-use base64::Engine;
-use bincode::serialize;
-use serde::{Deserialize, Serialize};
-
-#[derive(Serialize, Deserialize, Debug)]
-struct RequestPayload {
-    method: String,
-    params: serde_json::Value,
-    id: u64,
-    jsonrpc: String,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct ResponseData {
-    jsonrpc: String,
-    result: String,
-    id: u64,
-}
-
-fn serialize_and_encode_multi<T>(
-    inputs: &[T],
-    encoding: UiTransactionEncoding,
-) -> ClientResult<Vec<String>>
-where
-    T: serde::ser::Serialize,
-{
-    let mut encoded_inputs = Vec::new();
+impl Miner {
+    pub async fn send_and_confirm_with_key(
+        &self,
+        ixs: &[Instruction],
+        skip_confirm: bool,
+        signer: &Keypair,
+    ) -> ClientResult<Signature> {
+        self.send_and_confirm_with_keys(ixs, skip_confirm, &[signer])
+            .await
+    }
 
-    for input in inputs {
-        let serialized = serialize(input)
-            .map_err(|e| ClientErrorKind::Custom(format!("Serialization failed: {e}")))?;
-        let encoded = match encoding {
-            UiTransactionEncoding::Base58 => bs58::encode(serialized).into_string(),
-            UiTransactionEncoding::Base64 => base64::prelude::BASE64_STANDARD.encode(serialized),
-            _ => {
-                return Err(ClientErrorKind::Custom(format!(
-                    "unsupported encoding: {encoding}. Supported encodings: base58, base64"
-                ))
-                .into())
+    /// Like `send_and_confirm_with_key`, for transactions needing several signers.
+    /// The first signer pays the fees.
+    pub async fn send_and_confirm_with_keys(
+        &self,
+        ixs: &[Instruction],
+        skip_confirm: bool,
+        signers: &[&Keypair],
+    ) -> ClientResult<Signature> {
+        println!("for {}", signers[0].pubkey());
+
+        // Budget compute like every other write, unless the instructions bring their own
+        let ixs = match has_compute_budget(ixs) {
+            true => ixs.to_vec(),
+            false => {
+                self.with_compute_budget(
+                    ixs,
+                    &signers[0].pubkey(),
+                    ComputeBudget::Dynamic,
+                    self.maintenance_fee(),
+                )
+                .await
             }
         };
-        encoded_inputs.push(encoded);
-    }
 
-    Ok(encoded_inputs)
-}
-fn serialize_and_encode<T>(input: &T, encoding: UiTransactionEncoding) -> ClientResult<String>
-where
-    T: serde::ser::Serialize,
-{
-    let serialized = serialize(input)
-        .map_err(|e| ClientErrorKind::Custom(format!("Serialization failed: {e}")))?;
-    let encoded = match encoding {
-        UiTransactionEncoding::Base58 => bs58::encode(serialized).into_string(),
-        UiTransactionEncoding::Base64 => base64::prelude::BASE64_STANDARD.encode(serialized),
-        _ => {
-            return Err(ClientErrorKind::Custom(format!(
-                "unsupported encoding: {encoding}. Supported encodings: base58, base64"
-            ))
-            .into())
-        }
-    };
-    Ok(encoded)
-}
-
-async fn send_transaction_with_config_bundle(
-    transactions: Vec<impl SerializableTransaction>,
-    jito_url: String
-) -> ClientResult<Signature> {
-    let encoding = UiTransactionEncoding::Base58;
-    let serialized_encoded = serialize_and_encode_multi(transactions.as_slice(), encoding)?;
-
-    let payload = RequestPayload {
-        method: "sendBundle".to_string(),
-        params: json!([serialized_encoded]),
-        id: 1,
-        jsonrpc: "2.0".to_string(),
-    };
-
-    let client = reqwest::Client::new();
-
-    let signature_base58_str = match client
-        .post(jito_url)
-        .json(&payload)
-        .send()
-        .await
-        .unwrap()
-        .error_for_status()
-        .unwrap()
-        .json::<ResponseData>()
-        .await
-    {
-        Ok(response) => response.result,
-        Err(_) => {
-            return Err(ClientError {
-                request: None,
-                kind: ClientErrorKind::Custom("Failed to send jito transaction".into()),
-            })
+        // Build tx
+        let lookup_tables = self.lookup_tables().await;
+        let hash = self.recent_blockhash().await?.hash;
+        let tx = compile_transaction(&signers[0].pubkey(), &ixs, &lookup_tables, hash, signers)?;
+
+        // Show what would happen instead of sending
+        if self.dry_run {
+            let keys: Vec<_> = signers.iter().map(|signer| signer.pubkey()).collect();
+            self.simulate_transaction(&tx, &keys)
+                .await
+                .print("transaction");
+            return Ok(Signature::default());
         }
-    };
-
-    println!("Jito Bundle: {}", signature_base58_str);
-    let first_txn = transactions.first().unwrap();
-    Ok(*first_txn.get_signature())
-}
 
-async fn send_transaction_with_config(
-    client: &RpcClient,
-    transaction: &impl SerializableTransaction,
-    config: RpcSendTransactionConfig,
-) -> ClientResult<Signature> {
-    let encoding = if let Some(encoding) = config.encoding {
-        encoding
-    } else {
-        UiTransactionEncoding::Base64
-    };
-    let preflight_commitment = CommitmentConfig {
-        commitment: config.preflight_commitment.unwrap_or_default(),
-    };
-    // let preflight_commitment = client.maybe_map_commitment(preflight_commitment).await?;
-    let config = RpcSendTransactionConfig {
-        encoding: Some(encoding),
-        preflight_commitment: Some(preflight_commitment.commitment),
-        ..config
-    };
-    let serialized_encoded = serialize_and_encode(transaction, encoding)?;
-    let signature_base58_str: String = match client
-        .send(
-            RpcRequest::SendTransaction,
-            json!([serialized_encoded, config]),
+        // Submit tx, re-signing against a fresh blockhash between attempts
+        let transport = self.rpc_transport(RPC_RETRIES);
+        let resign = |hash, attempt| {
+            compile_transaction(
+                &signers[0].pubkey(),
+                &self.retry.escalate(&ixs, attempt),
+                &lookup_tables,
+                hash,
+                signers,
+            )
+            .map(|tx| vec![tx])
+        };
+        let policy = self.retry.submit_policy(
+            GATEWAY_RETRIES,
+            CONFIRM_RETRIES,
+            Duration::from_millis(CONFIRM_DELAY),
+            Duration::from_millis(GATEWAY_DELAY),
+        );
+        self.submit(
+            &transport,
+            vec![tx],
+            Some(&resign),
+            policy,
+            skip_confirm,
+            None,
         )
         .await
-    {
-        Ok(signature_base58_str) => signature_base58_str,
-        Err(err) => {
-            if let ClientErrorKind::RpcError(RpcError::RpcResponseError {
-                code,
-                message,
-                data,
-            }) = &err.kind
-            {
-                println!("{} {}", code, message);
-                if let RpcResponseErrorData::SendTransactionPreflightFailure(
-                    RpcSimulateTransactionResult {
-                        logs: Some(logs), ..
-                    },
-                ) = data
-                {
-                    for (i, log) in logs.iter().enumerate() {
-                        println!("{:>3}: {}", i + 1, log);
-                    }
-                    println!("");
-                }
-            }
-            return Err(err);
-        }
-    };
-
-    let signature = signature_base58_str
-        .parse::<Signature>()
-        .map_err(|err| Into::<ClientError>::into(RpcError::ParseError(err.to_string())))?;
-    if signature != *transaction.get_signature() {
-        Err(RpcError::RpcRequestError(format!(
-            "RPC node returned mismatched signature {:?}, expected {:?}",
-            signature,
-            transaction.get_signature()
-        ))
-        .into())
-    } else {
-        Ok(*transaction.get_signature())
     }
-}
 
-impl Miner {
-    pub async fn send_and_confirm_with_key(
+    /// Sends instruction groups as Jito bundles paid for by the fee payer. A group's
+    /// instructions always share a transaction. When the groups need more transactions than
+    /// one bundle holds, they go out as several bundles at once. Returns the signature of the
+    /// first bundle that landed. `signers` sign the transactions they appear in.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_and_confirm_bundle(
         &self,
-        ixs: &[Instruction],
+        groups: &[Vec<Instruction>],
+        signers: &[&Keypair],
+        fee_payer: &Keypair,
         skip_confirm: bool,
-        signer: &Keypair,
+        jito_tip_amount: u64,
+        jito_url: String,
+        jito_uuid: Option<&str>,
+        max_ixs_per_tx: Option<usize>,
     ) -> ClientResult<Signature> {
-        let mut stdout = stdout();
-        println!("for {}", signer.pubkey());
-        let client = self.rpc_client.clone();
-
-        // Build tx
-        let (mut hash, mut slot, mut send_cfg, mut tx) =
-            generate_transaction(&client, ixs, signer).await;
-
-        // Submit tx
-        let mut sigs = vec![];
-        let mut attempts = 0;
-        let mut sleep_duration = Duration::from_millis(10000);
-        loop {
-            println!("Attempt: {:?}", attempts);
-            match send_transaction_with_config(&client, &tx, send_cfg).await {
+        let lookup_tables = self.lookup_tables().await;
+
+        // Pack as many instruction groups into each transaction as fit in a packet
+        let batches =
+            pack_instructions(&fee_payer.pubkey(), groups, &lookup_tables, max_ixs_per_tx);
+        if batches.len() > MAX_BUNDLE_TXS {
+            println!(
+                "{} {} transactions exceed the Jito bundle limit of {}, sending {} bundles",
+                "NOTICE".bold().cyan(),
+                batches.len(),
+                MAX_BUNDLE_TXS,
+                batches.len().div_ceil(MAX_BUNDLE_TXS)
+            );
+        }
+        let bundles = batches.chunks(MAX_BUNDLE_TXS).map(|batches| {
+            self.send_bundle(
+                batches.to_vec(),
+                skip_confirm,
+                jito_tip_amount,
+                &jito_url,
+                jito_uuid,
+                signers,
+                fee_payer,
+                &lookup_tables,
+            )
+        });
+        let mut landed = None;
+        let mut last_err = None;
+        for res in join_all(bundles).await {
+            match res {
                 Ok(sig) => {
-                    sigs.push(sig);
-                    println!("{:?}", sig);
-
-                    // Confirm tx
-                    if skip_confirm {
-                        return Ok(sig);
-                    }
-                    for _ in 0..CONFIRM_RETRIES {
-                        std::thread::sleep(sleep_duration);
-                        match client.get_signature_statuses(&sigs).await {
-                            Ok(signature_statuses) => {
-                                println!("Confirms: {:?}", signature_statuses.value);
-                                for signature_status in signature_statuses.value {
-                                    if let Some(signature_status) = signature_status.as_ref() {
-                                        if signature_status.confirmation_status.is_some() {
-                                            let current_commitment = signature_status
-                                                .confirmation_status
-                                                .as_ref()
-                                                .unwrap();
-                                            match current_commitment {
-                                                TransactionConfirmationStatus::Processed => {
-                                                    sleep_duration = Duration::from_millis(1000)
-                                                }
-                                                TransactionConfirmationStatus::Confirmed
-                                                | TransactionConfirmationStatus::Finalized => {
-                                                    println!("Transaction landed!");
-                                                    return Ok(sig);
-                                                }
-                                            }
-                                        } else {
-                                            println!("No status");
-                                        }
-                                    }
-                                }
-                            }
-
-                            // Handle confirmation errors
-                            Err(err) => {
-                                println!("Error: {:?}", err);
-                            }
-                        }
-                    }
-                    println!("Transaction did not land");
+                    landed.get_or_insert(sig);
                 }
-
-                // Handle submit errors
                 Err(err) => {
-                    println!("Error {:?}", err);
+                    println!("{} Bundle failed: {}", "ERROR".bold().red(), err);
+                    last_err = Some(err);
                 }
             }
-            stdout.flush().ok();
-
-            // Retry
-            std::thread::sleep(Duration::from_millis(2000));
-            (hash, slot) = client
-                .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
-                .await
-                .unwrap();
-            send_cfg = RpcSendTransactionConfig {
-                skip_preflight: true,
-                preflight_commitment: Some(CommitmentLevel::Confirmed),
-                encoding: Some(UiTransactionEncoding::Base64),
-                max_retries: Some(RPC_RETRIES),
-                min_context_slot: Some(slot),
-            };
-            tx.sign(&[&signer], hash);
-            attempts += 1;
-            if attempts > GATEWAY_RETRIES {
-                return Err(ClientError {
-                    request: None,
-                    kind: ClientErrorKind::Custom("Max retries".into()),
-                });
-            }
+        }
+        match (landed, last_err) {
+            (Some(sig), _) => Ok(sig),
+            (None, Some(err)) => Err(err),
+            (None, None) => Err(ClientErrorKind::Custom("No instructions to send".into()).into()),
         }
     }
 
-    pub async fn send_and_confirm_bundle(
+    /// How many bundles `send_and_confirm_bundle` would send `groups` in, each paying a tip.
+    pub async fn bundle_count(
         &self,
-        ixs: &[Instruction],
+        payer: &Pubkey,
+        groups: &[Vec<Instruction>],
+        max_ixs_per_tx: Option<usize>,
+    ) -> usize {
+        let lookup_tables = self.lookup_tables().await;
+        pack_instructions(payer, groups, &lookup_tables, max_ixs_per_tx)
+            .len()
+            .div_ceil(MAX_BUNDLE_TXS)
+    }
+
+    /// Sends one bundle of at most `MAX_BUNDLE_TXS` transactions.
+    #[allow(clippy::too_many_arguments)]
+    async fn send_bundle(
+        &self,
+        batches: Vec<Vec<Instruction>>,
         skip_confirm: bool,
         jito_tip_amount: u64,
-        jito_url: String
+        jito_url: &str,
+        jito_uuid: Option<&str>,
+        signers: &[&Keypair],
+        fee_payer: &Keypair,
+        lookup_tables: &[AddressLookupTableAccount],
     ) -> ClientResult<Signature> {
-        let progress_bar = spinner::new_progress_bar();
-        let signers = self.multi_signers();
-        let fee_payer = self.fee_payer();
-        let client = self.rpc_client.clone();
+        let build_start = Instant::now();
         // Build tx
-        let (hash, _slot) = client
-            .get_latest_blockhash_with_commitment(CommitmentConfig::finalized())
-            .await
-            .unwrap();
-        let final_ixs = ixs.to_vec();
+        let hash = self.recent_blockhash().await?.hash;
         let mut txs: Vec<VersionedTransaction> = vec![];
-        let num_ixs_per_tx: usize = 2; // Number of instructions per transaction
-
-        let mut current_idx = 0;
-
-        while current_idx < final_ixs.len() {
-            let mut current_ixs: Vec<Instruction> = vec![];
-
-            for _ in 0..num_ixs_per_tx {
-                if current_idx >= final_ixs.len() {
-                    break;
-                }
-                current_ixs.push(final_ixs[current_idx].clone());
-                current_idx += 1;
-            }
-
-            let cu_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(500_000);
-            current_ixs.push(cu_limit_ix);
-
-            // Add Jito instruction to the last transaction
-            let mut fee_payer_signers: Vec<&Keypair> = vec![&fee_payer];
-            let mut add_jito_tip = false;
-
-            for ixn in current_ixs.iter() {
-                if ixn
-                    .accounts
-                    .iter()
-                    .any(|account| account.pubkey == fee_payer.pubkey())
-                {
-                    add_jito_tip = true;
-                    break;
-                }
-            }
-
-            if add_jito_tip {
-                let jito_key = self.find_jito_tip_account().await;
-                let jito_tip_ix =
-                    system_instruction::transfer(&fee_payer.pubkey(), &jito_key, jito_tip_amount);
-                current_ixs.push(jito_tip_ix);
-                fee_payer_signers = vec![];
-            }
-
-            let message_v0 =
-                v0::Message::try_compile(&fee_payer.pubkey(), current_ixs.as_slice(), &[], hash)
-                    .unwrap();
-            let message_v0 = VersionedMessage::V0(message_v0);
+        let mut cu_limits: Vec<u32> = vec![];
+        let mut plans: Vec<(Vec<Instruction>, Vec<&Keypair>)> = vec![];
+
+        // Tip from the last transaction, so the tip only pays out if the whole bundle lands
+        let mut batches = batches;
+        if let Some(last) = batches.last_mut() {
+            let jito_key = self.find_jito_tip_account().await;
+            last.push(system_instruction::transfer(
+                &fee_payer.pubkey(),
+                &jito_key,
+                jito_tip_amount,
+            ));
+        }
 
-            fee_payer_signers.extend(
-                signers
-                    .iter()
-                    .filter(|keypair| {
-                        current_ixs.iter().any(|ix| {
-                            ix.accounts
-                                .iter()
-                                .any(|acc| acc.is_signer && acc.pubkey == keypair.pubkey())
-                        })
+        // Request what each transaction actually uses rather than a flat limit, simulating
+        // them all at once so sizing doesn't eat into the time left to land
+        let payer = fee_payer.pubkey();
+        let simulated = join_all(
+            batches
+                .iter()
+                .map(|ixs| self.simulate_compute_units(ixs, &payer)),
+        )
+        .await;
+        for (mut current_ixs, units) in batches.into_iter().zip(simulated) {
+            let cu_limit = units.map(with_margin).unwrap_or(BUNDLE_CU_LIMIT);
+            current_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(cu_limit));
+            cu_limits.push(cu_limit);
+
+            // The fee payer signs every transaction, and each wallet the ones it appears in
+            let mut tx_signers: Vec<&Keypair> = vec![fee_payer];
+            tx_signers.extend(signers.iter().filter(|keypair| {
+                keypair.pubkey().ne(&fee_payer.pubkey())
+                    && current_ixs.iter().any(|ix| {
+                        ix.accounts
+                            .iter()
+                            .any(|acc| acc.is_signer && acc.pubkey == keypair.pubkey())
                     })
-                    .map(|keypair| keypair),
-            );
-
-            let tx =
-                VersionedTransaction::try_new(message_v0, fee_payer_signers.as_slice()).unwrap();
+            }));
+
+            let tx = compile_transaction(
+                &fee_payer.pubkey(),
+                &current_ixs,
+                lookup_tables,
+                hash,
+                &tx_signers,
+            )?;
             txs.push(tx);
-            current_ixs.clear();
+            plans.push((current_ixs, tx_signers));
         }
 
-        // Submit tx
-        let mut sigs = vec![];
-        let mut sleep_duration = Duration::from_millis(2000);
-        let mut attempts = 0;
-        loop {
-            let jito_url_clone = jito_url.clone();
-            match send_transaction_with_config_bundle(txs.clone(), jito_url_clone).await {
-                Ok(sig) => {
-                    sigs.push(sig);
-                    println!("{:?}", sig);
-
-                    // Confirm tx
-                    if skip_confirm {
-                        return Ok(sig);
-                    }
-                    for _ in 0..CONFIRM_RETRIES {
-                        std::thread::sleep(sleep_duration);
-                        match client.get_signature_statuses(&sigs).await {
-                            Ok(signature_statuses) => {
-                                progress_bar.set_message(format!(
-                                    "Confirms: {:?}",
-                                    signature_statuses.value
-                                ));
-                                for signature_status in signature_statuses.value {
-                                    if let Some(signature_status) = signature_status.as_ref() {
-                                        if signature_status.confirmation_status.is_some() {
-                                            let current_commitment = signature_status
-                                                .confirmation_status
-                                                .as_ref()
-                                                .unwrap();
-                                            match current_commitment {
-                                                TransactionConfirmationStatus::Processed => {
-                                                    sleep_duration = Duration::from_millis(1000)
-                                                }
-                                                TransactionConfirmationStatus::Confirmed
-                                                | TransactionConfirmationStatus::Finalized => {
-                                                    progress_bar.finish_with_message(format!(
-                                                        "Transaction landed"
-                                                    ));
-                                                    return Ok(sig);
-                                                }
-                                            }
-                                        } else {
-                                            println!("No status");
-                                        }
-                                    }
-                                }
-                            }
+        // Show what would happen instead of sending
+        if self.dry_run {
+            for (i, simulation) in self.simulate_bundle(&txs).await.iter().enumerate() {
+                simulation.print(&format!("bundle transaction {}", i + 1));
+            }
+            return Ok(Signature::default());
+        }
 
-                            // Handle confirmation errors
-                            Err(err) => {
-                                progress_bar.set_message(format!(
-                                    "{}: {}",
-                                    "ERROR".bold().red(),
-                                    err.kind().to_string()
-                                ));
-                            }
-                        }
-                    }
-                }
+        // Submit tx
+        let transport = JitoTransport {
+            http: self.http.client(),
+            url: jito_url.to_string(),
+            uuid: jito_uuid,
+        };
+        let policy = self.retry.submit_policy(
+            GATEWAY_RETRIES,
+            CONFIRM_RETRIES,
+            Duration::from_millis(CONFIRM_DELAY),
+            Duration::from_millis(BUNDLE_GATEWAY_DELAY),
+        );
+        let resign = |hash, attempt| {
+            plans
+                .iter()
+                .map(|(ixs, signers)| {
+                    compile_transaction(
+                        &fee_payer.pubkey(),
+                        &self.retry.escalate(ixs, attempt),
+                        lookup_tables,
+                        hash,
+                        signers,
+                    )
+                })
+                .collect()
+        };
+        let sig = self
+            .submit(
+                &transport,
+                txs.clone(),
+                self.retry.escalates().then_some(&resign as &Resign),
+                policy,
+                skip_confirm,
+                Some(build_start),
+            )
+            .await?;
+        if !skip_confirm {
+            self.record_bundle_telemetry(&txs, &cu_limits, jito_tip_amount)
+                .await;
+        }
+        Ok(sig)
+    }
 
-                // Handle submit errors
-                Err(err) => {
-                    progress_bar.set_message(format!(
-                        "{}: {}",
-                        "ERROR".bold().red(),
-                        err.kind().to_string()
-                    ));
-                }
-            }
-            // Retry
-            std::thread::sleep(Duration::from_millis(300));
-            attempts += 1;
-            if attempts > GATEWAY_RETRIES {
-                progress_bar.finish_with_message(format!("{}: Max retries", "ERROR".bold().red()));
-                return Err(ClientError {
-                    request: None,
-                    kind: ClientErrorKind::Custom("Max retries".into()),
-                });
-            }
+    async fn record_bundle_telemetry(
+        &self,
+        txs: &[VersionedTransaction],
+        cu_limits: &[u32],
+        tip: u64,
+    ) {
+        if self.control.telemetry().is_none() {
+            return;
+        }
+        let mut samples = vec![];
+        for (tx, cu_limit) in txs.iter().zip(cu_limits.iter()) {
+            let mut sample = TxSample::new(tx, *cu_limit);
+            sample.cu_used = self
+                .rpc_client()
+                .get_transaction_with_config(
+                    &tx.signatures[0],
+                    RpcTransactionConfig {
+                        encoding: Some(UiTransactionEncoding::Base64),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                        max_supported_transaction_version: Some(0),
+                    },
+                )
+                .await
+                .ok()
+                .and_then(|tx| tx.transaction.meta)
+                .and_then(|meta| meta.compute_units_consumed.into());
+            samples.push(sample);
+        }
+        if let Some(telemetry) = self.control.telemetry().as_mut() {
+            telemetry.record_bundle(samples, tip);
         }
     }
 
@@ -477,27 +349,120 @@ impl Miner {
     }
 }
 
-async fn generate_transaction(
-    client: &RpcClient,
+/// Packs instruction groups into transactions, adding groups while the transaction still fits
+/// in a packet once the tip and compute limit instructions are added, up to `max_ixs` if
+/// given. A group is never split across transactions.
+fn pack_instructions(
+    payer: &Pubkey,
+    groups: &[Vec<Instruction>],
+    lookup_tables: &[AddressLookupTableAccount],
+    max_ixs: Option<usize>,
+) -> Vec<Vec<Instruction>> {
+    let mut batches = vec![];
+    let mut current: Vec<Instruction> = vec![];
+    for group in groups.iter().filter(|group| !group.is_empty()) {
+        let mut candidate = current.clone();
+        candidate.extend_from_slice(group);
+        let fits = candidate.len() <= max_ixs.unwrap_or(usize::MAX)
+            && estimate_tx_size(payer, &candidate, lookup_tables)
+                .is_some_and(|size| size <= PACKET_DATA_SIZE);
+        if fits || current.is_empty() {
+            current = candidate;
+        } else {
+            batches.push(std::mem::replace(&mut current, group.clone()));
+        }
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    batches
+}
+
+/// Serialized size of a signed transaction, counting a tip transfer and compute unit limit.
+fn estimate_tx_size(
+    payer: &Pubkey,
     ixs: &[Instruction],
-    signer: &Keypair,
-) -> (Hash, u64, RpcSendTransactionConfig, Transaction) {
-    let (hash, slot) = client
-        .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
-        .await
-        .unwrap();
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Option<usize> {
+    let mut ixs = ixs.to_vec();
+    ixs.push(system_instruction::transfer(
+        payer,
+        &Pubkey::new_unique(),
+        0,
+    ));
+    ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(0));
+    let message = v0::Message::try_compile(payer, &ixs, lookup_tables, Hash::default()).ok()?;
+    let signatures = message.header.num_required_signatures as usize;
+    let message_size = bincode::serialized_size(&VersionedMessage::V0(message)).ok()? as usize;
+    Some(1 + signatures * 64 + message_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::instruction::AccountMeta;
+
+    use super::*;
 
-    let send_cfg = RpcSendTransactionConfig {
-        skip_preflight: true,
-        preflight_commitment: Some(CommitmentLevel::Processed),
-        encoding: Some(UiTransactionEncoding::Base64),
-        max_retries: None,
-        min_context_slot: Some(slot),
-    };
-    let message = Message::new(ixs, Some(&signer.pubkey()));
+    /// An instruction with `accounts` fresh accounts and `data` bytes of data.
+    fn ix(accounts: usize, data: usize) -> Instruction {
+        Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &vec![0; data],
+            (0..accounts)
+                .map(|_| AccountMeta::new(Pubkey::new_unique(), false))
+                .collect(),
+        )
+    }
 
-    let mut tx = Transaction::new(&[&signer], message, hash);
+    #[test]
+    fn packs_small_groups_together() {
+        let payer = Pubkey::new_unique();
+        let groups = vec![vec![ix(1, 8)], vec![ix(1, 8), ix(1, 8)], vec![ix(1, 8)]];
+        let batches = pack_instructions(&payer, &groups, &[], None);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].len(), 4);
+    }
 
-    tx.sign(&[&signer], hash);
-    (hash, slot, send_cfg, tx)
+    #[test]
+    fn respects_max_ixs_without_splitting_groups() {
+        let payer = Pubkey::new_unique();
+        let groups = vec![
+            vec![ix(1, 8), ix(1, 8)],
+            vec![ix(1, 8), ix(1, 8)],
+            vec![ix(1, 8)],
+        ];
+        let batches = pack_instructions(&payer, &groups, &[], Some(3));
+        let lens: Vec<usize> = batches.iter().map(|batch| batch.len()).collect();
+        assert_eq!(lens, vec![2, 3]);
+    }
+
+    #[test]
+    fn splits_on_packet_size() {
+        let payer = Pubkey::new_unique();
+        let groups: Vec<Vec<Instruction>> = (0..6).map(|_| vec![ix(2, 300)]).collect();
+        let batches = pack_instructions(&payer, &groups, &[], None);
+        assert!(batches.len() > 1);
+        assert_eq!(batches.iter().map(|batch| batch.len()).sum::<usize>(), 6);
+        for batch in batches.iter() {
+            assert!(estimate_tx_size(&payer, batch, &[]).unwrap() <= PACKET_DATA_SIZE);
+        }
+    }
+
+    #[test]
+    fn keeps_oversized_group_whole() {
+        let payer = Pubkey::new_unique();
+        let groups = vec![vec![ix(1, 8)], vec![ix(1, 8), ix(1, 8), ix(1, 8)]];
+        let batches = pack_instructions(&payer, &groups, &[], Some(2));
+        let lens: Vec<usize> = batches.iter().map(|batch| batch.len()).collect();
+        assert_eq!(lens, vec![1, 3]);
+    }
+
+    #[test]
+    fn skips_empty_groups() {
+        let payer = Pubkey::new_unique();
+        let groups = vec![vec![], vec![ix(1, 8)], vec![]];
+        let batches = pack_instructions(&payer, &groups, &[], None);
+        assert_eq!(batches.len(), 1);
+        assert!(pack_instructions(&payer, &[vec![]], &[], None).is_empty());
+    }
 }
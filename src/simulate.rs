@@ -0,0 +1,246 @@
+use colored::*;
+use serde_json::{json, Value};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    client_error::Result as ClientResult,
+    rpc_client::SerializableTransaction,
+    rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig},
+    rpc_request::RpcRequest,
+    rpc_response::RpcSimulateTransactionResult,
+};
+use solana_program::{
+    instruction::Instruction, message::Message, native_token::lamports_to_sol, pubkey::Pubkey,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    compute_budget::ComputeBudgetInstruction,
+    transaction::{Transaction, TransactionError, VersionedTransaction},
+};
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::{
+    cu_limits::CU_LIMIT_MAX, errors::describe_transaction_error, utils::get_balances, Miner,
+};
+
+/// What a simulated transaction would do.
+pub struct Simulation {
+    pub err: Option<String>,
+    pub units_consumed: Option<u64>,
+    pub logs: Vec<String>,
+    pub balance_changes: Vec<(Pubkey, i64)>,
+}
+
+impl Simulation {
+    pub fn print(&self, label: &str) {
+        println!("\n{} {}", "SIMULATION".bold().cyan(), label);
+        match &self.err {
+            Some(err) => println!("{}: {}", "Result".bold(), err.red()),
+            None => println!("{}: {}", "Result".bold(), "success".green()),
+        }
+        if let Some(units) = self.units_consumed {
+            println!("{}: {}", "Compute units".bold(), units);
+        }
+        for (address, change) in self.balance_changes.iter() {
+            if change.ne(&0) {
+                println!(
+                    "{}: {:+} SOL",
+                    address,
+                    change.signum() as f64 * lamports_to_sol(change.unsigned_abs())
+                );
+            }
+        }
+        for (i, log) in self.logs.iter().enumerate() {
+            println!("{:>3}: {}", i + 1, log.dimmed());
+        }
+    }
+}
+
+/// Signers are the accounts whose SOL a transaction can spend.
+fn signer_keys(tx: &VersionedTransaction) -> Vec<Pubkey> {
+    let keys = tx.message.static_account_keys();
+    let signers = tx.message.header().num_required_signatures as usize;
+    keys[..signers.min(keys.len())].to_vec()
+}
+
+impl Miner {
+    /// Compute units the instructions use, measured by simulating them unsigned.
+    pub async fn simulate_compute_units(&self, ixs: &[Instruction], payer: &Pubkey) -> Option<u64> {
+        let res = self.simulate_unsigned(ixs, payer).await.ok()?;
+        match res.err {
+            Some(_) => None,
+            None => res.units_consumed,
+        }
+    }
+
+    /// Why the instructions would fail, simulating them unsigned. Says nothing when the
+    /// simulation itself couldn't run.
+    pub async fn simulate_failure(&self, ixs: &[Instruction], payer: &Pubkey) -> Option<String> {
+        let res = self.simulate_unsigned(ixs, payer).await.ok()?;
+        res.err.map(|err| describe_transaction_error(&err))
+    }
+
+    async fn simulate_unsigned(
+        &self,
+        ixs: &[Instruction],
+        payer: &Pubkey,
+    ) -> ClientResult<RpcSimulateTransactionResult> {
+        let mut sim_ixs = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+            CU_LIMIT_MAX,
+        )];
+        sim_ixs.extend_from_slice(ixs);
+        let tx = Transaction::new_unsigned(Message::new(&sim_ixs, Some(payer)));
+        self.rpc_client()
+            .simulate_transaction_with_config(
+                &tx,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map(|res| res.value)
+    }
+
+    /// Runs `simulateTransaction` without needing a valid blockhash or signatures.
+    pub async fn simulate_transaction(
+        &self,
+        tx: &impl SerializableTransaction,
+        signers: &[Pubkey],
+    ) -> Simulation {
+        let before = get_balances(&self.rpc_client(), signers).await;
+        let res = self
+            .rpc_client()
+            .simulate_transaction_with_config(
+                tx,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    accounts: Some(RpcSimulateTransactionAccountsConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        addresses: signers.iter().map(|key| key.to_string()).collect(),
+                    }),
+                    ..Default::default()
+                },
+            )
+            .await;
+        match res {
+            Ok(res) => {
+                let result = res.value;
+                let after = result.accounts.unwrap_or_default();
+                Simulation {
+                    err: result.err.map(|err| describe_transaction_error(&err)),
+                    units_consumed: result.units_consumed,
+                    logs: result.logs.unwrap_or_default(),
+                    balance_changes: signers
+                        .iter()
+                        .zip(before.iter())
+                        .zip(after.iter())
+                        .map(|((key, before), after)| {
+                            let after = after.as_ref().map(|a| a.lamports).unwrap_or(0);
+                            (*key, after as i64 - *before as i64)
+                        })
+                        .collect(),
+                }
+            }
+            Err(err) => Simulation {
+                err: Some(err.to_string()),
+                units_consumed: None,
+                logs: vec![],
+                balance_changes: vec![],
+            },
+        }
+    }
+
+    /// Runs Jito's `simulateBundle`, which the RPC endpoint must support.
+    pub async fn simulate_bundle(&self, txs: &[VersionedTransaction]) -> Vec<Simulation> {
+        let encoded: Vec<String> = txs
+            .iter()
+            .map(|tx| {
+                base64::Engine::encode(
+                    &base64::prelude::BASE64_STANDARD,
+                    bincode::serialize(tx).unwrap(),
+                )
+            })
+            .collect();
+        let accounts: Vec<Vec<Pubkey>> = txs.iter().map(signer_keys).collect();
+        let accounts_config = |keys: &Vec<Pubkey>| {
+            json!({
+                "encoding": "base64",
+                "addresses": keys.iter().map(|key| key.to_string()).collect::<Vec<_>>(),
+            })
+        };
+        let params = json!([
+            { "encodedTransactions": encoded },
+            {
+                "skipSigVerify": true,
+                "replaceRecentBlockhash": true,
+                "preExecutionAccountsConfigs": accounts.iter().map(accounts_config).collect::<Vec<_>>(),
+                "postExecutionAccountsConfigs": accounts.iter().map(accounts_config).collect::<Vec<_>>(),
+            }
+        ]);
+        let res: Result<Value, _> = self
+            .rpc_client()
+            .send(
+                RpcRequest::Custom {
+                    method: "simulateBundle",
+                },
+                params,
+            )
+            .await;
+        let results = match res {
+            Ok(res) => res["value"]["transactionResults"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default(),
+            Err(err) => {
+                return vec![Simulation {
+                    err: Some(format!("simulateBundle failed: {}", err)),
+                    units_consumed: None,
+                    logs: vec![],
+                    balance_changes: vec![],
+                }]
+            }
+        };
+        results
+            .iter()
+            .zip(accounts.iter())
+            .map(|(result, keys)| {
+                let lamports =
+                    |key: &str, i: usize| result[key][i]["lamports"].as_u64().unwrap_or(0);
+                Simulation {
+                    err: match &result["err"] {
+                        Value::Null => None,
+                        err => Some(
+                            serde_json::from_value::<TransactionError>(err.clone())
+                                .map(|err| describe_transaction_error(&err))
+                                .unwrap_or_else(|_| err.to_string()),
+                        ),
+                    },
+                    units_consumed: result["unitsConsumed"].as_u64(),
+                    logs: result["logs"]
+                        .as_array()
+                        .map(|logs| {
+                            logs.iter()
+                                .filter_map(|log| log.as_str().map(String::from))
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    balance_changes: keys
+                        .iter()
+                        .enumerate()
+                        .map(|(i, key)| {
+                            let before = lamports("preExecutionAccounts", i);
+                            let after = lamports("postExecutionAccounts", i);
+                            (*key, after as i64 - before as i64)
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
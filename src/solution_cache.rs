@@ -0,0 +1,192 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use colored::*;
+use drillx::Solution;
+use serde::{Deserialize, Serialize};
+use solana_program::pubkey::Pubkey;
+
+#[derive(Serialize, Deserialize)]
+struct SavedSolution {
+    challenge: [u8; 32],
+    digest: [u8; 16],
+    nonce: [u8; 8],
+    difficulty: u32,
+}
+
+/// Each wallet's best solution for its current challenge, kept on disk so work hashed before
+/// a crash or restart can still be submitted. A solution stays good until the wallet's
+/// challenge changes, which only happens when it mines.
+pub struct SolutionCache {
+    path: PathBuf,
+    solutions: HashMap<String, SavedSolution>,
+}
+
+impl SolutionCache {
+    pub fn load(path: &str) -> Self {
+        let solutions = std::fs::read(path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        Self {
+            path: PathBuf::from(path),
+            solutions,
+        }
+    }
+
+    /// The saved solution for `authority`, if it was found for `challenge`.
+    pub fn get(&self, authority: &Pubkey, challenge: &[u8; 32]) -> Option<Solution> {
+        let saved = self.solutions.get(&authority.to_string())?;
+        if saved.challenge.ne(challenge) {
+            return None;
+        }
+        let solution = Solution::new(saved.digest, saved.nonce);
+        solution.is_valid(challenge).then_some(solution)
+    }
+
+    /// Saves `solution` unless a better one for the same challenge is already saved.
+    pub fn save(&mut self, authority: &Pubkey, challenge: [u8; 32], solution: &Solution) {
+        let difficulty = solution.to_hash().difficulty();
+        let key = authority.to_string();
+        if let Some(saved) = self.solutions.get(&key) {
+            if saved.challenge.eq(&challenge) && saved.difficulty.ge(&difficulty) {
+                return;
+            }
+        }
+        self.solutions.insert(
+            key,
+            SavedSolution {
+                challenge,
+                digest: solution.d,
+                nonce: solution.n,
+                difficulty,
+            },
+        );
+        self.write();
+    }
+
+    /// Forgets every saved solution, once they have been submitted.
+    pub fn clear(&mut self) {
+        if self.solutions.is_empty() {
+            return;
+        }
+        self.solutions.clear();
+        self.write();
+    }
+
+    fn write(&self) {
+        // Write then rename, so a crash mid-write can't leave a torn file
+        let json =
+            serde_json::to_string_pretty(&self.solutions).expect("Failed to serialize solutions");
+        let tmp = self.path.with_extension("tmp");
+        if let Err(err) = std::fs::write(&tmp, json).and_then(|_| std::fs::rename(&tmp, &self.path))
+        {
+            println!(
+                "{} Failed to save solutions to {}: {}",
+                "WARNING".bold().yellow(),
+                self.path.display(),
+                err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHALLENGE: [u8; 32] = [7; 32];
+
+    /// A scratch cache file unique to the test.
+    fn cache_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "ore-solution-cache-{}-{}.json",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_file(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    /// The first valid solution for `challenge` of at least `min_difficulty`.
+    fn solve(challenge: &[u8; 32], min_difficulty: u32) -> (Solution, u32) {
+        (0u64..)
+            .find_map(|nonce| {
+                let hash = drillx::hash(challenge, &nonce.to_le_bytes()).ok()?;
+                let difficulty = hash.difficulty();
+                difficulty
+                    .ge(&min_difficulty)
+                    .then(|| (Solution::new(hash.d, nonce.to_le_bytes()), difficulty))
+            })
+            .unwrap()
+    }
+
+    /// Two valid solutions for `CHALLENGE`, the second of higher difficulty.
+    fn solutions() -> (Solution, Solution) {
+        let (worse, difficulty) = solve(&CHALLENGE, 0);
+        let (better, _) = solve(&CHALLENGE, difficulty + 1);
+        (worse, better)
+    }
+
+    #[test]
+    fn keeps_the_best_solution_for_the_challenge() {
+        let path = cache_path("best");
+        let (worse, better) = solutions();
+        let authority = Pubkey::new_unique();
+        let mut cache = SolutionCache::load(&path);
+        cache.save(&authority, CHALLENGE, &better);
+        cache.save(&authority, CHALLENGE, &worse);
+        assert_eq!(cache.get(&authority, &CHALLENGE).unwrap().n, better.n);
+        assert!(cache.get(&Pubkey::new_unique(), &CHALLENGE).is_none());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn forgets_solutions_once_the_challenge_changes() {
+        let path = cache_path("challenge");
+        let (_, better) = solutions();
+        let authority = Pubkey::new_unique();
+        let mut cache = SolutionCache::load(&path);
+        cache.save(&authority, CHALLENGE, &better);
+        assert!(cache.get(&authority, &[8; 32]).is_none());
+
+        // Any solution for a new challenge replaces the old one
+        let next = [8; 32];
+        let (solution, _) = solve(&next, 0);
+        cache.save(&authority, next, &solution);
+        assert!(cache.get(&authority, &CHALLENGE).is_none());
+        assert_eq!(cache.get(&authority, &next).unwrap().n, solution.n);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn survives_a_restart_until_cleared() {
+        let path = cache_path("restart");
+        let (_, better) = solutions();
+        let authority = Pubkey::new_unique();
+        SolutionCache::load(&path).save(&authority, CHALLENGE, &better);
+
+        let mut cache = SolutionCache::load(&path);
+        assert_eq!(cache.get(&authority, &CHALLENGE).unwrap().n, better.n);
+        cache.clear();
+        assert!(SolutionCache::load(&path)
+            .get(&authority, &CHALLENGE)
+            .is_none());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn rejects_a_tampered_solution() {
+        let path = cache_path("tampered");
+        let (_, better) = solutions();
+        let authority = Pubkey::new_unique();
+        let mut cache = SolutionCache::load(&path);
+        cache.save(&authority, CHALLENGE, &better);
+        cache
+            .solutions
+            .get_mut(&authority.to_string())
+            .unwrap()
+            .nonce[0] ^= 1;
+        assert!(cache.get(&authority, &CHALLENGE).is_none());
+        let _ = std::fs::remove_file(path);
+    }
+}
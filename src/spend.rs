@@ -0,0 +1,151 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use colored::*;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_program::native_token::lamports_to_sol;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::{jito_tip::JITO_TIP_ACCOUNTS, ledger::Ledger, Miner};
+
+/// Span `--max-daily-spend` is measured over.
+const WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Fees and tips paid by landed transactions over the last day, so submitting can stop once
+/// `--max-daily-spend` is used up. Seeded from the ledger, so restarting doesn't reset it.
+pub struct SpendTracker {
+    cap: Option<u64>,
+    spends: Mutex<VecDeque<(Instant, u64)>>,
+    /// Tips paid since the last `take_tips`
+    tips: Mutex<u64>,
+}
+
+impl SpendTracker {
+    pub fn new(cap: Option<u64>, ledger: Option<&Ledger>) -> Self {
+        let now = Instant::now();
+        let now_ts = chrono::Utc::now().timestamp();
+        let spends = ledger
+            .and_then(|ledger| ledger.spends(now_ts - WINDOW.as_secs() as i64).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(timestamp, lamports)| {
+                let age = Duration::from_secs(now_ts.saturating_sub(timestamp).max(0) as u64);
+                now.checked_sub(age).map(|at| (at, lamports))
+            })
+            .collect();
+        Self {
+            cap,
+            spends: Mutex::new(spends),
+            tips: Mutex::new(0),
+        }
+    }
+
+    pub fn record(&self, fee: u64, tip: u64) {
+        self.spends
+            .lock()
+            .unwrap()
+            .push_back((Instant::now(), fee + tip));
+        *self.tips.lock().unwrap() += tip;
+    }
+
+    /// Tips landed transactions paid since the last call.
+    pub fn take_tips(&self) -> u64 {
+        std::mem::take(&mut *self.tips.lock().unwrap())
+    }
+
+    /// Lamports spent over the last day.
+    pub fn spent(&self) -> u64 {
+        let mut spends = self.spends.lock().unwrap();
+        while spends.front().is_some_and(|(at, _)| at.elapsed() >= WINDOW) {
+            spends.pop_front();
+        }
+        spends.iter().map(|(_, lamports)| lamports).sum()
+    }
+
+    pub fn cap(&self) -> Option<u64> {
+        self.cap
+    }
+
+    pub fn exhausted(&self) -> bool {
+        self.cap.is_some_and(|cap| self.spent() >= cap)
+    }
+
+    /// How long until enough spending ages out of the window to be under the cap again.
+    pub fn resumes_in(&self) -> Duration {
+        let Some(cap) = self.cap else {
+            return Duration::ZERO;
+        };
+        let spends = self.spends.lock().unwrap();
+        let mut spent: u64 = spends.iter().map(|(_, lamports)| lamports).sum();
+        for (at, lamports) in spends.iter() {
+            if spent < cap {
+                break;
+            }
+            spent -= lamports;
+            if spent < cap {
+                return WINDOW.saturating_sub(at.elapsed());
+            }
+        }
+        Duration::ZERO
+    }
+}
+
+impl Miner {
+    /// Reads back the fee and tip each landed transaction paid, and counts them towards the
+    /// daily spend and into the ledger.
+    pub async fn record_spend(&self, signatures: &[Signature]) {
+        for signature in signatures {
+            let Some((fee, tip)) = self.transaction_spend(signature).await else {
+                continue;
+            };
+            self.spend.record(fee, tip);
+            if let Some(ledger) = &self.ledger {
+                if let Err(err) = ledger.record_transaction(&signature.to_string(), fee, tip) {
+                    println!(
+                        "{} Failed to record transaction: {}",
+                        "WARNING".bold().yellow(),
+                        err
+                    );
+                }
+            }
+        }
+        if let Some(cap) = self.spend.cap() {
+            println!(
+                "Spent {} of {} SOL allowed today",
+                lamports_to_sol(self.spend.spent()),
+                lamports_to_sol(cap)
+            );
+        }
+    }
+
+    /// The fee a transaction paid, and what it tipped to Jito tip accounts.
+    async fn transaction_spend(&self, signature: &Signature) -> Option<(u64, u64)> {
+        let tx = self
+            .rpc_client()
+            .get_transaction_with_config(
+                signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await
+            .ok()?;
+        let meta = tx.transaction.meta?;
+        let decoded = tx.transaction.transaction.decode()?;
+        let tip = decoded
+            .message
+            .static_account_keys()
+            .iter()
+            .zip(meta.pre_balances.iter().zip(meta.post_balances.iter()))
+            .filter(|(key, _)| JITO_TIP_ACCOUNTS.contains(key))
+            .map(|(_, (pre, post))| post.saturating_sub(*pre))
+            .sum();
+        Some((meta.fee, tip))
+    }
+}
@@ -1,43 +1,145 @@
 use std::str::FromStr;
 
-use solana_program::pubkey::Pubkey;
-use solana_sdk::signature::Signer;
+use colored::*;
+use ore_api::consts::MINT_ADDRESS;
+use solana_program::{instruction::Instruction, program_pack::Pack, pubkey::Pubkey};
+use solana_sdk::signature::{Keypair, Signer};
 
 use crate::{
-    args::StakeArgs, cu_limits::CU_LIMIT_CLAIM, send_and_confirm::ComputeBudget,
-    utils::amount_f64_to_u64, Miner,
+    args::StakeArgs,
+    cu_limits::{ComputeBudget, CU_LIMIT_CLAIM},
+    exit::{abort, fail_with, ExitCode},
+    utils::{amount_f64_to_u64, amount_u64_to_string, ask_confirm, get_accounts},
+    Miner,
 };
 
+/// Wallets staked per bundle, so each bundle stays within Jito's transaction limit.
+const STAKES_PER_BUNDLE: usize = 20;
+
 impl Miner {
     pub async fn stake(&self, args: StakeArgs) {
+        if args.all {
+            self.stake_all(args).await;
+            return;
+        }
+
         // Get signer
         let signer = self.signer();
         let sender = match args.sender {
-            Some(sender) => Pubkey::from_str(&sender).expect("Failed to parse sender address"),
+            Some(sender) => Pubkey::from_str(&sender)
+                .unwrap_or_else(|_| abort(ExitCode::Config, "Failed to parse sender address")),
             None => spl_associated_token_account::get_associated_token_address(
                 &signer.pubkey(),
                 &ore_api::consts::MINT_ADDRESS,
             ),
         };
 
-        // Get token account
-        let Ok(Some(token_account)) = self.rpc_client.get_token_account(&sender).await else {
-            println!("Failed to fetch token account");
-            return;
-        };
-
         // Parse amount
         let amount: u64 = if let Some(amount) = args.amount {
             amount_f64_to_u64(amount)
         } else {
+            if args.durable.sign_only {
+                println!("--sign-only needs --amount, the balance can't be read offline");
+                return;
+            }
+
+            // Get token account
+            let Ok(Some(token_account)) = self.rpc_client().get_token_account(&sender).await else {
+                println!("Failed to fetch token account");
+                return;
+            };
             u64::from_str(token_account.token_amount.amount.as_str())
                 .expect("Failed to parse token balance")
         };
 
         // Send tx
         let ix = ore_api::instruction::stake(signer.pubkey(), sender, amount);
-        self.send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
+        if let Err(err) = self
+            .send_and_confirm_durable(&[ix], ComputeBudget::Fixed(CU_LIMIT_CLAIM), &args.durable)
             .await
-            .ok();
+        {
+            fail_with(&err);
+        }
+    }
+
+    /// Stakes from every folder wallet's own token account into its proof, packing the
+    /// stakes into Jito bundles paid for by the fee payer.
+    async fn stake_all(&self, args: StakeArgs) {
+        let signers = self.multi_signers();
+        let fee_payer = self.fee_payer();
+        let token_accounts: Vec<Pubkey> = signers
+            .iter()
+            .map(|signer| {
+                spl_associated_token_account::get_associated_token_address(
+                    &signer.pubkey(),
+                    &MINT_ADDRESS,
+                )
+            })
+            .collect();
+
+        // Work out each wallet's stake from its token balance
+        let per_wallet = args.amount.map(amount_f64_to_u64);
+        let floor = args.floor.map(amount_f64_to_u64).unwrap_or(0);
+        let accounts = get_accounts(&self.rpc_client(), &token_accounts).await;
+        let mut ixs = vec![];
+        let mut total = 0;
+        for ((signer, token_account), account) in signers
+            .iter()
+            .zip(token_accounts.iter())
+            .zip(accounts.iter())
+        {
+            let balance = account
+                .as_ref()
+                .and_then(|account| spl_token::state::Account::unpack(&account.data).ok())
+                .map(|account| account.amount)
+                .unwrap_or(0);
+            let available = balance.saturating_sub(floor);
+            let amount = per_wallet.map_or(available, |amount| amount.min(available));
+            if amount.gt(&0) {
+                ixs.push(ore_api::instruction::stake(
+                    signer.pubkey(),
+                    *token_account,
+                    amount,
+                ));
+                total += amount;
+            }
+        }
+        if ixs.is_empty() {
+            println!("Nothing to stake");
+            return;
+        }
+
+        // Confirm user wants to stake
+        if !ask_confirm(
+            format!(
+                "\nYou are about to stake {} from {} wallets.\n\nAre you sure you want to continue? [Y/n]",
+                format!("{} ORE", amount_u64_to_string(total)).bold(),
+                ixs.len(),
+            )
+            .as_str(),
+        ) {
+            return;
+        }
+
+        let signers: Vec<&Keypair> = signers.iter().collect();
+        for chunk in ixs.chunks(STAKES_PER_BUNDLE) {
+            let groups: Vec<Vec<Instruction>> = chunk.iter().map(|ix| vec![ix.clone()]).collect();
+            if let Err(err) = self
+                .send_and_confirm_bundle(
+                    &groups,
+                    &signers,
+                    &fee_payer,
+                    false,
+                    args.jito_tip,
+                    args.jito_url.clone(),
+                    args.jito_uuid.as_deref(),
+                    None,
+                )
+                .await
+            {
+                println!("{} Failed to stake: {}", "ERROR".bold().red(), err);
+                fail_with(&err);
+            }
+        }
     }
 }
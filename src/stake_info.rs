@@ -0,0 +1,94 @@
+use colored::*;
+use serde_json::json;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+use crate::{
+    labels::label,
+    rewards::stake_multiplier,
+    utils::{amount_u64_to_string, get_config, get_proofs_with_authorities, print_json},
+    Miner,
+};
+
+/// Multiplier steps reported as tiers. The program's multiplier itself is continuous.
+const TIER_STEP: f64 = 0.25;
+
+impl Miner {
+    pub async fn stake_info(&self) {
+        let authorities: Vec<Pubkey> = match self.folder_path {
+            Some(_) => self
+                .multi_signers()
+                .iter()
+                .map(|signer| signer.pubkey())
+                .collect(),
+            None => vec![self.signer().pubkey()],
+        };
+        let config = get_config(&self.rpc_client()).await;
+        let proofs = get_proofs_with_authorities(&self.rpc_client(), &authorities).await;
+
+        if self.json {
+            let wallets: Vec<_> = authorities
+                .iter()
+                .zip(proofs.iter())
+                .map(|(authority, proof)| match proof {
+                    Some(proof) => {
+                        let multiplier = stake_multiplier(proof.balance, config.top_balance);
+                        json!({
+                            "wallet": authority.to_string(),
+                            "staked": proof.balance,
+                            "multiplier": multiplier,
+                            "to_next_tier": next_tier_balance(multiplier, config.top_balance)
+                                .map(|balance| balance.saturating_sub(proof.balance)),
+                        })
+                    }
+                    None => json!({ "wallet": authority.to_string(), "staked": null }),
+                })
+                .collect();
+            print_json(&json!({ "top_balance": config.top_balance, "wallets": wallets }));
+            return;
+        }
+
+        println!(
+            "{}: {} ORE",
+            "Top stake".bold(),
+            amount_u64_to_string(config.top_balance)
+        );
+        println!(
+            "\n{:<44} {:>16} {:>10} {:>16}",
+            "Wallet".bold(),
+            "Staked".bold(),
+            "Multiplier".bold(),
+            "To next tier".bold()
+        );
+        for (authority, proof) in authorities.iter().zip(proofs.iter()) {
+            let Some(proof) = proof else {
+                println!("{:<44} {:>16}", label(authority), "no proof".dimmed());
+                continue;
+            };
+            let multiplier = stake_multiplier(proof.balance, config.top_balance);
+            let to_next = match next_tier_balance(multiplier, config.top_balance) {
+                Some(balance) => format!(
+                    "{} ORE",
+                    amount_u64_to_string(balance.saturating_sub(proof.balance))
+                ),
+                None => "max".green().to_string(),
+            };
+            println!(
+                "{:<44} {:>12} ORE {:>9.2}x {:>16}",
+                label(authority),
+                amount_u64_to_string(proof.balance),
+                multiplier,
+                to_next
+            );
+        }
+    }
+}
+
+/// Stake a proof needs for the tier above `multiplier`, or `None` at the 2x cap.
+fn next_tier_balance(multiplier: f64, top_balance: u64) -> Option<u64> {
+    if multiplier.ge(&2.0) || top_balance.eq(&0) {
+        return None;
+    }
+    let next = ((multiplier - 1.0) / TIER_STEP).floor() * TIER_STEP + TIER_STEP;
+    Some((top_balance as f64 * next.min(1.0)).ceil() as u64)
+}
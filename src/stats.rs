@@ -0,0 +1,285 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use chrono::{Local, TimeZone};
+use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
+
+use crate::{labels::label, notify::PassSummary, utils::amount_u64_to_string};
+
+/// Running totals for the current `mine` session.
+pub struct SessionStats {
+    started_at: Instant,
+    pub passes: u64,
+    pub landed: u64,
+    difficulty_sum: u64,
+    difficulty_count: u64,
+    pub rewards: u64,
+    pub fees: u64,
+    pub tips: u64,
+    pub rpc_calls: BTreeMap<String, u64>,
+    pub wallets: BTreeMap<Pubkey, WalletStats>,
+    pub timeline: Timeline,
+}
+
+/// How often a wallet's mine landed this session, and what it earned when it did.
+#[derive(Clone, Copy, Default)]
+pub struct WalletStats {
+    pub passes: u64,
+    pub landed: u64,
+    pub rewards: u64,
+}
+
+impl WalletStats {
+    pub fn missed(&self) -> u64 {
+        self.passes - self.landed
+    }
+
+    pub fn reward_per_landed(&self) -> u64 {
+        self.rewards.checked_div(self.landed).unwrap_or(0)
+    }
+}
+
+impl Default for SessionStats {
+    fn default() -> Self {
+        Self {
+            started_at: Instant::now(),
+            passes: 0,
+            landed: 0,
+            difficulty_sum: 0,
+            difficulty_count: 0,
+            rewards: 0,
+            fees: 0,
+            tips: 0,
+            rpc_calls: BTreeMap::new(),
+            wallets: BTreeMap::new(),
+            timeline: Timeline::default(),
+        }
+    }
+}
+
+impl SessionStats {
+    /// Records a finished pass. `spent` is the total lamports the fee payer lost during it.
+    pub fn record_pass(&mut self, summary: &PassSummary, spent: u64) {
+        self.passes += 1;
+        if summary.signature.is_some() {
+            self.landed += 1;
+        }
+        for (_, difficulty) in summary.difficulties.iter() {
+            self.difficulty_sum += *difficulty as u64;
+            self.difficulty_count += 1;
+        }
+        self.rewards += summary.reward;
+        self.tips += summary.tip.min(spent);
+        self.fees += spent.saturating_sub(summary.tip);
+    }
+
+    /// Records each wallet's part in a pass, with its reward if its mine landed.
+    pub fn record_wallets(&mut self, results: &[(Pubkey, Option<u64>)]) {
+        for (wallet, reward) in results {
+            let stats = self.wallets.entry(*wallet).or_default();
+            stats.passes += 1;
+            if let Some(reward) = reward {
+                stats.landed += 1;
+                stats.rewards += reward;
+            }
+        }
+    }
+
+    /// Folds in RPC requests made since the last call, by method.
+    pub fn record_rpc(&mut self, calls: BTreeMap<String, u64>) {
+        for (method, count) in calls {
+            *self.rpc_calls.entry(method).or_default() += count;
+        }
+    }
+
+    pub fn rpc_total(&self) -> u64 {
+        self.rpc_calls.values().sum()
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    pub fn average_difficulty(&self) -> f64 {
+        if self.difficulty_count == 0 {
+            0.0
+        } else {
+            self.difficulty_sum as f64 / self.difficulty_count as f64
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let uptime = self.uptime().as_secs();
+        let mut text = format!(
+            "Session summary\n\
+             Uptime: {}h {}m {}s\n\
+             Passes: {} attempted, {} landed\n\
+             Average difficulty: {:.2}\n\
+             Earned: {} ORE\n\
+             Spent: {} SOL in fees, {} SOL in tips",
+            uptime / 3600,
+            (uptime % 3600) / 60,
+            uptime % 60,
+            self.passes,
+            self.landed,
+            self.average_difficulty(),
+            amount_u64_to_string(self.rewards),
+            lamports_to_sol(self.fees),
+            lamports_to_sol(self.tips),
+        );
+        if !self.rpc_calls.is_empty() {
+            let mut calls: Vec<_> = self.rpc_calls.iter().collect();
+            calls.sort_by(|a, b| b.1.cmp(a.1));
+            text = format!(
+                "{}\nRPC: {} requests, {:.1} per pass\n{}",
+                text,
+                self.rpc_total(),
+                self.rpc_total() as f64 / self.passes.max(1) as f64,
+                calls
+                    .iter()
+                    .map(|(method, count)| format!("  {}: {}", method, count))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            );
+        }
+        if !self.wallets.is_empty() {
+            text = format!(
+                "{}\nWallets:\n{}",
+                text,
+                self.wallets
+                    .iter()
+                    .map(|(wallet, stats)| format!(
+                        "  {}: {} landed, {} missed, {} ORE per landed pass",
+                        label(wallet),
+                        stats.landed,
+                        stats.missed(),
+                        amount_u64_to_string(stats.reward_per_landed())
+                    ))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            );
+        }
+        if !self.timeline.events().is_empty() {
+            text = format!("{}\n\nTimeline\n{}", text, self.timeline.to_text());
+        }
+        text
+    }
+}
+
+/// Watches the share of recent passes that landed, raising an alert when it drops below the
+/// threshold and again only after it has recovered.
+pub struct LandingAlarm {
+    threshold: u8,
+    window: usize,
+    recent: VecDeque<bool>,
+    firing: bool,
+}
+
+impl LandingAlarm {
+    pub fn new(threshold: u8, window: usize) -> Self {
+        Self {
+            threshold,
+            window: window.max(1),
+            recent: VecDeque::new(),
+            firing: false,
+        }
+    }
+
+    /// Records a pass, returning the alert if it tipped the landing rate below the threshold.
+    pub fn record(&mut self, landed: bool) -> Option<String> {
+        if self.recent.len() == self.window {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(landed);
+        if self.recent.len() < self.window {
+            return None;
+        }
+        let landed = self.recent.iter().filter(|landed| **landed).count();
+        let rate = landed * 100 / self.window;
+        let below = rate < self.threshold as usize;
+        let tipped = below && !self.firing;
+        self.firing = below;
+        tipped.then(|| {
+            format!(
+                "Only {} of the last {} passes landed ({}%, below {}%). The tip may be too low, the RPC lagging, or the bus strategy poor.",
+                landed, self.window, rate, self.threshold
+            )
+        })
+    }
+}
+
+/// Timestamped events of a mining session, used to correlate earnings with network changes.
+#[derive(Default)]
+pub struct Timeline {
+    events: Vec<(i64, String)>,
+}
+
+impl Timeline {
+    pub fn annotate(&mut self, event: String) {
+        self.events.push((Local::now().timestamp(), event));
+    }
+
+    pub fn events(&self) -> &[(i64, String)] {
+        &self.events
+    }
+
+    pub fn to_text(&self) -> String {
+        self.events
+            .iter()
+            .map(|(ts, event)| {
+                let time = Local
+                    .timestamp_opt(*ts, 0)
+                    .single()
+                    .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_default();
+                format!("{} {}", time, event)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waits_for_a_full_window() {
+        let mut alarm = LandingAlarm::new(50, 4);
+        for _ in 0..3 {
+            assert!(alarm.record(false).is_none());
+        }
+        assert!(alarm.record(false).is_some());
+    }
+
+    #[test]
+    fn fires_once_until_the_rate_recovers() {
+        let mut alarm = LandingAlarm::new(50, 4);
+        let passes = [
+            true, true, false, false, false, false, true, true, true, false, false, false,
+        ];
+        let fired: Vec<bool> = passes
+            .iter()
+            .map(|landed| alarm.record(*landed))
+            .map(|alert| alert.is_some())
+            .collect();
+        // 2 of 4 is not below 50%, 1 of 4 tips it, staying low doesn't fire again, and once
+        // back at 2 of 4 the next drop fires anew
+        assert_eq!(
+            fired,
+            [false, false, false, false, true, false, false, false, false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn reports_the_rate() {
+        let mut alarm = LandingAlarm::new(80, 5);
+        for landed in [true, true, true, false] {
+            assert!(alarm.record(landed).is_none());
+        }
+        let alert = alarm.record(false).unwrap();
+        assert!(alert.contains("Only 3 of the last 5 passes landed (60%, below 80%)"));
+    }
+}
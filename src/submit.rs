@@ -0,0 +1,613 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use base64::Engine;
+use bincode::serialize;
+use colored::*;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::SerializableTransaction,
+    rpc_config::RpcSendTransactionConfig,
+    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+    rpc_response::RpcSimulateTransactionResult,
+};
+use solana_rpc_client::spinner;
+use solana_sdk::{
+    clock::DEFAULT_MS_PER_SLOT,
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    signature::Signature,
+    transaction::{TransactionError, VersionedTransaction},
+};
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::{
+    errors::{
+        classify_send_error, describe_transaction_error, explain_transaction_error, SendFailure,
+    },
+    tpu::QuicTpuClient,
+    Miner,
+};
+
+const JITO_AUTH_HEADER: &str = "x-jito-auth";
+
+/// Errors a submission gives up with, matched on to pick the exit code.
+pub const MAX_RETRIES: &str = "Max retries";
+pub const BLOCKHASH_EXPIRED: &str = "Blockhash expired";
+
+/// Confirmation checks, or resubmissions, between looking at whether the blockhash expired.
+const EXPIRY_CHECKS: usize = 10;
+
+/// Longest the final attempt waits for its transactions when the RPC can't say whether their
+/// blockhash expired. Blockhashes last about 150 blocks.
+const MAX_EXPIRY_WAIT: Duration = Duration::from_secs(90);
+
+/// Sends that go out unsimulated and without node-side retries: rebroadcasts, which the path
+/// already checked, and `--sender` endpoints, which forward straight to the leaders.
+const DIRECT_SEND_CONFIG: RpcSendTransactionConfig = RpcSendTransactionConfig {
+    skip_preflight: true,
+    preflight_commitment: None,
+    encoding: Some(UiTransactionEncoding::Base64),
+    max_retries: Some(0),
+    min_context_slot: None,
+};
+
+/// Signs the transactions again against a fresh blockhash for the given retry, for paths that
+/// resend with a new one rather than the original, or escalate their fees.
+pub type Resign<'a> =
+    dyn Fn(Hash, usize) -> ClientResult<Vec<VersionedTransaction>> + Send + Sync + 'a;
+
+/// Where a submission's transactions are sent. Every path shares the retry and confirmation
+/// logic in `Miner::submit`, and only differs in how the transactions reach a leader.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// Sends the transactions once, succeeding when the endpoint accepted them.
+    async fn send(&self, txs: &[VersionedTransaction]) -> ClientResult<()>;
+}
+
+/// Each transaction through `sendTransaction` on the RPC.
+pub struct RpcTransport {
+    pub client: Arc<RpcClient>,
+    pub config: RpcSendTransactionConfig,
+}
+
+#[async_trait]
+impl Transport for RpcTransport {
+    async fn send(&self, txs: &[VersionedTransaction]) -> ClientResult<()> {
+        for tx in txs {
+            match send_transaction_with_config(&self.client, tx, self.config).await {
+                Ok(_) => {}
+                // Landed from an earlier broadcast, so carry on with the rest
+                Err(err) if classify_send_error(&err) == SendFailure::AlreadyProcessed => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// All the transactions as one bundle through `sendBundle` on a Jito block engine.
+pub struct JitoTransport<'a> {
+    pub http: reqwest::Client,
+    pub url: String,
+    pub uuid: Option<&'a str>,
+}
+
+#[async_trait]
+impl Transport for JitoTransport<'_> {
+    async fn send(&self, txs: &[VersionedTransaction]) -> ClientResult<()> {
+        send_bundle(&self.http, txs, &self.url, self.uuid).await
+    }
+}
+
+/// Each transaction straight to the current and upcoming leaders over QUIC.
+pub struct TpuTransport<'a> {
+    pub tpu: &'a QuicTpuClient,
+}
+
+#[async_trait]
+impl Transport for TpuTransport<'_> {
+    async fn send(&self, txs: &[VersionedTransaction]) -> ClientResult<()> {
+        for tx in txs {
+            let wire = serialize(tx)
+                .map_err(|e| ClientErrorKind::Custom(format!("Serialization failed: {e}")))?;
+            if !self.tpu.send_wire_transaction(wire).await {
+                return Err(
+                    ClientErrorKind::Custom("No leader accepted the transaction".into()).into(),
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How persistently a path resends, and how long it waits on confirmation after each send.
+#[derive(Clone, Copy, Debug)]
+pub struct SubmitPolicy {
+    pub gateway_retries: usize,
+    pub confirm_retries: usize,
+    pub confirm_delay: Duration,
+    pub retry_delay: Duration,
+    /// Rebroadcast every interval until the transactions land or their blockhash expires,
+    /// instead of waiting out the confirmation checks.
+    pub spam_interval: Option<Duration>,
+}
+
+enum Confirmation {
+    Landed(Signature, u64),
+    Failed(TransactionError),
+    Pending,
+}
+
+/// Every signature sent for a submission. Any attempt may be the one that lands, so all of
+/// them are checked, not just the latest.
+#[derive(Default)]
+struct ConfirmationTracker {
+    sigs: Vec<Signature>,
+}
+
+impl ConfirmationTracker {
+    fn track(&mut self, sig: Signature) {
+        if !self.sigs.contains(&sig) {
+            self.sigs.push(sig);
+        }
+    }
+
+    async fn poll(&self, client: &RpcClient) -> ClientResult<Confirmation> {
+        let statuses = client.get_signature_statuses(&self.sigs).await?.value;
+        for (sig, status) in self.sigs.iter().zip(statuses) {
+            let Some(status) = status else {
+                continue;
+            };
+            if let Some(err) = status.err {
+                return Ok(Confirmation::Failed(err));
+            }
+            if status.satisfies_commitment(client.commitment()) {
+                return Ok(Confirmation::Landed(*sig, status.slot));
+            }
+        }
+        Ok(Confirmation::Pending)
+    }
+}
+
+impl Miner {
+    /// Sends `txs` through `transport` until one of the attempts lands, returning the first
+    /// transaction's signature. Given `resign`, each retry goes out with a fresh blockhash,
+    /// and escalated fees if configured.
+    /// Given `started`, the time to submit and to land is recorded for the control API.
+    pub async fn submit(
+        &self,
+        transport: &dyn Transport,
+        mut txs: Vec<VersionedTransaction>,
+        resign: Option<&Resign<'_>>,
+        policy: SubmitPolicy,
+        skip_confirm: bool,
+        mut started: Option<Instant>,
+    ) -> ClientResult<Signature> {
+        let progress_bar = spinner::new_progress_bar();
+        let client = self.rpc_client();
+        let mut tracker = ConfirmationTracker::default();
+        let mut submit_slot = None;
+        let mut attempts = 0;
+        loop {
+            // Stop paying once the daily budget is used up
+            if self.spend.exhausted() {
+                progress_bar.finish_with_message(format!(
+                    "{}: Daily spend cap reached",
+                    "ERROR".bold().red()
+                ));
+                return Err(ClientError {
+                    request: None,
+                    kind: ClientErrorKind::Custom("Daily spend cap reached".into()),
+                });
+            }
+            self.control.heartbeat();
+            progress_bar.set_message(format!("Submitting transaction... (attempt {})", attempts));
+            let sent = match transport.send(&txs).await {
+                // A copy already landed, so confirm it rather than resend and pay twice
+                Err(err) if classify_send_error(&err) == SendFailure::AlreadyProcessed => Ok(()),
+                sent => sent,
+            };
+            let mut delay = self.retry.backoff(attempts, policy.retry_delay);
+            match sent {
+                Ok(()) => {
+                    let sig = *txs[0].get_signature();
+                    if skip_confirm {
+                        progress_bar.finish_with_message(format!("Sent: {}", sig));
+                        return Ok(sig);
+                    }
+                    if let Some(started) = started.take() {
+                        self.control.record_submit_latency(started.elapsed());
+                        submit_slot = client
+                            .get_slot_with_commitment(CommitmentConfig::processed())
+                            .await
+                            .ok();
+                    }
+                    tracker.track(sig);
+
+                    // Confirm the tx landed, rebroadcasting it meanwhile when spamming. The last
+                    // attempt keeps checking for as long as its blockhash is valid.
+                    let (checks, delay) = match policy.spam_interval {
+                        Some(interval) => (usize::MAX, interval),
+                        None => (policy.confirm_retries, policy.confirm_delay),
+                    };
+                    let last_attempt = attempts >= policy.gateway_retries;
+                    let sent_at = Instant::now();
+                    let mut check = 0;
+                    loop {
+                        // Past its checks, the last attempt polls about once a slot
+                        if check >= checks {
+                            tokio::time::sleep(
+                                delay.max(Duration::from_millis(DEFAULT_MS_PER_SLOT)),
+                            )
+                            .await;
+                        } else {
+                            tokio::time::sleep(delay).await;
+                        }
+                        match tracker.poll(&client).await {
+                            Ok(Confirmation::Landed(sig, slot)) => {
+                                if let Some(submit_slot) = submit_slot {
+                                    let slots = slot.saturating_sub(submit_slot);
+                                    self.control.record_landing_latency(Duration::from_millis(
+                                        slots * DEFAULT_MS_PER_SLOT,
+                                    ));
+                                }
+                                progress_bar.finish_with_message(format!(
+                                    "{} {}",
+                                    "OK".bold().green(),
+                                    sig
+                                ));
+
+                                // An earlier attempt may be the one that landed
+                                let landed: Vec<_> = if sig.eq(txs[0].get_signature()) {
+                                    txs.iter().map(|tx| *tx.get_signature()).collect()
+                                } else {
+                                    vec![sig]
+                                };
+                                self.record_spend(&landed).await;
+                                return Ok(sig);
+                            }
+                            Ok(Confirmation::Failed(err)) => {
+                                progress_bar.finish_with_message(format!(
+                                    "{}: {}",
+                                    "ERROR".bold().red(),
+                                    describe_transaction_error(&err)
+                                ));
+                                return Err(ClientError {
+                                    request: None,
+                                    kind: ClientErrorKind::Custom(err.to_string()),
+                                });
+                            }
+                            Ok(Confirmation::Pending) => {}
+
+                            // Handle confirmation errors
+                            Err(err) => {
+                                progress_bar.set_message(format!(
+                                    "{}: {}",
+                                    "ERROR".bold().red(),
+                                    err.kind()
+                                ));
+                            }
+                        }
+                        check += 1;
+
+                        // Once the blockhash expires no copy can land, so stop waiting on it
+                        let checked_enough = check >= checks;
+                        if checked_enough && !last_attempt {
+                            break;
+                        }
+                        if checked_enough || check % EXPIRY_CHECKS == 0 {
+                            if self.blockhash_expired(&txs[0]).await {
+                                break;
+                            }
+                            if checked_enough && sent_at.elapsed() > MAX_EXPIRY_WAIT {
+                                break;
+                            }
+                        }
+                        if policy.spam_interval.is_some() {
+                            self.control.heartbeat();
+                            self.rebroadcast(transport, &txs).await;
+                        }
+                    }
+                }
+
+                // Handle submit errors
+                Err(err) => {
+                    let message = match classify_send_error(&err) {
+                        SendFailure::BlockhashNotFound => {
+                            // Without resigning, an expired blockhash can never land
+                            if resign.is_none() && self.blockhash_expired(&txs[0]).await {
+                                progress_bar.finish_with_message(format!(
+                                    "{}: Blockhash expired",
+                                    "ERROR".bold().red()
+                                ));
+                                return Err(ClientError {
+                                    request: None,
+                                    kind: ClientErrorKind::Custom(BLOCKHASH_EXPIRED.into()),
+                                });
+                            }
+                            // The node is catching up, or the retry signs with a fresh hash
+                            delay = policy.retry_delay;
+                            "Blockhash not found".to_string()
+                        }
+                        SendFailure::AccountInUse => {
+                            // Locks clear within a slot or two, so no need to back off
+                            delay = policy.retry_delay;
+                            "Account in use".to_string()
+                        }
+                        SendFailure::NodeBehind(Some(slots)) => {
+                            format!("RPC node is {} slots behind", slots)
+                        }
+                        SendFailure::NodeBehind(None) => "RPC node is behind".to_string(),
+                        SendFailure::AlreadyProcessed | SendFailure::Other => {
+                            err.kind().to_string()
+                        }
+                    };
+                    progress_bar.set_message(format!("{}: {}", "ERROR".bold().red(), message));
+                }
+            }
+
+            // Retry
+            tokio::time::sleep(delay).await;
+            attempts += 1;
+            if attempts > policy.gateway_retries {
+                progress_bar.finish_with_message(format!("{}: Max retries", "ERROR".bold().red()));
+                return Err(ClientError {
+                    request: None,
+                    kind: ClientErrorKind::Custom(MAX_RETRIES.into()),
+                });
+            }
+
+            // Resending transactions whose blockhash expired can't help
+            if resign.is_none()
+                && attempts % EXPIRY_CHECKS == 0
+                && self.blockhash_expired(&txs[0]).await
+            {
+                progress_bar
+                    .finish_with_message(format!("{}: Blockhash expired", "ERROR".bold().red()));
+                return Err(ClientError {
+                    request: None,
+                    kind: ClientErrorKind::Custom(BLOCKHASH_EXPIRED.into()),
+                });
+            }
+
+            // A timed out fetch keeps the last blockhash, which is likely still valid
+            if let Some(resign) = resign {
+                let hash = match self.recent_blockhash().await {
+                    Ok(latest) => latest.hash,
+                    Err(_) => *txs[0].message.recent_blockhash(),
+                };
+                txs = resign(hash, attempts)?;
+            }
+        }
+    }
+
+    /// `sendTransaction` through the `--sender` endpoint when there is one, otherwise the RPC
+    /// with the configured preflight and `rpc_retries` node-side retries.
+    pub fn rpc_transport(&self, rpc_retries: usize) -> RpcTransport {
+        match &self.sender {
+            Some(sender) => RpcTransport {
+                client: sender.clone(),
+                config: DIRECT_SEND_CONFIG,
+            },
+            None => RpcTransport {
+                client: self.rpc_client(),
+                config: RpcSendTransactionConfig {
+                    skip_preflight: self.skip_preflight,
+                    preflight_commitment: Some(self.preflight_commitment()),
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    max_retries: Some(self.retry.rpc_retries(rpc_retries)),
+                    min_context_slot: None,
+                },
+            },
+        }
+    }
+
+    /// Sends `txs` again through the path and to every `--spam-rpc` at once. Failures are
+    /// ignored, since the confirmation checks decide the outcome.
+    async fn rebroadcast(&self, transport: &dyn Transport, txs: &[VersionedTransaction]) {
+        let extra: Vec<_> = self
+            .spam_rpcs
+            .iter()
+            .map(|client| RpcTransport {
+                client: client.clone(),
+                config: DIRECT_SEND_CONFIG,
+            })
+            .collect();
+        join_all(
+            std::iter::once(transport.send(txs))
+                .chain(extra.iter().map(|transport| transport.send(txs))),
+        )
+        .await;
+    }
+
+    /// Whether `tx`'s blockhash is too old for it to land. Judged by block height when the
+    /// blockhash came from the cache, otherwise by asking the RPC, assuming not if it can't say.
+    async fn blockhash_expired(&self, tx: &VersionedTransaction) -> bool {
+        let client = self.rpc_client();
+        let hash = tx.message.recent_blockhash();
+        match self.blockhash.last_valid_block_height(hash) {
+            Some(last_valid) => client
+                .get_block_height()
+                .await
+                .is_ok_and(|height| height > last_valid),
+            None => !client
+                .is_blockhash_valid(hash, CommitmentConfig::processed())
+                .await
+                .unwrap_or(true),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RequestPayload {
+    method: String,
+    params: serde_json::Value,
+    id: u64,
+    jsonrpc: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ResponseData {
+    jsonrpc: String,
+    result: String,
+    id: u64,
+}
+
+fn serialize_and_encode<T>(input: &T, encoding: UiTransactionEncoding) -> ClientResult<String>
+where
+    T: serde::ser::Serialize,
+{
+    let serialized = serialize(input)
+        .map_err(|e| ClientErrorKind::Custom(format!("Serialization failed: {e}")))?;
+    let encoded = match encoding {
+        UiTransactionEncoding::Base58 => bs58::encode(serialized).into_string(),
+        UiTransactionEncoding::Base64 => base64::prelude::BASE64_STANDARD.encode(serialized),
+        _ => {
+            return Err(ClientErrorKind::Custom(format!(
+                "unsupported encoding: {encoding}. Supported encodings: base58, base64"
+            ))
+            .into())
+        }
+    };
+    Ok(encoded)
+}
+
+async fn send_bundle(
+    http: &reqwest::Client,
+    transactions: &[VersionedTransaction],
+    jito_url: &str,
+    jito_uuid: Option<&str>,
+) -> ClientResult<()> {
+    let serialized_encoded = transactions
+        .iter()
+        .map(|tx| serialize_and_encode(tx, UiTransactionEncoding::Base58))
+        .collect::<ClientResult<Vec<_>>>()?;
+
+    let payload = RequestPayload {
+        method: "sendBundle".to_string(),
+        params: json!([serialized_encoded]),
+        id: 1,
+        jsonrpc: "2.0".to_string(),
+    };
+
+    // Authenticated requests get the UUID's rate limit instead of the shared one
+    let mut request = http.post(jito_url).json(&payload);
+    if let Some(uuid) = jito_uuid {
+        request = request.header(JITO_AUTH_HEADER, uuid);
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(err) if err.is_timeout() => {
+            return Err(ClientError {
+                request: None,
+                kind: ClientErrorKind::Custom("Timed out reaching jito".into()),
+            })
+        }
+        Err(err) => {
+            return Err(ClientError {
+                request: None,
+                kind: ClientErrorKind::Custom(format!("Failed to reach jito: {}", err)),
+            })
+        }
+    };
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(ClientError {
+            request: None,
+            kind: ClientErrorKind::Custom("Rate limited by jito, consider --jito-uuid".into()),
+        });
+    }
+    let bundle_id = match response.error_for_status() {
+        Ok(response) => match response.json::<ResponseData>().await {
+            Ok(response) => response.result,
+            Err(_) => {
+                return Err(ClientError {
+                    request: None,
+                    kind: ClientErrorKind::Custom("Failed to send jito transaction".into()),
+                })
+            }
+        },
+        Err(err) => {
+            return Err(ClientError {
+                request: None,
+                kind: ClientErrorKind::Custom(format!("Failed to send jito transaction: {}", err)),
+            })
+        }
+    };
+
+    println!("Jito Bundle: {}", bundle_id);
+    Ok(())
+}
+
+async fn send_transaction_with_config(
+    client: &RpcClient,
+    transaction: &impl SerializableTransaction,
+    config: RpcSendTransactionConfig,
+) -> ClientResult<Signature> {
+    let encoding = config.encoding.unwrap_or(UiTransactionEncoding::Base64);
+    let config = RpcSendTransactionConfig {
+        encoding: Some(encoding),
+        preflight_commitment: Some(config.preflight_commitment.unwrap_or_default()),
+        ..config
+    };
+    let serialized_encoded = serialize_and_encode(transaction, encoding)?;
+    let signature_base58_str: String = match client
+        .send(
+            RpcRequest::SendTransaction,
+            json!([serialized_encoded, config]),
+        )
+        .await
+    {
+        Ok(signature_base58_str) => signature_base58_str,
+        // Already landed is not a failure worth printing
+        Err(err) if classify_send_error(&err) == SendFailure::AlreadyProcessed => return Err(err),
+        Err(err) => {
+            if let ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                code,
+                message,
+                data,
+            }) = &err.kind
+            {
+                println!("{} {}", code, message);
+                if let RpcResponseErrorData::SendTransactionPreflightFailure(
+                    RpcSimulateTransactionResult { err, logs, .. },
+                ) = data
+                {
+                    // Explain ORE errors; anything else gets the raw logs
+                    match err.as_ref().and_then(explain_transaction_error) {
+                        Some(explanation) => println!("{} {}", "ERROR".bold().red(), explanation),
+                        None => {
+                            for (i, log) in logs.iter().flatten().enumerate() {
+                                println!("{:>3}: {}", i + 1, log);
+                            }
+                            println!();
+                        }
+                    }
+                }
+            }
+            return Err(err);
+        }
+    };
+
+    let signature = signature_base58_str
+        .parse::<Signature>()
+        .map_err(|err| Into::<ClientError>::into(RpcError::ParseError(err.to_string())))?;
+    if signature != *transaction.get_signature() {
+        Err(RpcError::RpcRequestError(format!(
+            "RPC node returned mismatched signature {:?}, expected {:?}",
+            signature,
+            transaction.get_signature()
+        ))
+        .into())
+    } else {
+        Ok(*transaction.get_signature())
+    }
+}
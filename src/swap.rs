@@ -0,0 +1,224 @@
+use std::str::FromStr;
+
+use base64::Engine;
+use colored::*;
+use ore_api::consts::MINT_ADDRESS;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    native_token::lamports_to_sol,
+    pubkey::Pubkey,
+};
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::{
+    args::SwapArgs,
+    exit::fail_with,
+    price::sol_usd,
+    utils::{amount_f64_to_u64, amount_u64_to_string, ask_confirm},
+    Miner,
+};
+
+pub const JUPITER_API_URL: &str = "https://quote-api.jup.ag/v6";
+
+const WRAPPED_SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JupiterAccount {
+    pubkey: String,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JupiterInstruction {
+    program_id: String,
+    accounts: Vec<JupiterAccount>,
+    data: String,
+}
+
+impl JupiterInstruction {
+    fn to_instruction(&self) -> Option<Instruction> {
+        let accounts = self
+            .accounts
+            .iter()
+            .map(|account| {
+                let pubkey = Pubkey::from_str(&account.pubkey).ok()?;
+                Some(match account.is_writable {
+                    true => AccountMeta::new(pubkey, account.is_signer),
+                    false => AccountMeta::new_readonly(pubkey, account.is_signer),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Instruction {
+            program_id: Pubkey::from_str(&self.program_id).ok()?,
+            accounts,
+            data: base64::prelude::BASE64_STANDARD.decode(&self.data).ok()?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SwapInstructions {
+    #[serde(default)]
+    compute_budget_instructions: Vec<JupiterInstruction>,
+    #[serde(default)]
+    setup_instructions: Vec<JupiterInstruction>,
+    swap_instruction: JupiterInstruction,
+    cleanup_instruction: Option<JupiterInstruction>,
+}
+
+impl Miner {
+    pub async fn swap(&self, args: SwapArgs) {
+        let signer = self.signer();
+        let amount = match args.amount {
+            Some(amount) => amount_f64_to_u64(amount),
+            None => self.ore_token_balance(signer.pubkey()).await,
+        };
+        if amount.eq(&0) {
+            println!("Nothing to swap");
+            return;
+        }
+        let Some(quote) = self
+            .jupiter_quote(&args.jupiter_url, amount, args.slippage_bps)
+            .await
+        else {
+            return;
+        };
+        if !ask_confirm(
+            format!(
+                "\nYou are about to swap {} for about {}.\n\nAre you sure you want to continue? [Y/n]",
+                format!("{} ORE", amount_u64_to_string(amount)).bold(),
+                format!("{} SOL", quoted_lamports(&quote).map(lamports_to_sol).unwrap_or(0.0)).bold(),
+            )
+            .as_str(),
+        ) {
+            return;
+        }
+        self.jupiter_swap(&args.jupiter_url, &signer, quote).await;
+    }
+
+    /// Swaps `amount` ORE from the signer's token account to SOL without asking.
+    pub async fn swap_ore_to_sol(
+        &self,
+        jupiter_url: &str,
+        signer: &Keypair,
+        amount: u64,
+        slippage_bps: u16,
+    ) -> Option<Signature> {
+        let quote = self
+            .jupiter_quote(jupiter_url, amount, slippage_bps)
+            .await?;
+        self.jupiter_swap(jupiter_url, signer, quote).await
+    }
+
+    pub async fn ore_token_balance(&self, wallet: Pubkey) -> u64 {
+        let token_account = get_associated_token_address(&wallet, &MINT_ADDRESS);
+        match self.rpc_client().get_token_account(&token_account).await {
+            Ok(Some(token_account)) => {
+                u64::from_str(&token_account.token_amount.amount).unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+
+    async fn jupiter_quote(
+        &self,
+        jupiter_url: &str,
+        amount: u64,
+        slippage_bps: u16,
+    ) -> Option<Value> {
+        // Legacy transactions keep the swap compatible with the regular send path
+        let url = format!(
+            "{}/quote?inputMint={}&outputMint={}&amount={}&slippageBps={}&asLegacyTransaction=true",
+            jupiter_url, MINT_ADDRESS, WRAPPED_SOL_MINT, amount, slippage_bps
+        );
+        let quote = match reqwest::get(url).await {
+            Ok(res) => res.json::<Value>().await.ok(),
+            Err(_) => None,
+        };
+        match quote {
+            Some(quote) if quote.get("error").is_none() => Some(quote),
+            Some(quote) => {
+                println!("{} Jupiter quote: {}", "ERROR".bold().red(), quote["error"]);
+                None
+            }
+            None => {
+                println!("{} Failed to fetch a Jupiter quote", "ERROR".bold().red());
+                None
+            }
+        }
+    }
+
+    async fn jupiter_swap(
+        &self,
+        jupiter_url: &str,
+        signer: &Keypair,
+        quote: Value,
+    ) -> Option<Signature> {
+        let out_lamports = quoted_lamports(&quote);
+        let body = json!({
+            "quoteResponse": quote,
+            "userPublicKey": signer.pubkey().to_string(),
+            "wrapAndUnwrapSol": true,
+            "asLegacyTransaction": true,
+            "computeUnitPriceMicroLamports": self.maintenance_fee(),
+        });
+        let res = reqwest::Client::new()
+            .post(format!("{}/swap-instructions", jupiter_url))
+            .json(&body)
+            .send()
+            .await;
+        let swap = match res {
+            Ok(res) => res.json::<SwapInstructions>().await.ok(),
+            Err(_) => None,
+        };
+        let Some(swap) = swap else {
+            println!("{} Failed to build the Jupiter swap", "ERROR".bold().red());
+            return None;
+        };
+        let Some(ixs) = swap
+            .compute_budget_instructions
+            .iter()
+            .chain(swap.setup_instructions.iter())
+            .chain(std::iter::once(&swap.swap_instruction))
+            .chain(swap.cleanup_instruction.iter())
+            .map(JupiterInstruction::to_instruction)
+            .collect::<Option<Vec<_>>>()
+        else {
+            println!(
+                "{} Jupiter returned a malformed instruction",
+                "ERROR".bold().red()
+            );
+            return None;
+        };
+
+        match self.send_and_confirm_with_key(&ixs, false, signer).await {
+            Ok(sig) => {
+                if let Some(lamports) = out_lamports {
+                    let sol = lamports_to_sol(lamports);
+                    println!(
+                        "Swapped for about {} SOL{}",
+                        sol,
+                        sol_usd(self.prices().await, sol)
+                    );
+                }
+                Some(sig)
+            }
+            Err(err) => {
+                println!("{} Swap failed: {}", "ERROR".bold().red(), err);
+                fail_with(&err);
+                None
+            }
+        }
+    }
+}
+
+fn quoted_lamports(quote: &Value) -> Option<u64> {
+    quote["outAmount"].as_str()?.parse().ok()
+}
@@ -0,0 +1,179 @@
+use std::{sync::Arc, time::Duration};
+
+use colored::*;
+
+use crate::{
+    args::GenerateUnitArgs,
+    control::Control,
+    exit::{abort, ExitCode},
+    Miner,
+};
+
+/// How long systemd waits for the current pass to finish after `systemctl stop`.
+const STOP_TIMEOUT_SECS: u64 = 180;
+
+/// How long systemd waits before restarting a miner that failed.
+const RESTART_SECS: u64 = 15;
+
+/// Sends a state update to systemd, as `sd_notify` does. Does nothing when not started by
+/// systemd with `Type=notify`.
+pub fn notify(state: &str) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::net::UnixDatagram;
+
+        let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+            return;
+        };
+        let Ok(socket) = UnixDatagram::unbound() else {
+            return;
+        };
+        let path = path.to_string_lossy().to_string();
+        let res = match path.strip_prefix('@') {
+            #[cfg(target_os = "linux")]
+            Some(name) => {
+                use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+                SocketAddr::from_abstract_name(name)
+                    .and_then(|addr| socket.send_to_addr(state.as_bytes(), &addr))
+            }
+            _ => socket.send_to(state.as_bytes(), &path),
+        };
+        if let Err(err) = res {
+            println!(
+                "{} Failed to notify systemd: {}",
+                "WARNING".bold().yellow(),
+                err
+            );
+        }
+    }
+}
+
+/// How often systemd expects to hear from this process, if it set a watchdog for it.
+fn watchdog_interval() -> Option<Duration> {
+    if let Ok(pid) = std::env::var("WATCHDOG_PID") {
+        if pid.ne(&std::process::id().to_string()) {
+            return None;
+        }
+    }
+    let usec: u64 = std::env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    Some(Duration::from_micros(usec))
+}
+
+/// Tells systemd the miner is up, then keeps its watchdog fed for as long as the mine loop
+/// makes progress, so a hung miner is restarted.
+pub fn start(control: Arc<Control>) {
+    notify("READY=1");
+    let Some(interval) = watchdog_interval() else {
+        return;
+    };
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval / 2).await;
+            if control.progressed_within(interval) {
+                notify("WATCHDOG=1");
+            }
+        }
+    });
+}
+
+/// Quotes an argument for a unit file's `ExecStart`, which has its own quoting and expands
+/// `%` specifiers and `$` variables.
+fn quote(arg: &str) -> String {
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    if escaped.is_empty() || escaped.contains(char::is_whitespace) || escaped.ne(arg) {
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    }
+}
+
+impl Miner {
+    /// Prints a service unit that mines with the global arguments given to this command and
+    /// the mine arguments after `--`, hardened to what mining needs.
+    pub async fn generate_unit(&self, args: GenerateUnitArgs) {
+        let exe = std::env::current_exe()
+            .unwrap_or_else(|err| abort(ExitCode::Failure, format!("Failed to find ore: {}", err)));
+        let dir = std::env::current_dir().unwrap_or_else(|err| {
+            abort(
+                ExitCode::Failure,
+                format!("Failed to read the working directory: {}", err),
+            )
+        });
+        let user = args
+            .user
+            .or_else(|| std::env::var("USER").ok())
+            .unwrap_or_else(|| abort(ExitCode::Config, "Pass --user to run the service as"));
+
+        // Everything before the subcommand is a global argument to keep
+        let mut command = vec![exe.to_string_lossy().to_string()];
+        command.extend(
+            std::env::args()
+                .skip(1)
+                .take_while(|arg| arg.ne("generate-unit")),
+        );
+        command.push("mine".to_string());
+        command.extend(args.mine_args.iter().cloned());
+        if !args.mine_args.iter().any(|arg| arg.eq("--systemd")) {
+            command.push("--systemd".to_string());
+        }
+        let exec_start = command
+            .iter()
+            .map(|arg| quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let dir = dir.display();
+
+        println!(
+            "[Unit]
+Description={description}
+Wants=network-online.target
+After=network-online.target
+
+[Service]
+Type=notify
+NotifyAccess=main
+User={user}
+WorkingDirectory={dir}
+ExecStart={exec_start}
+Restart=on-failure
+RestartSec={RESTART_SECS}
+# Bad arguments or keypairs won't fix themselves on a restart
+RestartPreventExitStatus=2
+WatchdogSec={watchdog}
+TimeoutStopSec={STOP_TIMEOUT_SECS}
+
+NoNewPrivileges=yes
+CapabilityBoundingSet=
+AmbientCapabilities=
+ProtectSystem=strict
+ProtectHome=read-only
+ReadWritePaths={dir}
+PrivateTmp=yes
+PrivateDevices=yes
+ProtectKernelTunables=yes
+ProtectKernelModules=yes
+ProtectKernelLogs=yes
+ProtectControlGroups=yes
+ProtectClock=yes
+ProtectHostname=yes
+RestrictNamespaces=yes
+RestrictRealtime=yes
+RestrictSUIDSGID=yes
+RestrictAddressFamilies=AF_UNIX AF_INET AF_INET6
+LockPersonality=yes
+# Hashing compiles its programs at runtime, so MemoryDenyWriteExecute stays off
+SystemCallArchitectures=native
+SystemCallFilter=@system-service
+UMask=0077
+
+[Install]
+WantedBy=multi-user.target",
+            description = args.description,
+            watchdog = args.watchdog_sec,
+        );
+    }
+}
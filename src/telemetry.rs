@@ -0,0 +1,121 @@
+use solana_sdk::{packet::PACKET_DATA_SIZE, transaction::VersionedTransaction};
+
+/// Base fee charged per transaction signature.
+const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+/// Shape of one transaction submitted in a bundle.
+pub struct TxSample {
+    pub bytes: usize,
+    pub signatures: usize,
+    pub instructions: usize,
+    pub cu_requested: u32,
+    pub cu_used: Option<u64>,
+}
+
+impl TxSample {
+    pub fn new(tx: &VersionedTransaction, cu_requested: u32) -> Self {
+        Self {
+            bytes: bincode::serialized_size(tx).unwrap_or(0) as usize,
+            signatures: tx.signatures.len(),
+            instructions: tx.message.instructions().len(),
+            cu_requested,
+            cu_used: None,
+        }
+    }
+}
+
+/// Packing statistics collected across bundles.
+#[derive(Default)]
+pub struct BundleTelemetry {
+    bundles: u64,
+    txs: Vec<TxSample>,
+    tips: u64,
+}
+
+impl BundleTelemetry {
+    pub fn record_bundle(&mut self, txs: Vec<TxSample>, tip: u64) {
+        self.bundles += 1;
+        self.tips += tip;
+        self.txs.extend(txs);
+    }
+
+    pub fn report(&self, priority_fee: u64) -> String {
+        if self.txs.is_empty() {
+            return "Packing report: no bundles submitted yet".to_string();
+        }
+        let tx_count = self.txs.len() as u64;
+        let bytes: usize = self.txs.iter().map(|tx| tx.bytes).sum();
+        let signatures: usize = self.txs.iter().map(|tx| tx.signatures).sum();
+        let fill = bytes as f64 / (tx_count as f64 * PACKET_DATA_SIZE as f64) * 100.0;
+        let sigs_per_tx = signatures as f64 / tx_count as f64;
+        let instructions: usize = self.txs.iter().map(|tx| tx.instructions).sum();
+        let ixs_per_tx = instructions as f64 / tx_count as f64;
+        let txs_per_bundle = tx_count as f64 / self.bundles as f64;
+
+        // Compute units, only for transactions we could look up after landing
+        let measured: Vec<&TxSample> = self.txs.iter().filter(|tx| tx.cu_used.is_some()).collect();
+        let cu_requested: u64 = measured.iter().map(|tx| tx.cu_requested as u64).sum();
+        let cu_used: u64 = measured.iter().map(|tx| tx.cu_used.unwrap()).sum();
+        let cu_efficiency = if cu_requested > 0 {
+            cu_used as f64 / cu_requested as f64 * 100.0
+        } else {
+            100.0
+        };
+
+        // Waste: unused CUs billed at the priority fee, and fee payer signatures on txs
+        // that would not exist with perfect packing.
+        let wasted_cu_fee = cu_requested.saturating_sub(cu_used) * priority_fee / 1_000_000;
+        let ideal_txs = (bytes as u64)
+            .div_ceil(PACKET_DATA_SIZE as u64)
+            .max(self.bundles);
+        let wasted_sig_fee = tx_count.saturating_sub(ideal_txs) * LAMPORTS_PER_SIGNATURE;
+
+        let mut report = format!(
+            "Packing report ({} bundles, {} txs)\n\
+             Avg tx size: {} / {} bytes ({:.1}% full)\n\
+             Avg signatures per tx: {:.2}\n\
+             Avg instructions per tx: {:.2}\n\
+             Avg txs per bundle: {:.2}\n\
+             CU used vs requested: {} / {} ({:.1}%)\n\
+             Tips paid: {} lamports\n\
+             Estimated waste: {} lamports unused CU fees, {} lamports extra tx fees",
+            self.bundles,
+            tx_count,
+            bytes as u64 / tx_count,
+            PACKET_DATA_SIZE,
+            fill,
+            sigs_per_tx,
+            ixs_per_tx,
+            txs_per_bundle,
+            cu_used,
+            cu_requested,
+            cu_efficiency,
+            self.tips,
+            wasted_cu_fee,
+            wasted_sig_fee,
+        );
+
+        let mut suggestions = vec![];
+        if fill < 60.0 && txs_per_bundle > 1.0 {
+            suggestions
+                .push("Transactions are mostly empty; pack more instructions per transaction");
+        }
+        if fill > 90.0 {
+            suggestions.push(
+                "Transactions are near the size limit; use address lookup tables to free space",
+            );
+        }
+        if sigs_per_tx > 3.0 {
+            suggestions
+                .push("Many signers per transaction; reduce signers per tx or use lookup tables");
+        }
+        if cu_efficiency < 50.0 {
+            suggestions
+                .push("Most requested compute units go unused; lower the compute unit limit");
+        }
+        for suggestion in suggestions {
+            report = format!("{}\n  - {}", report, suggestion);
+        }
+        report
+    }
+}
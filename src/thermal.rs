@@ -0,0 +1,113 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use colored::*;
+
+/// Drivers that report CPU package or core temperatures under hwmon.
+#[cfg(target_os = "linux")]
+const CPU_HWMON_DRIVERS: [&str; 5] = ["coretemp", "k10temp", "zenpower", "cpu_thermal", "acpitz"];
+
+/// How far below the ceiling the CPU must cool before threads are brought back.
+const HYSTERESIS: f64 = 5.0;
+
+/// How often the throttle checks the temperature.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The hottest CPU sensor in °C, if one can be read.
+#[cfg(target_os = "linux")]
+pub fn cpu_temperature() -> Option<f64> {
+    // Prefer the CPU's own sensors, falling back to the generic thermal zones
+    let hwmon = std::fs::read_dir("/sys/class/hwmon")
+        .ok()?
+        .flatten()
+        .filter(|entry| {
+            std::fs::read_to_string(entry.path().join("name"))
+                .map(|name| CPU_HWMON_DRIVERS.contains(&name.trim()))
+                .unwrap_or(false)
+        })
+        .flat_map(|entry| {
+            std::fs::read_dir(entry.path())
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter(|file| {
+                    let name = file.file_name().to_string_lossy().to_string();
+                    name.starts_with("temp") && name.ends_with("_input")
+                })
+                .filter_map(|file| read_millidegrees(&file.path()))
+        })
+        .fold(None, max_temp);
+    hwmon.or_else(|| {
+        std::fs::read_dir("/sys/class/thermal")
+            .ok()?
+            .flatten()
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("thermal_zone")
+            })
+            .filter_map(|entry| read_millidegrees(&entry.path().join("temp")))
+            .fold(None, max_temp)
+    })
+}
+
+/// The CPU die temperature in °C via the `osx-cpu-temp` tool, which reads the SMC.
+#[cfg(target_os = "macos")]
+pub fn cpu_temperature() -> Option<f64> {
+    let output = std::process::Command::new("osx-cpu-temp").output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    text.trim().trim_end_matches("°C").parse::<f64>().ok()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn cpu_temperature() -> Option<f64> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn read_millidegrees(path: &std::path::Path) -> Option<f64> {
+    let raw = std::fs::read_to_string(path).ok()?;
+    Some(raw.trim().parse::<f64>().ok()? / 1000.0)
+}
+
+#[cfg(target_os = "linux")]
+fn max_temp(max: Option<f64>, temp: f64) -> Option<f64> {
+    Some(max.map_or(temp, |max| max.max(temp)))
+}
+
+/// Watches the CPU temperature until `done`, halving the solver threads allowed to run
+/// while it is above `ceiling` and adding them back one at a time once it has cooled.
+pub fn spawn_throttle(ceiling: f64, threads: u64, active: Arc<AtomicU64>, done: Arc<AtomicBool>) {
+    if cpu_temperature().is_none() {
+        println!(
+            "{} Could not read the CPU temperature, --max-temp has no effect",
+            "WARNING".bold().yellow()
+        );
+        return;
+    }
+    std::thread::spawn(move || {
+        while !done.load(Ordering::Relaxed) {
+            if let Some(temp) = cpu_temperature() {
+                let current = active.load(Ordering::Relaxed);
+                if temp.ge(&ceiling) && current.gt(&0) {
+                    active.store(current / 2, Ordering::Relaxed);
+                    println!(
+                        "\n{} CPU at {:.0}°C, throttling to {} threads",
+                        "NOTICE".bold().cyan(),
+                        temp,
+                        current / 2
+                    );
+                } else if temp.le(&(ceiling - HYSTERESIS)) && current.lt(&threads) {
+                    active.store(current + 1, Ordering::Relaxed);
+                }
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Duration, Utc};
+use colored::*;
+use serde::Deserialize;
+use solana_program::native_token::{lamports_to_sol, sol_to_lamports};
+
+use crate::{args::TipStatsArgs, Miner};
+
+/// Tip percentiles of bundles landed in the last few minutes, as computed by Jito.
+const TIP_FLOOR_URL: &str = "https://bundles.jito.wtf/api/v1/bundles/tip_floor";
+
+/// Most recently landed bundles, newest first.
+const RECENT_BUNDLES_URL: &str = "https://bundles.jito.wtf/api/v1/bundles/recent";
+
+/// How many recent bundles to fetch when computing percentiles ourselves.
+const RECENT_BUNDLES_LIMIT: usize = 2000;
+
+const PERCENTILES: [u64; 5] = [25, 50, 75, 95, 99];
+
+#[derive(Deserialize)]
+pub struct TipFloor {
+    landed_tips_25th_percentile: f64,
+    pub landed_tips_50th_percentile: f64,
+    landed_tips_75th_percentile: f64,
+    landed_tips_95th_percentile: f64,
+    landed_tips_99th_percentile: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LandedBundle {
+    landed_tip_lamports: u64,
+    timestamp: String,
+}
+
+impl Miner {
+    pub async fn tip_stats(&self, args: TipStatsArgs) {
+        // Percentiles over the requested window, from the bundles themselves
+        let since = Utc::now() - Duration::minutes(args.minutes);
+        match recent_tips(since).await {
+            Some(mut tips) if !tips.is_empty() => {
+                tips.sort_unstable();
+                println!(
+                    "{} ({} bundles, last {} min)",
+                    "Landed tips".bold(),
+                    tips.len(),
+                    args.minutes
+                );
+                for p in PERCENTILES {
+                    let index = ((tips.len() - 1) as u64 * p / 100) as usize;
+                    print_tip(&format!("p{}", p), tips[index]);
+                }
+            }
+            _ => println!(
+                "{} No landed bundles found for the last {} min",
+                "WARNING".bold().yellow(),
+                args.minutes
+            ),
+        }
+
+        // Jito's own rolling floor, for comparison
+        let Some(floor) = tip_floor().await else {
+            println!(
+                "{} Failed to fetch the Jito tip floor",
+                "ERROR".bold().red()
+            );
+            return;
+        };
+        println!("\n{}", "Jito tip floor".bold());
+        for (p, sol) in PERCENTILES.iter().zip([
+            floor.landed_tips_25th_percentile,
+            floor.landed_tips_50th_percentile,
+            floor.landed_tips_75th_percentile,
+            floor.landed_tips_95th_percentile,
+            floor.landed_tips_99th_percentile,
+        ]) {
+            print_tip(&format!("p{}", p), sol_to_lamports(sol));
+        }
+        println!(
+            "\nPass the percentile you want to land at as --jito-tip <LAMPORTS>. Higher lands more often."
+        );
+    }
+}
+
+fn print_tip(label: &str, lamports: u64) {
+    println!(
+        "  {:>4}: {:>10} lamports ({:.9} SOL)",
+        label,
+        lamports,
+        lamports_to_sol(lamports)
+    );
+}
+
+pub async fn tip_floor() -> Option<TipFloor> {
+    reqwest::get(TIP_FLOOR_URL)
+        .await
+        .ok()?
+        .json::<Vec<TipFloor>>()
+        .await
+        .ok()?
+        .into_iter()
+        .next()
+}
+
+/// Tips of bundles landed since `since`.
+async fn recent_tips(since: DateTime<Utc>) -> Option<Vec<u64>> {
+    let bundles = reqwest::Client::new()
+        .get(RECENT_BUNDLES_URL)
+        .query(&[
+            ("limit", RECENT_BUNDLES_LIMIT.to_string()),
+            ("sort", "Time".to_string()),
+            ("asc", "false".to_string()),
+        ])
+        .send()
+        .await
+        .ok()?
+        .json::<Vec<LandedBundle>>()
+        .await
+        .ok()?;
+    Some(
+        bundles
+            .into_iter()
+            .filter(|bundle| {
+                DateTime::parse_from_rfc3339(&bundle.timestamp)
+                    .map(|time| time.with_timezone(&Utc) >= since)
+                    .unwrap_or(false)
+            })
+            .map(|bundle| bundle.landed_tip_lamports)
+            .collect(),
+    )
+}
@@ -0,0 +1,116 @@
+use std::{sync::Arc, time::Duration};
+
+use colored::*;
+use solana_cli_config::Config;
+use solana_client::{
+    client_error::Result as ClientResult, nonblocking::tpu_client::TpuClient,
+    tpu_client::TpuClientConfig,
+};
+use solana_program::instruction::Instruction;
+use solana_quic_client::{QuicConfig, QuicConnectionManager, QuicPool};
+use solana_sdk::signature::{Keypair, Signature, Signer};
+
+use crate::{
+    cu_limits::ComputeBudget,
+    send_and_confirm::compile_transaction,
+    submit::{Resign, TpuTransport},
+    Miner,
+};
+
+pub type QuicTpuClient = TpuClient<QuicPool, QuicConnectionManager, QuicConfig>;
+
+const TPU_RETRIES: usize = 100;
+const TPU_CONFIRM_RETRIES: usize = 1;
+const TPU_DELAY: u64 = 400;
+
+impl Miner {
+    /// Connects to the TPU ports of the current and upcoming leaders, following the leader
+    /// schedule over the RPC's websocket.
+    pub async fn tpu_client(&self) -> Option<Arc<QuicTpuClient>> {
+        let websocket_url = Config::compute_websocket_url(&self.rpc_client().url());
+        match TpuClient::new(
+            "ore-tpu",
+            self.rpc_client(),
+            &websocket_url,
+            TpuClientConfig::default(),
+        )
+        .await
+        {
+            Ok(client) => Some(Arc::new(client)),
+            Err(err) => {
+                println!(
+                    "{} Failed to connect TPU client via {}: {}",
+                    "ERROR".bold().red(),
+                    websocket_url,
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    /// Sends a transaction straight to the leaders over QUIC, resending until it lands or
+    /// the blockhash is no longer worth waiting on. The signer pays its own fees.
+    pub async fn send_and_confirm_tpu(
+        &self,
+        tpu: &QuicTpuClient,
+        ixs: &[Instruction],
+        signer: &Keypair,
+    ) -> ClientResult<Signature> {
+        // Size compute units from simulation, like the RPC path
+        let final_ixs = self
+            .with_compute_budget(
+                ixs,
+                &signer.pubkey(),
+                ComputeBudget::Dynamic,
+                self.mine_fee(&signer.pubkey()),
+            )
+            .await;
+
+        // Sign tx
+        let hash = self.recent_blockhash().await?.hash;
+        let lookup_tables = self.lookup_tables().await;
+        let tx = compile_transaction(
+            &signer.pubkey(),
+            &final_ixs,
+            &lookup_tables,
+            hash,
+            &[signer],
+        )?;
+
+        // Show what would happen instead of sending
+        if self.dry_run {
+            self.simulate_transaction(&tx, &[signer.pubkey()])
+                .await
+                .print("TPU transaction");
+            return Ok(Signature::default());
+        }
+
+        // Resend to the leaders until the signature shows up confirmed
+        let policy = self.retry.submit_policy(
+            TPU_RETRIES,
+            TPU_CONFIRM_RETRIES,
+            Duration::from_millis(TPU_DELAY),
+            Duration::ZERO,
+        );
+        let resign = |hash, attempt| {
+            compile_transaction(
+                &signer.pubkey(),
+                &self.retry.escalate(&final_ixs, attempt),
+                &lookup_tables,
+                hash,
+                &[signer],
+            )
+            .map(|tx| vec![tx])
+        };
+        self.submit(
+            &TpuTransport { tpu },
+            vec![tx],
+            self.retry.escalates().then_some(&resign as &Resign),
+            policy,
+            false,
+            None,
+        )
+        .await
+    }
+}
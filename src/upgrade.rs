@@ -3,8 +3,8 @@ use solana_sdk::{pubkey::Pubkey, signer::Signer};
 use spl_token::amount_to_ui_amount;
 
 use crate::{
-    cu_limits::CU_LIMIT_UPGRADE,
-    send_and_confirm::ComputeBudget,
+    cu_limits::{ComputeBudget, CU_LIMIT_UPGRADE},
+    exit::{abort, fail_with, ExitCode},
     utils::{amount_f64_to_u64_v1, ask_confirm},
     Miner, UpgradeArgs,
 };
@@ -12,8 +12,27 @@ use crate::{
 impl Miner {
     pub async fn upgrade(&self, args: UpgradeArgs) {
         let signer = &self.signer();
-        let beneficiary = self.get_or_initialize_ata().await;
-        let (sender, sender_balance) = self.get_ata_v1().await;
+
+        // Signing offline, so derive the token accounts rather than checking them
+        let (beneficiary, sender, sender_balance) = if args.durable.sign_only {
+            if args.amount.is_none() {
+                println!("--sign-only needs --amount, the balance can't be read offline");
+                return;
+            }
+            let beneficiary = spl_associated_token_account::get_associated_token_address(
+                &signer.pubkey(),
+                &ore_api::consts::MINT_ADDRESS,
+            );
+            let sender = spl_associated_token_account::get_associated_token_address(
+                &signer.pubkey(),
+                &ore_api::consts::MINT_V1_ADDRESS,
+            );
+            (beneficiary, sender, 0.0)
+        } else {
+            let beneficiary = self.get_or_initialize_ata().await;
+            let (sender, sender_balance) = self.get_ata_v1().await;
+            (beneficiary, sender, sender_balance)
+        };
 
         let amount_f64 = match args.amount {
             Some(f64) => f64,
@@ -40,12 +59,13 @@ impl Miner {
 
         let ix = ore_api::instruction::upgrade(signer.pubkey(), beneficiary, sender, amount);
         match self
-            .send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_UPGRADE), false)
+            .send_and_confirm_durable(&[ix], ComputeBudget::Fixed(CU_LIMIT_UPGRADE), &args.durable)
             .await
         {
             Ok(_sig) => {}
             Err(err) => {
                 println!("error: {}", err);
+                fail_with(&err);
             }
         }
     }
@@ -54,7 +74,7 @@ impl Miner {
     async fn get_ata_v1(&self) -> (Pubkey, f64) {
         // Initialize client.
         let signer = self.signer();
-        let client = self.rpc_client.clone();
+        let client = self.rpc_client();
 
         // Derive assoicated token address (for v1 account)
         let token_account_pubkey_v1 = spl_associated_token_account::get_associated_token_address(
@@ -64,21 +84,21 @@ impl Miner {
 
         // Get token account balance
         let balance = match client.get_token_account(&token_account_pubkey_v1).await {
-            Ok(None) => {
-                panic!("v1 token account doesn't exist")
-            }
+            Ok(None) => abort(ExitCode::Failure, "v1 token account doesn't exist"),
             Ok(Some(token_account)) => match token_account.token_amount.ui_amount {
                 Some(ui_amount) => ui_amount,
-                None => {
-                    panic!(
+                None => abort(
+                    ExitCode::Failure,
+                    format!(
                         "Error parsing token account UI amount: {}",
                         token_account.token_amount.amount
-                    )
-                }
+                    ),
+                ),
             },
-            Err(err) => {
-                panic!("Error fetching token account: {}", err)
-            }
+            Err(err) => abort(
+                ExitCode::from(&err),
+                format!("Error fetching token account: {}", err),
+            ),
         };
 
         // Return v1 token account address
@@ -88,7 +108,7 @@ impl Miner {
     async fn get_or_initialize_ata(&self) -> Pubkey {
         // Initialize client
         let signer = self.signer();
-        let client = self.rpc_client.clone();
+        let client = self.rpc_client();
 
         // Derive assoicated token address (ata)
         let token_account_pubkey = spl_associated_token_account::get_associated_token_address(
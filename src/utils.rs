@@ -1,32 +1,41 @@
-use std::io::Read;
+use std::{io::Read, str::FromStr, time::Duration};
 
 use cached::proc_macro::cached;
 use ore_api::{
     consts::{
-        CONFIG_ADDRESS, MINT_ADDRESS, PROOF, TOKEN_DECIMALS, TOKEN_DECIMALS_V1, TREASURY_ADDRESS,
+        BUS_ADDRESSES, CONFIG_ADDRESS, MINT_ADDRESS, PROOF, TOKEN_DECIMALS, TOKEN_DECIMALS_V1,
+        TREASURY_ADDRESS,
     },
-    state::{Config, Proof, Treasury},
+    state::{Bus, Config, Proof, Treasury},
 };
 use ore_utils::AccountDeserialize;
+use reqwest::header::{HeaderName, HeaderValue};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_program::{pubkey::Pubkey, sysvar};
-use solana_sdk::clock::Clock;
+use solana_sdk::{account::Account, clock::Clock};
 use spl_associated_token_account::get_associated_token_address;
 
+use crate::{
+    account_stream::account_cache,
+    args::ClaimAmount,
+    exit::{abort, fail, ExitCode},
+};
+
 pub async fn _get_treasury(client: &RpcClient) -> Treasury {
     let data = client
         .get_account_data(&TREASURY_ADDRESS)
         .await
-        .expect("Failed to get treasury account");
+        .unwrap_or_else(|err| abort(ExitCode::from(&err), "Failed to get treasury account"));
     *Treasury::try_from_bytes(&data).expect("Failed to parse treasury account")
 }
 
 pub async fn get_config(client: &RpcClient) -> Config {
-    let data = client
-        .get_account_data(&CONFIG_ADDRESS)
+    let account = get_accounts(client, &[CONFIG_ADDRESS])
         .await
+        .pop()
+        .flatten()
         .expect("Failed to get config account");
-    *Config::try_from_bytes(&data).expect("Failed to parse config account")
+    *Config::try_from_bytes(&account.data).expect("Failed to parse config account")
 }
 
 pub async fn get_proof_with_authority(client: &RpcClient, authority: Pubkey) -> Proof {
@@ -38,15 +47,68 @@ pub async fn get_proof(client: &RpcClient, address: Pubkey) -> Proof {
     let data = client
         .get_account_data(&address)
         .await
-        .expect("Failed to get miner account");
+        .unwrap_or_else(|err| abort(ExitCode::from(&err), "Failed to get miner account"));
     *Proof::try_from_bytes(&data).expect("Failed to parse miner account")
 }
 
+/// Most accounts a single `getMultipleAccounts` call returns.
+const MULTIPLE_ACCOUNTS_LIMIT: usize = 100;
+
+/// Fetches many accounts in as few round trips as possible. Missing accounts are `None`.
+/// Served from the account stream instead when it holds all of them.
+pub async fn get_accounts(client: &RpcClient, addresses: &[Pubkey]) -> Vec<Option<Account>> {
+    if let Some(accounts) = account_cache().and_then(|cache| cache.get_all(addresses)) {
+        return accounts;
+    }
+    let mut accounts = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MULTIPLE_ACCOUNTS_LIMIT) {
+        let fetched = client
+            .get_multiple_accounts(chunk)
+            .await
+            .unwrap_or_else(|err| abort(ExitCode::from(&err), "Failed to get accounts"));
+        accounts.extend(fetched);
+    }
+    accounts
+}
+
+pub async fn get_busses(client: &RpcClient) -> Vec<Bus> {
+    get_accounts(client, &BUS_ADDRESSES)
+        .await
+        .into_iter()
+        .flatten()
+        .filter_map(|account| Bus::try_from_bytes(&account.data).ok().copied())
+        .collect()
+}
+
+/// Proofs of each authority, in order; `None` where no proof is open.
+pub async fn get_proofs_with_authorities(
+    client: &RpcClient,
+    authorities: &[Pubkey],
+) -> Vec<Option<Proof>> {
+    let addresses: Vec<_> = authorities.iter().map(|a| proof_pubkey(*a)).collect();
+    get_accounts(client, &addresses)
+        .await
+        .into_iter()
+        .map(|account| {
+            account.and_then(|account| Proof::try_from_bytes(&account.data).ok().copied())
+        })
+        .collect()
+}
+
+/// SOL balances of each address in lamports, in order.
+pub async fn get_balances(client: &RpcClient, addresses: &[Pubkey]) -> Vec<u64> {
+    get_accounts(client, addresses)
+        .await
+        .into_iter()
+        .map(|account| account.map(|account| account.lamports).unwrap_or(0))
+        .collect()
+}
+
 pub async fn get_clock(client: &RpcClient) -> Clock {
     let data = client
         .get_account_data(&sysvar::clock::ID)
         .await
-        .expect("Failed to get miner account");
+        .unwrap_or_else(|err| abort(ExitCode::from(&err), "Failed to get clock"));
     bincode::deserialize::<Clock>(&data).expect("Failed to deserialize clock")
 }
 
@@ -70,15 +132,110 @@ pub fn ask_confirm(question: &str) -> bool {
     println!("{}", question);
     loop {
         let mut input = [0];
-        let _ = std::io::stdin().read(&mut input);
+        // Nothing left to read, such as after keys were piped in on stdin
+        if std::io::stdin().read(&mut input).unwrap_or(0) == 0 {
+            println!("No answer on stdin, not continuing.");
+            fail(ExitCode::UserAbort);
+            return false;
+        }
         match input[0] as char {
             'y' | 'Y' => return true,
-            'n' | 'N' => return false,
+            'n' | 'N' => {
+                fail(ExitCode::UserAbort);
+                return false;
+            }
             _ => println!("y/n only please."),
         }
     }
 }
 
+/// Prints `value` as pretty JSON, for `--json` output.
+pub fn print_json(value: &serde_json::Value) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap());
+}
+
+/// Parses a claim amount, either in ORE like `1.5` or as a share of the balance like `50%`.
+pub fn parse_claim_amount(input: &str) -> Result<ClaimAmount, String> {
+    let input = input.trim();
+    if let Some(percent) = input.strip_suffix('%') {
+        let percent = percent
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| format!("expected a percentage in `{}`", input))?;
+        if !(percent > 0.0 && percent <= 100.0) {
+            return Err(format!(
+                "percentage must be above 0 and at most 100, got `{}`",
+                input
+            ));
+        }
+        return Ok(ClaimAmount::Percent(percent));
+    }
+    input
+        .parse::<f64>()
+        .map(ClaimAmount::Ore)
+        .map_err(|_| format!("expected an ORE amount or a percentage, got `{}`", input))
+}
+
+/// Parses an HTTP header given as `Name: value`, like `x-api-key: abc123`.
+pub fn parse_header(input: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (name, value) = input
+        .split_once(':')
+        .ok_or_else(|| format!("expected `Name: value`, got `{}`", input))?;
+    let name = HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|_| format!("invalid header name `{}`", name.trim()))?;
+    let value = HeaderValue::from_str(value.trim())
+        .map_err(|_| format!("invalid value for header `{}`", name))?;
+    Ok((name, value))
+}
+
+/// Parses a wallet's priority fee scale given as `ADDRESS=FACTOR`, like `7xKX...=1.5`.
+pub fn parse_wallet_fee_scale(input: &str) -> Result<(Pubkey, f64), String> {
+    let (address, factor) = input
+        .split_once('=')
+        .ok_or_else(|| format!("expected `ADDRESS=FACTOR`, got `{}`", input))?;
+    let address = Pubkey::from_str(address.trim())
+        .map_err(|_| format!("invalid wallet address `{}`", address.trim()))?;
+    let factor = factor
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|factor| factor.is_finite() && *factor >= 0.0)
+        .ok_or_else(|| format!("invalid fee scale `{}`", factor.trim()))?;
+    Ok((address, factor))
+}
+
+/// Parses a duration like `90`, `45s`, `30m`, `4h`, `1d` or `1h30m`. Bare numbers are seconds.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    if let Ok(secs) = input.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3_600,
+            'd' => 86_400,
+            _ => return Err(format!("unknown unit `{}` in `{}`", c, input)),
+        };
+        let value = digits
+            .parse::<u64>()
+            .map_err(|_| format!("expected a number before `{}` in `{}`", c, input))?;
+        total = total.saturating_add(value.saturating_mul(unit));
+        digits.clear();
+    }
+    if !digits.is_empty() {
+        return Err(format!("missing unit after `{}` in `{}`", digits, input));
+    }
+    Ok(Duration::from_secs(total))
+}
+
 #[cached]
 pub fn proof_pubkey(authority: Pubkey) -> Pubkey {
     Pubkey::find_program_address(&[PROOF, authority.as_ref()], &ore_api::ID).0
@@ -0,0 +1,213 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{OnceLock, RwLock},
+};
+
+use serde_json::Value;
+use solana_sdk::signature::Keypair;
+
+use crate::keys::parse_keypair;
+
+/// Prefix of keypair sources read from Vault, as in `vault:ore/payer`.
+pub const PREFIX: &str = "vault:";
+
+/// Secret names under a folder path, with the keypair bytes each holds.
+type FolderEntries = Vec<(String, Vec<u8>)>;
+
+/// The Vault connection, if any keypair source points at it.
+static VAULT: OnceLock<Vault> = OnceLock::new();
+
+/// Keypairs fetched from a HashiCorp Vault KV v2 engine. They are only ever held in memory,
+/// and fetched again on a wallet reload to pick up rotated keys.
+pub struct Vault {
+    addr: String,
+    token: String,
+    mount: String,
+    field: String,
+    http: reqwest::Client,
+    /// Secret paths read as one keypair each, like `--keypair vault:ore/miner`
+    key_paths: Vec<String>,
+    /// Paths whose secrets are each a wallet, like `--folder-path vault:ore/miners`
+    folder_paths: Vec<String>,
+    keys: RwLock<HashMap<String, Vec<u8>>>,
+    folders: RwLock<HashMap<String, FolderEntries>>,
+}
+
+/// Connects to Vault and fetches the keypairs the given sources name. Sources not starting
+/// with `vault:` are left alone.
+pub async fn connect(
+    addr: String,
+    token: String,
+    mount: String,
+    field: String,
+    key_sources: &[Option<&str>],
+    folder_source: Option<&str>,
+) -> Result<(), String> {
+    let vault = Vault {
+        addr: addr.trim_end_matches('/').to_string(),
+        token,
+        mount: mount.trim_matches('/').to_string(),
+        field,
+        http: reqwest::Client::new(),
+        key_paths: key_sources
+            .iter()
+            .filter_map(vault_path)
+            .map(str::to_string)
+            .collect(),
+        folder_paths: vault_path(&folder_source)
+            .map(str::to_string)
+            .into_iter()
+            .collect(),
+        keys: RwLock::new(HashMap::new()),
+        folders: RwLock::new(HashMap::new()),
+    };
+    vault.fetch().await?;
+    VAULT.set(vault).ok();
+    Ok(())
+}
+
+fn vault_path<'a>(source: &Option<&'a str>) -> Option<&'a str> {
+    source.and_then(|source| source.strip_prefix(PREFIX))
+}
+
+/// Fetches every keypair again, keeping the ones already held if Vault can't be read.
+pub async fn refresh() -> Result<(), String> {
+    match VAULT.get() {
+        Some(vault) => vault.fetch().await,
+        None => Ok(()),
+    }
+}
+
+/// The keypair at `path`, as fetched at startup or the last refresh.
+pub fn keypair(path: &str) -> Result<Keypair, String> {
+    let vault = VAULT
+        .get()
+        .ok_or_else(|| format!("{}{}: pass --vault-addr", PREFIX, path))?;
+    let keys = vault.keys.read().unwrap();
+    let bytes = keys
+        .get(path)
+        .ok_or_else(|| format!("{}{}: not fetched", PREFIX, path))?;
+    Keypair::from_bytes(bytes).map_err(|err| format!("{}{}: {}", PREFIX, path, err))
+}
+
+/// The wallets under `path`, named after their secrets, for `--folder-path vault:<path>`.
+pub fn folder_keypairs(
+    path: &str,
+    keypairs: &mut Vec<(PathBuf, Keypair)>,
+    errors: &mut Vec<String>,
+) {
+    let Some(vault) = VAULT.get() else {
+        errors.push(format!("{}{}: pass --vault-addr", PREFIX, path));
+        return;
+    };
+    let folders = vault.folders.read().unwrap();
+    let Some(entries) = folders.get(path) else {
+        errors.push(format!("{}{}: not fetched", PREFIX, path));
+        return;
+    };
+    for (name, bytes) in entries {
+        match Keypair::from_bytes(bytes) {
+            Ok(keypair) => keypairs.push((PathBuf::from(name), keypair)),
+            Err(err) => errors.push(format!("{}{}/{}: {}", PREFIX, path, name, err)),
+        }
+    }
+}
+
+impl Vault {
+    async fn fetch(&self) -> Result<(), String> {
+        let mut keys = HashMap::new();
+        for path in self.key_paths.iter() {
+            keys.insert(path.clone(), self.read_keypair(path).await?);
+        }
+        let mut folders = HashMap::new();
+        for path in self.folder_paths.iter() {
+            let mut entries = Vec::new();
+            for name in self.list(path).await? {
+                let bytes = self.read_keypair(&format!("{}/{}", path, name)).await?;
+                entries.push((name, bytes));
+            }
+            folders.insert(path.clone(), entries);
+        }
+        *self.keys.write().unwrap() = keys;
+        *self.folders.write().unwrap() = folders;
+        Ok(())
+    }
+
+    /// Reads the keypair held in the secret's `--vault-field`.
+    async fn read_keypair(&self, path: &str) -> Result<Vec<u8>, String> {
+        let url = format!("{}/v1/{}/data/{}", self.addr, self.mount, path);
+        let body = self
+            .get(&url)
+            .await
+            .map_err(|err| format!("{}{}: {}", PREFIX, path, err))?;
+        let text = match &body["data"]["data"][&self.field] {
+            Value::String(text) => text.clone(),
+            Value::Null => {
+                return Err(format!(
+                    "{}{}: no `{}` field in the secret",
+                    PREFIX, path, self.field
+                ))
+            }
+            value => value.to_string(),
+        };
+        parse_keypair(&text)
+            .map(|keypair| keypair.to_bytes().to_vec())
+            .map_err(|err| format!("{}{}: {}", PREFIX, path, err))
+    }
+
+    /// The secrets directly under `path`, in name order.
+    async fn list(&self, path: &str) -> Result<Vec<String>, String> {
+        let url = format!(
+            "{}/v1/{}/metadata/{}?list=true",
+            self.addr, self.mount, path
+        );
+        let body = self
+            .get(&url)
+            .await
+            .map_err(|err| format!("{}{}: {}", PREFIX, path, err))?;
+        let mut names: Vec<String> = body["data"]["keys"]
+            .as_array()
+            .map(|keys| {
+                keys.iter()
+                    .filter_map(|key| key.as_str())
+                    // Nested folders end in a slash
+                    .filter(|key| !key.ends_with('/'))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        Ok(names)
+    }
+
+    async fn get(&self, url: &str) -> Result<Value, String> {
+        let res = self
+            .http
+            .get(url)
+            .header("X-Vault-Token", &self.token)
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        let status = res.status();
+        let text = res.text().await.map_err(|err| err.to_string())?;
+        let body = serde_json::from_str::<Value>(&text);
+        if !status.is_success() {
+            // Proxies and load balancers answer with HTML or nothing at all
+            let errors = body
+                .ok()
+                .and_then(|body| {
+                    body["errors"].as_array().map(|errors| {
+                        errors
+                            .iter()
+                            .filter_map(|err| err.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    })
+                })
+                .unwrap_or_else(|| text.trim().to_string());
+            return Err(format!("Vault returned {} {}", status, errors));
+        }
+        body.map_err(|err| format!("Vault returned invalid JSON: {}", err))
+    }
+}
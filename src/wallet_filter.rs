@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+
+use colored::*;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+use crate::labels::label;
+
+/// Narrows the folder wallets to `--only` and drops `--exclude`. A pattern matches a
+/// wallet's address, or its file name or label with `*` and `?` wildcards.
+#[derive(Clone, Debug, Default)]
+pub struct WalletFilter {
+    pub only: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl WalletFilter {
+    pub fn is_empty(&self) -> bool {
+        self.only.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Keeps the wallets the filter lets through, warning about `--only` patterns that match
+    /// none of them, which are usually typos.
+    pub fn apply(&self, keypairs: Vec<(PathBuf, Keypair)>) -> Vec<(PathBuf, Keypair)> {
+        for pattern in self.only.iter() {
+            if !keypairs
+                .iter()
+                .any(|(path, keypair)| matches(pattern, path, &keypair.pubkey()))
+            {
+                println!(
+                    "{} --only {} matches no wallet in the folder",
+                    "WARNING".bold().yellow(),
+                    pattern
+                );
+            }
+        }
+        keypairs
+            .into_iter()
+            .filter(|(path, keypair)| self.includes(path, &keypair.pubkey()))
+            .collect()
+    }
+
+    pub fn includes(&self, path: &Path, wallet: &Pubkey) -> bool {
+        let only = self.only.is_empty()
+            || self
+                .only
+                .iter()
+                .any(|pattern| matches(pattern, path, wallet));
+        only && !self
+            .exclude
+            .iter()
+            .any(|pattern| matches(pattern, path, wallet))
+    }
+}
+
+fn matches(pattern: &str, path: &Path, wallet: &Pubkey) -> bool {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    pattern.eq(&wallet.to_string()) || glob(pattern, &file_name) || glob(pattern, &label(wallet))
+}
+
+/// Matches `text` against a pattern where `*` is any run of characters and `?` any one.
+fn glob(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Where the last `*` was, and how much of the text it has taken so far
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(c) if c.eq(&'?') || c.eq(&text[t]) => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Let the last `*` take one more character and try again
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| c.eq(&'*'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(only: &[&str], exclude: &[&str]) -> WalletFilter {
+        WalletFilter {
+            only: only.iter().map(|pattern| pattern.to_string()).collect(),
+            exclude: exclude.iter().map(|pattern| pattern.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob("*", ""));
+        assert!(glob("*", "miner-1.json"));
+        assert!(glob("miner-*.json", "miner-12.json"));
+        assert!(glob("miner-?.json", "miner-1.json"));
+        assert!(!glob("miner-?.json", "miner-12.json"));
+        assert!(glob("*-1*", "miner-1-backup.json"));
+        assert!(glob("a*b*c", "aXbYbZc"));
+        assert!(!glob("a*b*c", "aXbYbZ"));
+        assert!(!glob("miner", "miner.json"));
+    }
+
+    #[test]
+    fn matches_address_or_file_name() {
+        let wallet = Pubkey::new_unique();
+        let path = Path::new("/keys/miner-1.json");
+        assert!(matches(&wallet.to_string(), path, &wallet));
+        assert!(matches("miner-*", path, &wallet));
+        assert!(!matches("keys", path, &wallet));
+        assert!(!matches(&Pubkey::new_unique().to_string(), path, &wallet));
+    }
+
+    #[test]
+    fn includes_only_and_drops_excluded() {
+        let wallet = Pubkey::new_unique();
+        let first = Path::new("miner-1.json");
+        let second = Path::new("miner-2.json");
+
+        assert!(filter(&[], &[]).includes(first, &wallet));
+        assert!(filter(&["miner-1*"], &[]).includes(first, &wallet));
+        assert!(!filter(&["miner-1*"], &[]).includes(second, &wallet));
+        assert!(!filter(&[], &["*-2.json"]).includes(second, &wallet));
+        assert!(!filter(&["miner-*"], &["miner-1.json"]).includes(first, &wallet));
+        assert!(filter(&["miner-*"], &["miner-1.json"]).includes(second, &wallet));
+    }
+}
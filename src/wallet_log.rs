@@ -0,0 +1,86 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use colored::*;
+use solana_sdk::pubkey::Pubkey;
+
+/// Rotated files kept per wallet, as `<pubkey>.log.1` through `<pubkey>.log.N`.
+const ROTATED_FILES: usize = 3;
+
+/// Appends each wallet's mining events to its own `<pubkey>.log` under `--log-dir`, so one
+/// misbehaving wallet can be followed without the rest of the fleet in the way.
+pub struct WalletLogs {
+    dir: PathBuf,
+    max_bytes: u64,
+    warned: AtomicBool,
+}
+
+impl WalletLogs {
+    pub fn new(dir: &str, max_mb: u64) -> Self {
+        if let Err(err) = std::fs::create_dir_all(dir) {
+            println!(
+                "{} Failed to create log directory {}: {}",
+                "WARNING".bold().yellow(),
+                dir,
+                err
+            );
+        }
+        Self {
+            dir: dir.into(),
+            max_bytes: max_mb * 1024 * 1024,
+            warned: AtomicBool::new(false),
+        }
+    }
+
+    /// Appends a timestamped event to the wallet's log, rotating it once it is full.
+    pub fn log(&self, wallet: &Pubkey, event: impl AsRef<str>) {
+        let line = format!(
+            "{} {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            event.as_ref()
+        );
+        if let Err(err) = self.append(wallet, &line) {
+            // Once is enough, the same problem would repeat for every event
+            if !self.warned.swap(true, Ordering::Relaxed) {
+                println!(
+                    "{} Failed to write wallet log for {}: {}",
+                    "WARNING".bold().yellow(),
+                    wallet,
+                    err
+                );
+            }
+        }
+    }
+
+    fn append(&self, wallet: &Pubkey, line: &str) -> std::io::Result<()> {
+        let path = self.dir.join(format!("{}.log", wallet));
+        let len = std::fs::metadata(&path).map_or(0, |metadata| metadata.len());
+        if len > 0 && len + line.len() as u64 > self.max_bytes {
+            self.rotate(wallet)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?
+            .write_all(line.as_bytes())
+    }
+
+    /// Shifts `<pubkey>.log` to `.log.1`, `.log.1` to `.log.2` and so on, dropping the oldest.
+    fn rotate(&self, wallet: &Pubkey) -> std::io::Result<()> {
+        let path = |index: usize| match index {
+            0 => self.dir.join(format!("{}.log", wallet)),
+            _ => self.dir.join(format!("{}.log.{}", wallet, index)),
+        };
+        std::fs::remove_file(path(ROTATED_FILES)).ok();
+        for index in (0..ROTATED_FILES).rev() {
+            if path(index).exists() {
+                std::fs::rename(path(index), path(index + 1))?;
+            }
+        }
+        Ok(())
+    }
+}
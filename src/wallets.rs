@@ -0,0 +1,589 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use colored::*;
+use ore_api::consts::MINT_ADDRESS;
+use serde::{Deserialize, Serialize};
+use solana_client::client_error::Result as ClientResult;
+use solana_program::{native_token::sol_to_lamports, system_instruction};
+use solana_sdk::signature::{write_keypair_file, Keypair, Signature, Signer};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+
+use crate::{
+    args::{RotateArgs, RotateAuthorityArgs, RotateStep, WalletsArgs, WalletsCommand},
+    exit::{abort, fail_with, ExitCode},
+    keys::{read_keypair, read_keypair_file, STDIN},
+    utils::{ask_confirm, get_proof_with_authority},
+    Miner,
+};
+
+/// Rotation progress, kept in the keypairs folder until the rotation finishes.
+pub const ROTATION_STATE_FILE: &str = "rotation.json";
+
+const STEP_GENERATE: &str = "Generate replacement keypair";
+const STEP_OPEN: &str = "Fund replacement and open its proof";
+const STEP_MIGRATE: &str = "Move stake and tokens, close old proof";
+const STEP_SWEEP: &str = "Sweep remaining SOL";
+const STEP_ARCHIVE: &str = "Archive old keypair";
+const STEPS: [&str; 5] = [
+    STEP_GENERATE,
+    STEP_OPEN,
+    STEP_MIGRATE,
+    STEP_SWEEP,
+    STEP_ARCHIVE,
+];
+
+#[derive(Serialize, Deserialize)]
+struct ChecklistItem {
+    name: String,
+    done: bool,
+    signature: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RotationPair {
+    old_path: String,
+    old_pubkey: String,
+    new_path: String,
+    new_pubkey: String,
+    steps: Vec<ChecklistItem>,
+}
+
+impl RotationPair {
+    fn is_done(&self, name: &str) -> bool {
+        self.steps
+            .iter()
+            .any(|step| step.name.eq(name) && step.done)
+    }
+
+    fn check(&mut self, name: &str, done: bool, signature: Option<String>) {
+        self.steps.retain(|step| step.name.ne(name));
+        self.steps.push(ChecklistItem {
+            name: name.to_string(),
+            done,
+            signature,
+        });
+    }
+
+    fn check_result(&mut self, name: &str, res: ClientResult<Signature>) -> bool {
+        match res {
+            Ok(sig) => {
+                self.check(name, true, Some(sig.to_string()));
+                true
+            }
+            Err(err) => {
+                println!("{} {}: {}", "ERROR".bold().red(), name, err);
+                fail_with(&err);
+                self.check(name, false, None);
+                false
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct RotationState {
+    started_at: i64,
+    overlap_hours: u64,
+    pairs: Vec<RotationPair>,
+}
+
+impl RotationState {
+    fn read(path: &Path) -> Option<Self> {
+        let data = std::fs::read(path).ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    fn write(&self, path: &Path) {
+        let json = serde_json::to_string_pretty(self).expect("Failed to serialize rotation state");
+        std::fs::write(path, json).expect("Failed to write rotation state");
+    }
+
+    fn print(&self) {
+        for pair in self.pairs.iter() {
+            println!("\n{} -> {}", pair.old_pubkey, pair.new_pubkey.bold());
+            for name in STEPS {
+                let step = pair.steps.iter().find(|step| step.name.eq(name));
+                let mark = match step {
+                    Some(step) if step.done => "[x]".green(),
+                    Some(_) => "[!]".red(),
+                    None => "[ ]".normal(),
+                };
+                let signature = step
+                    .and_then(|step| step.signature.clone())
+                    .unwrap_or_default();
+                println!("  {} {} {}", mark, name, signature.dimmed());
+            }
+        }
+    }
+}
+
+impl Miner {
+    pub async fn wallets(&self, args: WalletsArgs) {
+        match args.command {
+            WalletsCommand::Rotate(args) => match args.step {
+                RotateStep::Start => {
+                    self.rotate_start(args).await;
+                }
+                RotateStep::Status => self.rotate_status(),
+                RotateStep::Finish => self.rotate_finish(args).await,
+            },
+        }
+    }
+
+    fn rotation_state_path(&self) -> PathBuf {
+        let folder_path = self.folder_path.as_ref().expect("No folder path provided");
+        // Rotating writes new keypairs next to the old ones
+        if folder_path.eq(STDIN) {
+            abort(ExitCode::Config, "Rotating needs a keypair folder on disk");
+        }
+        Path::new(folder_path).join(ROTATION_STATE_FILE)
+    }
+
+    /// Starts a folder rotation. Returns whether one was started.
+    async fn rotate_start(&self, args: RotateArgs) -> bool {
+        let state_path = self.rotation_state_path();
+        if state_path.exists() {
+            println!("A rotation is already in progress. See `ore wallets rotate status`.");
+            return false;
+        }
+        let old_signers = self.multi_signer_files();
+        if !ask_confirm(
+            format!(
+                "\nYou are about to generate {} replacement keypairs, each funded with {} SOL by the key it replaces.\n\nAre you sure you want to continue? [Y/n]",
+                old_signers.len(),
+                args.fund
+            )
+            .as_str(),
+        ) {
+            return false;
+        }
+
+        let mut state = RotationState {
+            started_at: chrono::Utc::now().timestamp(),
+            overlap_hours: args.overlap_hours,
+            pairs: vec![],
+        };
+        for (old_path, old_signer) in old_signers.iter() {
+            let new_signer = Keypair::new();
+            let stem = old_path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("keypair");
+            let new_path = old_path.with_file_name(format!(
+                "{}-{}.json",
+                stem,
+                &new_signer.pubkey().to_string()[..8]
+            ));
+            let mut pair = RotationPair {
+                old_path: old_path.display().to_string(),
+                old_pubkey: old_signer.pubkey().to_string(),
+                new_path: new_path.display().to_string(),
+                new_pubkey: new_signer.pubkey().to_string(),
+                steps: vec![],
+            };
+
+            // Persist the new key before it can hold any funds
+            let saved = write_keypair_file(&new_signer, &new_path).is_ok();
+            pair.check(STEP_GENERATE, saved, None);
+            if saved {
+                let res = self.fund_and_open(old_signer, &new_signer, args.fund).await;
+                pair.check_result(STEP_OPEN, res);
+            }
+            state.pairs.push(pair);
+            state.write(&state_path);
+        }
+
+        state.print();
+        println!(
+            "\nOld and new keys will both mine from the folder. Run `ore wallets rotate finish` after the {}h overlap.",
+            args.overlap_hours
+        );
+        true
+    }
+
+    fn rotate_status(&self) {
+        match RotationState::read(&self.rotation_state_path()) {
+            Some(state) => {
+                let ends_at = state.started_at + state.overlap_hours as i64 * 3600;
+                let remaining = ends_at - chrono::Utc::now().timestamp();
+                state.print();
+                if remaining > 0 {
+                    println!("\nOverlap window ends in {}m", remaining / 60);
+                } else {
+                    println!("\nOverlap window has ended");
+                }
+            }
+            None => println!("No rotation in progress"),
+        }
+    }
+
+    async fn rotate_finish(&self, args: RotateArgs) {
+        let state_path = self.rotation_state_path();
+        let Some(mut state) = RotationState::read(&state_path) else {
+            println!("No rotation in progress");
+            return;
+        };
+        let ends_at = state.started_at + state.overlap_hours as i64 * 3600;
+        let remaining = ends_at - chrono::Utc::now().timestamp();
+        if remaining > 0 && !args.force {
+            println!(
+                "Overlap window ends in {}m. Use --force to finish now.",
+                remaining / 60
+            );
+            return;
+        }
+        let archive_dir = match args.archive_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(format!(
+                "{}-archive",
+                self.folder_path.as_ref().unwrap().trim_end_matches('/')
+            )),
+        };
+        std::fs::create_dir_all(&archive_dir).expect("Failed to create archive directory");
+
+        for i in 0..state.pairs.len() {
+            let pair = &mut state.pairs[i];
+            if !pair.is_done(STEP_OPEN) || pair.is_done(STEP_ARCHIVE) {
+                continue;
+            }
+            let (Ok(old_signer), Ok(new_signer)) = (
+                read_keypair_file(Path::new(&pair.old_path)),
+                read_keypair_file(Path::new(&pair.new_path)),
+            ) else {
+                println!(
+                    "{} Failed to read keypairs for {}",
+                    "ERROR".bold().red(),
+                    pair.old_pubkey
+                );
+                continue;
+            };
+
+            // Move the stake and any loose tokens to the new key, then close the old proof
+            if !pair.is_done(STEP_MIGRATE) {
+                let res = self
+                    .migrate_proof(
+                        &old_signer,
+                        &new_signer,
+                        args.jito_tip,
+                        args.jito_url.clone(),
+                        args.jito_uuid.as_deref(),
+                    )
+                    .await;
+                if !pair.check_result(STEP_MIGRATE, res) {
+                    continue;
+                }
+            }
+
+            // Sweep what is left of the old key, with the new key paying the fee
+            if !pair.is_done(STEP_SWEEP) {
+                let res = self.sweep_sol(&old_signer, &new_signer).await;
+                if !pair.check_result(STEP_SWEEP, res) {
+                    continue;
+                }
+            }
+
+            // Retire the old key
+            let file_name = Path::new(&pair.old_path).file_name().unwrap().to_owned();
+            let archived = std::fs::rename(&pair.old_path, archive_dir.join(file_name)).is_ok();
+            pair.check(STEP_ARCHIVE, archived, None);
+            state.write(&state_path);
+        }
+
+        state.print();
+        if state.pairs.iter().all(|pair| pair.is_done(STEP_ARCHIVE)) {
+            std::fs::remove_file(&state_path).ok();
+            println!(
+                "\nRotation complete. Old keys archived to {}",
+                archive_dir.display()
+            );
+        } else {
+            state.write(&state_path);
+            println!("\nSome steps failed. Re-run `ore wallets rotate finish` to retry them.");
+        }
+    }
+
+    /// Moves a proof to a new authority in one go, for keys that can't wait out an overlap.
+    /// The program has no way to change a proof's authority, so the old proof is claimed,
+    /// closed and its stake reopened under the new key.
+    pub async fn rotate_authority(&self, args: RotateAuthorityArgs) {
+        if args.all {
+            // The folder rotation, without the overlap
+            let rotate = |step| RotateArgs {
+                step,
+                fund: args.fund,
+                overlap_hours: 0,
+                archive_dir: args.archive_dir.clone(),
+                force: true,
+                jito_tip: args.jito_tip,
+                jito_url: args.jito_url.clone(),
+                jito_uuid: args.jito_uuid.clone(),
+            };
+            if self.rotate_start(rotate(RotateStep::Start)).await {
+                self.rotate_finish(rotate(RotateStep::Finish)).await;
+            }
+            return;
+        }
+
+        let old_signer = self.signer();
+        let (new_signer, new_path) = match args.new_authority {
+            Some(path) => (
+                read_keypair(&path).unwrap_or_else(|err| {
+                    abort(
+                        ExitCode::Config,
+                        format!("Failed to read new authority keypair {}", err),
+                    )
+                }),
+                PathBuf::from(path),
+            ),
+            None => {
+                let old_path = self.keypair_filepath.clone().unwrap();
+                if old_path.eq(STDIN) || old_path.starts_with("env:") {
+                    abort(
+                        ExitCode::Config,
+                        "Pass --new-authority, there is no keypair file to save the new one next to",
+                    );
+                }
+                let new_signer = Keypair::new();
+                let old_path = PathBuf::from(old_path);
+                let stem = old_path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or("keypair");
+                let new_path = old_path.with_file_name(format!(
+                    "{}-{}.json",
+                    stem,
+                    &new_signer.pubkey().to_string()[..8]
+                ));
+                write_keypair_file(&new_signer, &new_path).expect("Failed to save new keypair");
+                (new_signer, new_path)
+            }
+        };
+        if !ask_confirm(
+            format!(
+                "\nYou are about to move the proof of {} to {}, funded with {} SOL.\n\nAre you sure you want to continue? [Y/n]",
+                old_signer.pubkey(),
+                new_signer.pubkey(),
+                args.fund
+            )
+            .as_str(),
+        ) {
+            return;
+        }
+
+        let steps = [STEP_OPEN, STEP_MIGRATE, STEP_SWEEP];
+        for step in steps {
+            let res = match step {
+                STEP_OPEN => {
+                    self.fund_and_open(&old_signer, &new_signer, args.fund)
+                        .await
+                }
+                STEP_MIGRATE => {
+                    self.migrate_proof(
+                        &old_signer,
+                        &new_signer,
+                        args.jito_tip,
+                        args.jito_url.clone(),
+                        args.jito_uuid.as_deref(),
+                    )
+                    .await
+                }
+                _ => self.sweep_sol(&old_signer, &new_signer).await,
+            };
+            match res {
+                Ok(sig) => println!("{} {} {}", "[x]".green(), step, sig.to_string().dimmed()),
+                Err(err) => {
+                    println!("{} {}: {}", "ERROR".bold().red(), step, err);
+                    fail_with(&err);
+                    return;
+                }
+            }
+        }
+        println!(
+            "\nAuthority rotated. Point --keypair at {} from now on.",
+            new_path.display()
+        );
+    }
+
+    /// Funds the new key from the old one and opens its proof.
+    async fn fund_and_open(
+        &self,
+        old_signer: &Keypair,
+        new_signer: &Keypair,
+        fund: f64,
+    ) -> ClientResult<Signature> {
+        let ixs = [
+            system_instruction::transfer(
+                &old_signer.pubkey(),
+                &new_signer.pubkey(),
+                sol_to_lamports(fund),
+            ),
+            ore_api::instruction::open(
+                new_signer.pubkey(),
+                new_signer.pubkey(),
+                old_signer.pubkey(),
+            ),
+        ];
+        self.send_and_confirm_with_keys(&ixs, false, &[old_signer, new_signer])
+            .await
+    }
+
+    /// Moves the stake and any loose tokens to the new key, then closes the old proof and token
+    /// account, with the rent going to the new key. Every step goes in one Jito bundle, so
+    /// either all of them land or none do and no proof is left closed with its tokens behind.
+    async fn migrate_proof(
+        &self,
+        old_signer: &Keypair,
+        new_signer: &Keypair,
+        jito_tip: u64,
+        jito_url: String,
+        jito_uuid: Option<&str>,
+    ) -> ClientResult<Signature> {
+        let proof = get_proof_with_authority(&self.rpc_client(), old_signer.pubkey()).await;
+        let old_tokens = get_associated_token_address(&old_signer.pubkey(), &MINT_ADDRESS);
+        let new_tokens = get_associated_token_address(&new_signer.pubkey(), &MINT_ADDRESS);
+        let mut groups = vec![vec![create_associated_token_account_idempotent(
+            &old_signer.pubkey(),
+            &new_signer.pubkey(),
+            &MINT_ADDRESS,
+            &spl_token::id(),
+        )]];
+        if proof.balance.gt(&0) {
+            groups.push(vec![
+                ore_api::instruction::claim(old_signer.pubkey(), new_tokens, proof.balance),
+                ore_api::instruction::stake(new_signer.pubkey(), new_tokens, proof.balance),
+            ]);
+        }
+        if let Ok(Some(token_account)) = self.rpc_client().get_token_account(&old_tokens).await {
+            let amount = u64::from_str(&token_account.token_amount.amount).unwrap_or(0);
+            let mut ixs = vec![];
+            if amount.gt(&0) {
+                ixs.push(
+                    spl_token::instruction::transfer(
+                        &spl_token::id(),
+                        &old_tokens,
+                        &new_tokens,
+                        &old_signer.pubkey(),
+                        &[],
+                        amount,
+                    )
+                    .unwrap(),
+                );
+            }
+            ixs.push(
+                spl_token::instruction::close_account(
+                    &spl_token::id(),
+                    &old_tokens,
+                    &new_signer.pubkey(),
+                    &old_signer.pubkey(),
+                    &[],
+                )
+                .unwrap(),
+            );
+            groups.push(ixs);
+        }
+        groups.push(vec![ore_api::instruction::close(old_signer.pubkey())]);
+        self.send_and_confirm_bundle(
+            &groups,
+            &[new_signer],
+            old_signer,
+            false,
+            jito_tip,
+            jito_url,
+            jito_uuid,
+            None,
+        )
+        .await
+    }
+
+    /// Sweeps what is left of the old key, with the new key paying the fee.
+    async fn sweep_sol(
+        &self,
+        old_signer: &Keypair,
+        new_signer: &Keypair,
+    ) -> ClientResult<Signature> {
+        let balance = self
+            .rpc_client()
+            .get_balance(&old_signer.pubkey())
+            .await
+            .unwrap_or(0);
+        let ix = system_instruction::transfer(&old_signer.pubkey(), &new_signer.pubkey(), balance);
+        self.send_and_confirm_with_keys(&[ix], false, &[new_signer, old_signer])
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_client::client_error::{ClientError, ClientErrorKind};
+
+    use super::*;
+
+    fn pair() -> RotationPair {
+        RotationPair {
+            old_path: "old.json".to_string(),
+            old_pubkey: Keypair::new().pubkey().to_string(),
+            new_path: "new.json".to_string(),
+            new_pubkey: Keypair::new().pubkey().to_string(),
+            steps: vec![],
+        }
+    }
+
+    #[test]
+    fn rechecking_a_step_replaces_it() {
+        let mut pair = pair();
+        assert!(!pair.is_done(STEP_OPEN));
+        pair.check(STEP_OPEN, false, None);
+        assert!(!pair.is_done(STEP_OPEN));
+        pair.check(STEP_OPEN, true, Some("sig".to_string()));
+        assert!(pair.is_done(STEP_OPEN));
+        assert!(!pair.is_done(STEP_MIGRATE));
+        assert_eq!(pair.steps.len(), 1);
+    }
+
+    #[test]
+    fn records_the_outcome_of_a_transaction() {
+        let mut pair = pair();
+        let signature = Signature::new_unique();
+        assert!(pair.check_result(STEP_MIGRATE, Ok(signature)));
+        assert!(pair.is_done(STEP_MIGRATE));
+        assert_eq!(pair.steps[0].signature, Some(signature.to_string()));
+
+        let err = ClientError::from(ClientErrorKind::Custom("dropped".to_string()));
+        assert!(!pair.check_result(STEP_SWEEP, Err(err)));
+        assert!(!pair.is_done(STEP_SWEEP));
+        assert!(pair.steps.iter().any(|step| step.name.eq(STEP_SWEEP)));
+    }
+
+    #[test]
+    fn state_survives_a_restart() {
+        let path = std::env::temp_dir().join(format!(
+            "ore-rotation-{}-{}",
+            std::process::id(),
+            ROTATION_STATE_FILE
+        ));
+        let mut pair = pair();
+        pair.check(STEP_GENERATE, true, None);
+        let new_pubkey = pair.new_pubkey.clone();
+        RotationState {
+            started_at: 1_700_000_000,
+            overlap_hours: 24,
+            pairs: vec![pair],
+        }
+        .write(&path);
+
+        let state = RotationState::read(&path).unwrap();
+        assert_eq!(state.overlap_hours, 24);
+        assert_eq!(state.pairs[0].new_pubkey, new_pubkey);
+        assert!(state.pairs[0].is_done(STEP_GENERATE));
+
+        std::fs::write(&path, "{").unwrap();
+        assert!(RotationState::read(&path).is_none());
+        std::fs::remove_file(&path).unwrap();
+        assert!(RotationState::read(&path).is_none());
+    }
+}
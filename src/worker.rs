@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use colored::*;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+use crate::{args::WorkerArgs, coordinator::PoolMessage, mine::SolverOptions, Miner};
+
+/// How long to wait before reconnecting to a coordinator that went away.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+impl Miner {
+    /// Hashes challenges for a coordinator. Holds no keys and makes no RPC calls.
+    pub async fn worker(&self, args: WorkerArgs) {
+        self.check_num_cores(args.threads);
+        loop {
+            match work_for(&args).await {
+                Ok(()) => println!(
+                    "{} Coordinator {} closed the connection",
+                    "WARNING".bold().yellow(),
+                    args.coordinator
+                ),
+                Err(err) => println!(
+                    "{} Lost coordinator {}: {}",
+                    "ERROR".bold().red(),
+                    args.coordinator,
+                    err
+                ),
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+}
+
+/// Serves one connection to the coordinator until it closes.
+async fn work_for(args: &WorkerArgs) -> std::io::Result<()> {
+    let stream = TcpStream::connect(&args.coordinator).await?;
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    send(
+        &mut writer,
+        &PoolMessage::Hello {
+            token: args.token.clone(),
+            threads: args.threads,
+        },
+    )
+    .await?;
+    println!("Connected to coordinator {}", args.coordinator);
+
+    while let Some(line) = lines.next_line().await? {
+        let Ok(PoolMessage::Work {
+            id,
+            challenge,
+            first_nonce,
+            cutoff,
+            min_difficulty,
+            target_difficulty,
+        }) = serde_json::from_str::<PoolMessage>(&line)
+        else {
+            continue;
+        };
+        let solution = Miner::find_hash_par(
+            challenge,
+            first_nonce,
+            cutoff,
+            args.threads,
+            min_difficulty,
+            target_difficulty,
+            SolverOptions::default(),
+            None,
+        )
+        .await;
+        send(
+            &mut writer,
+            &PoolMessage::Solution {
+                id,
+                digest: solution.d,
+                nonce: solution.n,
+            },
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+async fn send(
+    writer: &mut tokio::net::tcp::OwnedWriteHalf,
+    message: &PoolMessage,
+) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(message).unwrap();
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await
+}